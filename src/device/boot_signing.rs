@@ -0,0 +1,782 @@
+//! RP2350 secure boot signing: boot keys, their OTP hash and sealed UF2 images.
+//!
+//! This is the `picotool seal --sign` step done in-process. A locked RP2350 only boots an image
+//! whose IMAGE_DEF block carries a secp256k1 signature from a key whose SHA-256 hash is burned into
+//! one of the BOOTKEY OTP slots. Sealing appends a new IMAGE_DEF block (image type, load map, hash
+//! definition and signature) after the firmware and links it into the image's block loop, so the
+//! bootrom picks it up as the last IMAGE_DEF of the loop.
+//!
+//! The metadata layout follows `picobin.h` from the pico-sdk and the RP2350 datasheet, section 5.9.
+
+use crate::device::{
+    error::PFError,
+    types::{BootKeyInfo, SealedImage},
+};
+use k256::{
+    SecretKey,
+    ecdsa::{
+        Signature, SigningKey, VerifyingKey,
+        signature::hazmat::{PrehashSigner, PrehashVerifier},
+    },
+    elliptic_curve::sec1::ToEncodedPoint,
+    pkcs8::{DecodePrivateKey, LineEnding},
+};
+use ring::{
+    digest,
+    rand::{SecureRandom, SystemRandom},
+};
+use std::io::Write;
+use std::path::Path;
+use x509_parser::pem::Pem;
+
+// --- UF2 container ---
+
+const UF2_MAGIC_START0: u32 = 0x0A32_4655;
+const UF2_MAGIC_START1: u32 = 0x9E5D_5157;
+const UF2_MAGIC_END: u32 = 0x0AB1_6F30;
+const UF2_FLAG_FAMILY_ID_PRESENT: u32 = 0x0000_2000;
+const UF2_BLOCK_SIZE: usize = 512;
+const UF2_PAYLOAD_SIZE: usize = 256;
+
+const RP2350_ARM_S_FAMILY_ID: u32 = 0xE48B_FF59;
+const RP2350_RISCV_FAMILY_ID: u32 = 0xE48B_FF5A;
+const RP2350_ARM_NS_FAMILY_ID: u32 = 0xE48B_FF5B;
+
+const FLASH_START: u32 = 0x1000_0000;
+/// Largest flash device the RP2350 maps on one chip select, and so the largest image to seal.
+const MAX_IMAGE_SIZE: u32 = 16 * 1024 * 1024;
+/// The bootrom only searches the first 4 KiB of an image for its block loop.
+const BLOCK_SEARCH_WINDOW: usize = 4096;
+/// Upper bound for walking a block loop, far above what any real image uses.
+const MAX_LOOP_BLOCKS: usize = 16;
+
+// --- picobin metadata blocks ---
+
+const PICOBIN_BLOCK_MARKER_START: u32 = 0xFFFF_DED3;
+const PICOBIN_BLOCK_MARKER_END: u32 = 0xAB12_3579;
+
+const PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE: u8 = 0x42;
+const PICOBIN_BLOCK_ITEM_LOAD_MAP: u8 = 0x06;
+const PICOBIN_BLOCK_ITEM_1BS_HASH_DEF: u8 = 0x47;
+const PICOBIN_BLOCK_ITEM_SIGNATURE: u8 = 0x09;
+const PICOBIN_BLOCK_ITEM_2BS_LAST: u8 = 0xFF;
+/// Set in the item type byte when the size field is two bytes wide.
+const PICOBIN_BLOCK_ITEM_SIZE_2BS: u8 = 0x80;
+
+const PICOBIN_IMAGE_TYPE_EXE: u16 = 0x0001;
+const PICOBIN_IMAGE_TYPE_EXE_SECURITY_S: u16 = 0x0020;
+const PICOBIN_IMAGE_TYPE_EXE_CPU_RISCV: u16 = 0x0100;
+const PICOBIN_IMAGE_TYPE_EXE_CHIP_RP2350: u16 = 0x1000;
+
+/// LOAD_MAP entry count, in the last header byte.
+const PICOBIN_LOAD_MAP_ONE_ENTRY: u16 = 0x0100;
+
+const PICOBIN_HASH_SHA256: u8 = 0x01;
+const PICOBIN_SIGNATURE_SECP256K1: u8 = 0x01;
+
+/// Public key (x, y) and signature (r, s), 64 bytes each.
+const SIGNATURE_ITEM_WORDS: u8 = 1 + 16 + 16;
+
+// --- Boot keys ---
+
+fn info(path: &Path, secret: &SecretKey) -> BootKeyInfo {
+    let public = public_key_bytes(secret);
+    BootKeyInfo {
+        path: path.display().to_string(),
+        public_key: hex::encode_upper(public),
+        otp_hash: hex::encode_upper(digest::digest(&digest::SHA256, &public)),
+    }
+}
+
+/// x || y of the public key, the form both the SIGNATURE item and the OTP hash use.
+fn public_key_bytes(secret: &SecretKey) -> [u8; 64] {
+    let point = secret.public_key().to_encoded_point(false);
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&point.as_bytes()[1..]);
+    out
+}
+
+fn read_key(path: &Path) -> Result<SecretKey, PFError> {
+    let data = std::fs::read(path)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    // `openssl ecparam -genkey` writes an EC PARAMETERS block before the key itself.
+    for pem in Pem::iter_from_buffer(&data).filter_map(Result::ok) {
+        let key = match pem.label.as_str() {
+            "EC PRIVATE KEY" => SecretKey::from_sec1_der(&pem.contents).ok(),
+            "PRIVATE KEY" => SecretKey::from_pkcs8_der(&pem.contents).ok(),
+            _ => continue,
+        };
+        return key.ok_or_else(|| {
+            PFError::Io(format!("{} is not a secp256k1 private key", path.display()))
+        });
+    }
+    SecretKey::from_sec1_der(&data)
+        .map_err(|_| PFError::Io(format!("No private key found in {}", path.display())))
+}
+
+/// Loads a secp256k1 boot key (SEC1 or PKCS#8, PEM or DER).
+pub(crate) fn load_key(path: &Path) -> Result<BootKeyInfo, PFError> {
+    Ok(info(path, &read_key(path)?))
+}
+
+/// Generates a new secp256k1 boot key and writes it as a SEC1 PEM file readable only by the owner.
+pub(crate) fn generate_key(path: &Path) -> Result<BootKeyInfo, PFError> {
+    let rng = SystemRandom::new();
+    let secret = loop {
+        let mut bytes = [0u8; 32];
+        rng.fill(&mut bytes)
+            .map_err(|_| PFError::Io("Failed to generate random bytes".into()))?;
+        // Retry the rare scalar that is zero or not below the curve order.
+        if let Ok(secret) = SecretKey::from_slice(&bytes) {
+            break secret;
+        }
+    };
+
+    let pem = secret
+        .to_sec1_pem(LineEnding::LF)
+        .map_err(|e| PFError::Io(format!("Failed to encode key: {}", e)))?;
+
+    // Created with its final mode and never over an existing file, which may be another key.
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(pem.as_bytes()))
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => PFError::Io(format!(
+                "{} already exists. Boot keys are never overwritten, choose a new file name",
+                path.display()
+            )),
+            _ => PFError::Io(format!("Failed to write {}: {}", path.display(), e)),
+        })?;
+
+    log::info!("Generated boot key {}", path.display());
+    Ok(info(path, &secret))
+}
+
+// --- UF2 ---
+
+struct Uf2Block {
+    flags: u32,
+    target: u32,
+    payload: Vec<u8>,
+    block_no: u32,
+    num_blocks: u32,
+    family: u32,
+}
+
+fn word(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// The word at `offset`, or `None` when it runs past the end of `data`.
+fn word_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset.checked_add(4)?).map(|w| word(w, 0))
+}
+
+fn parse_uf2(data: &[u8]) -> Result<Vec<Uf2Block>, PFError> {
+    if data.is_empty() || !data.len().is_multiple_of(UF2_BLOCK_SIZE) {
+        return Err(PFError::Io("Not a UF2 file".into()));
+    }
+    data.chunks(UF2_BLOCK_SIZE)
+        .map(|b| {
+            if word(b, 0) != UF2_MAGIC_START0
+                || word(b, 4) != UF2_MAGIC_START1
+                || word(b, 508) != UF2_MAGIC_END
+            {
+                return Err(PFError::Io("Corrupt UF2 block".into()));
+            }
+            let size = word(b, 16) as usize;
+            if size > 476 {
+                return Err(PFError::Io("Corrupt UF2 block".into()));
+            }
+            Ok(Uf2Block {
+                flags: word(b, 8),
+                target: word(b, 12),
+                payload: b[32..32 + size].to_vec(),
+                block_no: word(b, 20),
+                num_blocks: word(b, 24),
+                family: word(b, 28),
+            })
+        })
+        .collect()
+}
+
+fn write_uf2_block(out: &mut Vec<u8>, block: &Uf2Block) {
+    for value in [
+        UF2_MAGIC_START0,
+        UF2_MAGIC_START1,
+        block.flags,
+        block.target,
+        block.payload.len() as u32,
+        block.block_no,
+        block.num_blocks,
+        block.family,
+    ] {
+        out.extend(value.to_le_bytes());
+    }
+    out.extend(&block.payload);
+    out.resize(out.len() + 476 - block.payload.len(), 0);
+    out.extend(UF2_MAGIC_END.to_le_bytes());
+}
+
+fn is_rp2350_image(family: u32) -> bool {
+    matches!(
+        family,
+        RP2350_ARM_S_FAMILY_ID | RP2350_RISCV_FAMILY_ID | RP2350_ARM_NS_FAMILY_ID
+    )
+}
+
+// --- Metadata blocks ---
+
+fn item_header(item_type: u8, size_words: u8, extra: u16) -> u32 {
+    item_type as u32 | (size_words as u32) << 8 | (extra as u32) << 16
+}
+
+/// Offset of the first metadata block in `image`, if it has one.
+fn find_block(image: &[u8]) -> Option<usize> {
+    let window = image.len().min(BLOCK_SEARCH_WINDOW);
+    (0..window.saturating_sub(3))
+        .step_by(4)
+        .find(|&offset| word(image, offset) == PICOBIN_BLOCK_MARKER_START)
+}
+
+/// A metadata block found in an image.
+struct Block {
+    start: usize,
+    /// Item type and offset of each item before the LAST item.
+    items: Vec<(u8, usize)>,
+    /// Offset of the word holding the relative link to the next block.
+    link: usize,
+}
+
+impl Block {
+    fn item(&self, item_type: u8) -> Option<usize> {
+        self.items
+            .iter()
+            .find(|(t, _)| *t == item_type)
+            .map(|&(_, offset)| offset)
+    }
+
+    /// Start of the next block in the loop.
+    fn next(&self, image: &[u8]) -> Option<usize> {
+        usize::try_from(self.start as i64 + word(image, self.link) as i32 as i64).ok()
+    }
+}
+
+fn parse_block(image: &[u8], start: usize) -> Result<Block, PFError> {
+    let mut items = Vec::new();
+    let mut offset = start + 4;
+    while offset + 4 <= image.len() {
+        let header = word(image, offset);
+        let item_type = header as u8;
+        if item_type == PICOBIN_BLOCK_ITEM_2BS_LAST {
+            let link = offset + 4;
+            if link + 8 > image.len() || word(image, link + 4) != PICOBIN_BLOCK_MARKER_END {
+                break;
+            }
+            return Ok(Block { start, items, link });
+        }
+        let size = if item_type & PICOBIN_BLOCK_ITEM_SIZE_2BS != 0 {
+            (header >> 8) as u16 as usize
+        } else {
+            (header >> 8) as u8 as usize
+        };
+        if size == 0 {
+            break;
+        }
+        items.push((item_type, offset));
+        offset += size * 4;
+    }
+    Err(PFError::Io(
+        "The image contains a malformed metadata block".into(),
+    ))
+}
+
+/// The blocks of the loop starting at `first`, in link order. The bootrom gives up on a loop that
+/// does not lead back to its first block.
+fn block_loop(image: &[u8], first: usize) -> Result<Vec<Block>, PFError> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut start = first;
+    while blocks.len() < MAX_LOOP_BLOCKS {
+        let block = parse_block(image, start)?;
+        let next = block.next(image);
+        blocks.push(block);
+        match next {
+            Some(next) if next == first => return Ok(blocks),
+            Some(next)
+                if next + 4 <= image.len() && word(image, next) == PICOBIN_BLOCK_MARKER_START =>
+            {
+                start = next;
+            }
+            _ => break,
+        }
+    }
+    Err(PFError::Io(
+        "The image's metadata blocks do not form a closed loop".into(),
+    ))
+}
+
+/// IMAGE_TYPE flags of the existing block, or a secure ARM executable for the RP2350.
+fn image_type(image: &[u8], block: Option<usize>, family: u32) -> u16 {
+    let existing = block.and_then(|start| {
+        let header = word_at(image, start + 4)?;
+        (header as u8 == PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE).then_some((header >> 16) as u16)
+    });
+    existing.unwrap_or_else(|| {
+        let cpu = if family == RP2350_RISCV_FAMILY_ID {
+            PICOBIN_IMAGE_TYPE_EXE_CPU_RISCV
+        } else {
+            0
+        };
+        PICOBIN_IMAGE_TYPE_EXE
+            | PICOBIN_IMAGE_TYPE_EXE_SECURITY_S
+            | cpu
+            | PICOBIN_IMAGE_TYPE_EXE_CHIP_RP2350
+    })
+}
+
+/// Signs `image` (loaded at `base`) and appends the signed IMAGE_DEF block to it.
+fn seal_image(
+    image: &mut Vec<u8>,
+    base: u32,
+    family: u32,
+    secret: &SecretKey,
+) -> Result<[u8; 32], PFError> {
+    let first_block = find_block(image);
+    let image_type = image_type(image, first_block, family);
+
+    image.resize(image.len().next_multiple_of(4), 0);
+    let new_block = image.len();
+
+    // Insert the new block at the end of the loop: the block that led back to the first one now
+    // links to the new block, and the new block links back to the first.
+    let back_link = match first_block {
+        Some(start) => {
+            let loop_blocks = block_loop(image, start)?;
+            let last = loop_blocks
+                .last()
+                .expect("a block loop has at least one block");
+            let link = last.link;
+            let from = last.start;
+            image[link..link + 4].copy_from_slice(&((new_block - from) as i32).to_le_bytes());
+            start as i32 - new_block as i32
+        }
+        None => 0,
+    };
+
+    let mut block = vec![
+        PICOBIN_BLOCK_MARKER_START,
+        item_header(PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE, 1, image_type),
+        // One relative entry: the whole image, stored where it runs (XIP).
+        item_header(PICOBIN_BLOCK_ITEM_LOAD_MAP, 4, PICOBIN_LOAD_MAP_ONE_ENTRY),
+        (-(new_block as i32)) as u32,
+        base,
+        new_block as u32,
+    ];
+    let hash_def = block.len();
+    block.extend([
+        item_header(
+            PICOBIN_BLOCK_ITEM_1BS_HASH_DEF,
+            2,
+            (PICOBIN_HASH_SHA256 as u16) << 8,
+        ),
+        0,
+    ]);
+    // The hash covers the block from its start marker up to and including the HASH_DEF item.
+    block[hash_def + 1] = (hash_def + 2) as u32;
+
+    let mut hashed = image[..new_block].to_vec();
+    for w in &block {
+        hashed.extend(w.to_le_bytes());
+    }
+    let hash: [u8; 32] = digest::digest(&digest::SHA256, &hashed)
+        .as_ref()
+        .try_into()
+        .map_err(|_| PFError::Io("Unexpected digest length".into()))?;
+
+    let signature: Signature = SigningKey::from(secret)
+        .sign_prehash(&hash)
+        .map_err(|e| PFError::Io(format!("Signing failed: {}", e)))?;
+
+    let mut signature_item = public_key_bytes(secret).to_vec();
+    signature_item.extend(signature.to_bytes().as_slice());
+    block.push(item_header(
+        PICOBIN_BLOCK_ITEM_SIGNATURE,
+        SIGNATURE_ITEM_WORDS,
+        (PICOBIN_SIGNATURE_SECP256K1 as u16) << 8,
+    ));
+    block.extend(signature_item.chunks(4).map(|c| word(c, 0)));
+
+    let item_words = (block.len() - 1) as u32;
+    block.extend([
+        PICOBIN_BLOCK_ITEM_2BS_LAST as u32 | item_words << 8,
+        back_link as u32,
+        PICOBIN_BLOCK_MARKER_END,
+    ]);
+
+    for w in block {
+        image.extend(w.to_le_bytes());
+    }
+    Ok(hash)
+}
+
+/// Reads a sealed image back the way the bootrom finds it: walks the block loop from the first
+/// block, takes the last IMAGE_DEF and recomputes its hash from the LOAD_MAP and HASH_DEF items,
+/// then checks the signature and that it was made with `public_key`. Returns the hash.
+///
+/// This catches a block that is not linked in or does not describe the image, but it follows the
+/// same reading of the datasheet as `seal_image`, so it is no substitute for comparing with
+/// `picotool seal` output.
+fn check_sealed(image: &[u8], public_key: &[u8; 64]) -> Result<[u8; 32], PFError> {
+    let fail = |what: &str| PFError::Io(format!("Sealed image check failed: {}", what));
+
+    let first = find_block(image).ok_or_else(|| fail("no metadata block"))?;
+    let blocks = block_loop(image, first)?;
+    let block = blocks
+        .iter()
+        .rev()
+        .find(|b| b.item(PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE).is_some())
+        .ok_or_else(|| fail("no IMAGE_DEF in the block loop"))?;
+
+    let load_map = block
+        .item(PICOBIN_BLOCK_ITEM_LOAD_MAP)
+        .ok_or_else(|| fail("no LOAD_MAP"))?;
+    let header = word_at(image, load_map).ok_or_else(|| fail("truncated LOAD_MAP"))?;
+    if header >> 31 != 0 {
+        return Err(fail("absolute LOAD_MAP"));
+    }
+    let mut hashed = Vec::new();
+    for entry in 0..((header >> 24) & 0x7F) as usize {
+        let at = load_map + 4 + entry * 12;
+        let (Some(offset), Some(size)) = (word_at(image, at), word_at(image, at + 8)) else {
+            return Err(fail("truncated LOAD_MAP"));
+        };
+        let storage = block.start as i64 + offset as i32 as i64;
+        let size = size as usize;
+        let data = usize::try_from(storage)
+            .ok()
+            .and_then(|from| image.get(from..from.checked_add(size)?))
+            .ok_or_else(|| fail("LOAD_MAP entry outside the image"))?;
+        hashed.extend(data);
+    }
+
+    let hash_def = block
+        .item(PICOBIN_BLOCK_ITEM_1BS_HASH_DEF)
+        .ok_or_else(|| fail("no HASH_DEF"))?;
+    let (Some(hash_header), Some(hash_words)) =
+        (word_at(image, hash_def), word_at(image, hash_def + 4))
+    else {
+        return Err(fail("truncated HASH_DEF"));
+    };
+    if (hash_header >> 24) as u8 != PICOBIN_HASH_SHA256 {
+        return Err(fail("HASH_DEF is not SHA-256"));
+    }
+    let block_words = (hash_words & 0xFFFF) as usize;
+    let block_end = block.start + block_words * 4;
+    if block_end < hash_def + 8 || block_end > image.len() {
+        return Err(fail("HASH_DEF does not cover itself"));
+    }
+    hashed.extend(&image[block.start..block_end]);
+    let hash: [u8; 32] = digest::digest(&digest::SHA256, &hashed)
+        .as_ref()
+        .try_into()
+        .map_err(|_| fail("unexpected digest length"))?;
+
+    let signature_item = block
+        .item(PICOBIN_BLOCK_ITEM_SIGNATURE)
+        .ok_or_else(|| fail("no SIGNATURE"))?;
+    let key = image
+        .get(signature_item + 4..signature_item + 68)
+        .ok_or_else(|| fail("truncated SIGNATURE"))?;
+    let signature = image
+        .get(signature_item + 68..signature_item + 132)
+        .ok_or_else(|| fail("truncated SIGNATURE"))?;
+    if key != public_key {
+        return Err(fail("signed with a different key"));
+    }
+    let mut point = vec![0x04];
+    point.extend(key);
+    let verifier = VerifyingKey::from_sec1_bytes(&point).map_err(|_| fail("invalid public key"))?;
+    let signature = Signature::from_slice(signature).map_err(|_| fail("invalid signature"))?;
+    verifier
+        .verify_prehash(&hash, &signature)
+        .map_err(|_| fail("signature does not verify"))?;
+    Ok(hash)
+}
+
+/// The payloads of `blocks` laid out from their lowest target address.
+fn flatten(blocks: &[Uf2Block]) -> Result<(u32, Vec<u8>), PFError> {
+    let base = blocks.iter().map(|b| b.target).min().unwrap_or(FLASH_START);
+    if base < FLASH_START {
+        return Err(PFError::Io(
+            "Only images that run from flash can be sealed".into(),
+        ));
+    }
+    let too_large = || {
+        PFError::Io(format!(
+            "The image does not fit in {} MiB of flash",
+            MAX_IMAGE_SIZE / (1024 * 1024)
+        ))
+    };
+    let mut end = base;
+    for block in blocks {
+        let block_end = u32::try_from(block.payload.len())
+            .ok()
+            .and_then(|len| block.target.checked_add(len))
+            .ok_or_else(too_large)?;
+        end = end.max(block_end);
+    }
+    if end - base > MAX_IMAGE_SIZE {
+        return Err(too_large());
+    }
+    let mut image = vec![0u8; (end - base) as usize];
+    for block in blocks {
+        let offset = (block.target - base) as usize;
+        image[offset..offset + block.payload.len()].copy_from_slice(&block.payload);
+    }
+    Ok((base, image))
+}
+
+/// Signs the RP2350 firmware in the UF2 file `input` with the boot key at `key_path` and writes the
+/// sealed image to `output`.
+pub(crate) fn seal_uf2(
+    key_path: &Path,
+    input: &Path,
+    output: &Path,
+) -> Result<SealedImage, PFError> {
+    let secret = read_key(key_path)?;
+    let data = std::fs::read(input)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", input.display(), e)))?;
+    let blocks = parse_uf2(&data)?;
+
+    let family = blocks
+        .iter()
+        .find(|b| b.flags & UF2_FLAG_FAMILY_ID_PRESENT != 0 && is_rp2350_image(b.family))
+        .map(|b| b.family)
+        .ok_or_else(|| PFError::Io("The UF2 file has no RP2350 image".into()))?;
+    let (ours, others): (Vec<_>, Vec<_>) = blocks
+        .into_iter()
+        .partition(|b| b.flags & UF2_FLAG_FAMILY_ID_PRESENT != 0 && b.family == family);
+
+    let (base, mut image) = flatten(&ours)?;
+
+    let hash = seal_image(&mut image, base, family, &secret)?;
+
+    // Blocks for other families (such as the RP2350-E10 workaround block) are kept as they are.
+    let mut out = Vec::new();
+    for block in &others {
+        write_uf2_block(&mut out, block);
+    }
+    let chunks = image.chunks(UF2_PAYLOAD_SIZE);
+    let num_blocks = chunks.len() as u32;
+    for (i, chunk) in chunks.enumerate() {
+        write_uf2_block(
+            &mut out,
+            &Uf2Block {
+                flags: UF2_FLAG_FAMILY_ID_PRESENT,
+                target: base + (i * UF2_PAYLOAD_SIZE) as u32,
+                payload: chunk.to_vec(),
+                block_no: i as u32,
+                num_blocks,
+                family,
+            },
+        );
+    }
+
+    // Check what is about to be written, not the buffer it was built from.
+    let written: Vec<Uf2Block> = parse_uf2(&out)?
+        .into_iter()
+        .filter(|b| b.flags & UF2_FLAG_FAMILY_ID_PRESENT != 0 && b.family == family)
+        .collect();
+    let (_, written) = flatten(&written)?;
+    if check_sealed(&written, &public_key_bytes(&secret))? != hash {
+        return Err(PFError::Io(
+            "Sealed image check failed: the hash does not match the signed one".into(),
+        ));
+    }
+
+    std::fs::write(output, out)
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", output.display(), e)))?;
+
+    log::info!(
+        "Sealed {} ({} bytes) into {}",
+        input.display(),
+        image.len(),
+        output.display()
+    );
+
+    Ok(SealedImage {
+        output: output.display().to_string(),
+        image_size: image.len() as u32,
+        image_hash: hex::encode_upper(hash),
+        boot_key: info(key_path, &secret),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fixed test key. Its hash must never be burned into a device.
+    fn test_key() -> SecretKey {
+        SecretKey::from_slice(&[0x11; 32]).unwrap()
+    }
+
+    /// A small ARM image whose IMAGE_DEF only carries IMAGE_TYPE, the way the pico-sdk links an
+    /// unsigned binary: one block that links to itself.
+    fn unsigned_image() -> Vec<u8> {
+        let mut image = vec![0u8; 0x100];
+        for w in [
+            PICOBIN_BLOCK_MARKER_START,
+            item_header(
+                PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE,
+                1,
+                PICOBIN_IMAGE_TYPE_EXE
+                    | PICOBIN_IMAGE_TYPE_EXE_SECURITY_S
+                    | PICOBIN_IMAGE_TYPE_EXE_CHIP_RP2350,
+            ),
+            PICOBIN_BLOCK_ITEM_2BS_LAST as u32 | 1 << 8,
+            0,
+            PICOBIN_BLOCK_MARKER_END,
+        ] {
+            image.extend(w.to_le_bytes());
+        }
+        image.resize(0x400, 0xA5);
+        image
+    }
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/boot_signing")
+    }
+
+    /// The flattened image of the RP2350 ARM blocks in a UF2 file.
+    fn arm_image(uf2: &[u8]) -> Vec<u8> {
+        let blocks: Vec<Uf2Block> = parse_uf2(uf2)
+            .unwrap()
+            .into_iter()
+            .filter(|b| b.family == RP2350_ARM_S_FAMILY_ID)
+            .collect();
+        flatten(&blocks).unwrap().1
+    }
+
+    /// Offset of r || s in the last IMAGE_DEF of the loop.
+    fn signature_offset(image: &[u8]) -> usize {
+        let blocks = block_loop(image, find_block(image).unwrap()).unwrap();
+        let block = blocks
+            .iter()
+            .rev()
+            .find(|b| b.item(PICOBIN_BLOCK_ITEM_1BS_IMAGE_TYPE).is_some())
+            .unwrap();
+        block.item(PICOBIN_BLOCK_ITEM_SIGNATURE).unwrap() + 68
+    }
+
+    #[test]
+    fn sealed_image_passes_its_own_check() {
+        let secret = test_key();
+        let mut image = unsigned_image();
+        let hash = seal_image(&mut image, FLASH_START, RP2350_ARM_S_FAMILY_ID, &secret).unwrap();
+
+        assert_eq!(
+            check_sealed(&image, &public_key_bytes(&secret)).unwrap(),
+            hash
+        );
+        assert_eq!(block_loop(&image, 0x100).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn resealing_uses_the_new_block() {
+        let first = test_key();
+        let second = SecretKey::from_slice(&[0x22; 32]).unwrap();
+        let mut image = unsigned_image();
+        seal_image(&mut image, FLASH_START, RP2350_ARM_S_FAMILY_ID, &first).unwrap();
+        let hash = seal_image(&mut image, FLASH_START, RP2350_ARM_S_FAMILY_ID, &second).unwrap();
+
+        assert_eq!(
+            check_sealed(&image, &public_key_bytes(&second)).unwrap(),
+            hash
+        );
+        assert!(check_sealed(&image, &public_key_bytes(&first)).is_err());
+    }
+
+    #[test]
+    fn image_without_a_block_gets_a_closed_loop() {
+        let secret = test_key();
+        let mut image = vec![0u8; 0x200];
+        let hash = seal_image(&mut image, FLASH_START, RP2350_ARM_S_FAMILY_ID, &secret).unwrap();
+
+        assert_eq!(
+            check_sealed(&image, &public_key_bytes(&secret)).unwrap(),
+            hash
+        );
+    }
+
+    #[test]
+    fn truncated_block_is_not_read_past_the_end() {
+        let image = PICOBIN_BLOCK_MARKER_START.to_le_bytes().to_vec();
+        assert_eq!(
+            image_type(&image, Some(0), RP2350_ARM_S_FAMILY_ID),
+            image_type(&image, None, RP2350_ARM_S_FAMILY_ID)
+        );
+        assert!(check_sealed(&image, &[0; 64]).is_err());
+    }
+
+    #[test]
+    fn flatten_refuses_images_larger_than_flash() {
+        let block = |target: u32| Uf2Block {
+            flags: UF2_FLAG_FAMILY_ID_PRESENT,
+            target,
+            payload: vec![0; UF2_PAYLOAD_SIZE],
+            block_no: 0,
+            num_blocks: 2,
+            family: RP2350_ARM_S_FAMILY_ID,
+        };
+        assert!(flatten(&[block(FLASH_START), block(FLASH_START + MAX_IMAGE_SIZE)]).is_err());
+        assert!(flatten(&[block(FLASH_START), block(u32::MAX - 16)]).is_err());
+        assert!(flatten(&[block(FLASH_START), block(FLASH_START + 0x1000)]).is_ok());
+    }
+
+    /// Known-answer test against `picotool seal --sign`; see tests/fixtures/boot_signing/README.md
+    /// for how the fixtures are made. The images must match byte for byte except the signature
+    /// value, and picotool's signature must verify under our hash of its image.
+    #[test]
+    #[ignore = "needs the picotool fixtures described in tests/fixtures/boot_signing/README.md"]
+    fn matches_picotool_seal() {
+        let dir = fixtures();
+        let output = std::env::temp_dir().join(format!("picoforge-kat-{}.uf2", std::process::id()));
+        seal_uf2(
+            &dir.join("boot_key.pem"),
+            &dir.join("unsigned.uf2"),
+            &output,
+        )
+        .unwrap();
+        let ours = std::fs::read(&output).unwrap();
+        let _ = std::fs::remove_file(&output);
+        let theirs = std::fs::read(dir.join("sealed.uf2")).unwrap();
+
+        let secret = read_key(&dir.join("boot_key.pem")).unwrap();
+        let mut ours = arm_image(&ours);
+        let mut theirs = arm_image(&theirs);
+        check_sealed(&theirs, &public_key_bytes(&secret)).unwrap();
+
+        // ECDSA signatures need not be reproducible, so r || s is left out of the comparison.
+        for image in [&mut ours, &mut theirs] {
+            let at = signature_offset(image);
+            image[at..at + 64].fill(0);
+        }
+        assert_eq!(ours.len(), theirs.len());
+        assert!(ours == theirs, "sealed image differs from picotool's");
+    }
+}
@@ -0,0 +1,311 @@
+//! Which firmware supports which operation.
+//!
+//! `RELEASES` maps firmware versions to the rescue and vendor features PicoForge has verified them
+//! with; a feature is available from the first release that lists it. `MATRIX` lists what GetInfo
+//! must report for each feature: an option, an extension, a protocol version or a vendor config
+//! command. `device::io` checks the latter before talking to the device, and the views use the same
+//! answer to disable actions with the reason instead of letting them fail with a raw status word.
+//!
+//! A release older than the first one a feature is verified with is not refused, as nothing shows
+//! it lacks the feature; a warning is logged and the device answers for itself. Only secure boot,
+//! which burns OTP, insists on a verified release (see [`firmware_verified`]). Features no release
+//! lists are standard CTAP and decided by GetInfo alone. Firmware newer than `TESTED_FIRMWARE` is
+//! allowed.
+
+use super::fido::constants::VendorConfigCommand;
+use crate::device::{
+    error::PFError,
+    types::{Feature, FidoDeviceInfo, FullDeviceStatus},
+};
+use std::sync::Mutex;
+
+/// Newest release the whole table has been verified with.
+pub const TESTED_FIRMWARE: FirmwareVersion = FirmwareVersion { major: 7, minor: 2 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl FirmwareVersion {
+    /// Parses the `major.minor` form `DeviceInfo::firmware_version` uses.
+    pub fn parse(version: &str) -> Option<Self> {
+        let (major, minor) = version.trim().split_once('.')?;
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A firmware release and the rescue and vendor features verified from it on.
+struct Release {
+    version: FirmwareVersion,
+    features: &'static [Feature],
+}
+
+const RELEASES: &[Release] = &[Release {
+    version: FirmwareVersion { major: 7, minor: 2 },
+    features: &[
+        // Rescue applet: READ/WRITE PHY (0x1E/0x1C, P1 0x01), tags VID/PID to LED driver.
+        Feature::RescueConfig,
+        // Rescue applet: SECURE (0x1D) with the boot key in P1, READ 0x03 with the key slot.
+        Feature::RescueSecureBoot,
+        // Rescue applet: KEYDEV_SIGN (0x10).
+        Feature::RescueDeviceKey,
+        // Vendor PhysicalOptions (0x05) and the physical config commands.
+        Feature::FidoPhysicalConfig,
+        // Vendor Backup (0x01).
+        Feature::FidoBackup,
+        // Vendor Unlock (0x03) and the auth encryption config commands.
+        Feature::FidoAuthEncryption,
+        Feature::FidoPinPolicy,
+    ],
+}];
+
+/// What GetInfo must report for a feature.
+struct Requirement {
+    feature: Feature,
+    /// GetInfo option that must be present and true.
+    option: Option<&'static str>,
+    /// GetInfo extension that must be listed.
+    extension: Option<&'static str>,
+    /// GetInfo protocol version that must be listed.
+    version: Option<&'static str>,
+    /// authenticatorConfig vendor command that must be advertised, when the firmware lists them.
+    config_command: Option<VendorConfigCommand>,
+}
+
+impl Requirement {
+    /// A feature GetInfo has nothing to say about.
+    const fn of(feature: Feature) -> Self {
+        Self {
+            feature,
+            option: None,
+            extension: None,
+            version: None,
+            config_command: None,
+        }
+    }
+}
+
+const MATRIX: &[Requirement] = &[
+    Requirement::of(Feature::RescueConfig),
+    Requirement::of(Feature::RescueSecureBoot),
+    Requirement::of(Feature::RescueDeviceKey),
+    Requirement {
+        config_command: Some(VendorConfigCommand::PhysicalOptions),
+        ..Requirement::of(Feature::FidoPhysicalConfig)
+    },
+    Requirement::of(Feature::FidoBackup),
+    Requirement {
+        config_command: Some(VendorConfigCommand::AuthEncryptionEnable),
+        ..Requirement::of(Feature::FidoAuthEncryption)
+    },
+    Requirement {
+        config_command: Some(VendorConfigCommand::PinComplexityPolicy),
+        ..Requirement::of(Feature::FidoPinPolicy)
+    },
+    Requirement {
+        option: Some("credMgmt"),
+        ..Requirement::of(Feature::CredentialManagement)
+    },
+    Requirement {
+        option: Some("largeBlobs"),
+        ..Requirement::of(Feature::LargeBlobs)
+    },
+    Requirement {
+        option: Some("setMinPINLength"),
+        ..Requirement::of(Feature::MinPinLength)
+    },
+    Requirement {
+        extension: Some("hmac-secret"),
+        ..Requirement::of(Feature::HmacSecret)
+    },
+    Requirement {
+        version: Some("FIDO_2_0"),
+        ..Requirement::of(Feature::Registration)
+    },
+];
+
+fn requirement(feature: Feature) -> Result<&'static Requirement, String> {
+    MATRIX
+        .iter()
+        .find(|r| r.feature == feature)
+        .ok_or_else(|| format!("{} has no row in the capability matrix", feature.label()))
+}
+
+/// The first release `feature` is verified with, or `None` for standard CTAP features.
+fn since(feature: Feature) -> Option<FirmwareVersion> {
+    RELEASES
+        .iter()
+        .find(|r| r.features.contains(&feature))
+        .map(|r| r.version)
+}
+
+/// Whether `feature` has been verified with `version` or an older release, ignoring GetInfo.
+pub(crate) fn firmware_verified(version: FirmwareVersion, feature: Feature) -> Result<(), String> {
+    let Some(required) = since(feature) else {
+        return Ok(());
+    };
+    if version < required {
+        return Err(format!(
+            "{} has only been verified with firmware {} and newer, the device runs {}",
+            feature.label(),
+            required,
+            version
+        ));
+    }
+    Ok(())
+}
+
+fn check(fido: Option<&FidoDeviceInfo>, feature: Feature) -> Result<(), String> {
+    let requirement = requirement(feature)?;
+
+    let Some(info) = fido else {
+        return Ok(());
+    };
+    if let Some(option) = requirement.option
+        && info.options.get(option) != Some(&true)
+    {
+        return Err(format!(
+            "{} is not supported by this authenticator (no {} in GetInfo)",
+            feature.label(),
+            option
+        ));
+    }
+    if let Some(extension) = requirement.extension
+        && !info.extensions.iter().any(|e| e == extension)
+    {
+        return Err(format!(
+            "{} is not supported by this authenticator (no {} extension)",
+            feature.label(),
+            extension
+        ));
+    }
+    if let Some(version) = requirement.version
+        && !info.versions.iter().any(|v| v == version)
+    {
+        return Err(format!(
+            "{} needs a {} authenticator",
+            feature.label(),
+            version
+        ));
+    }
+    if let Some(command) = requirement.config_command {
+        let advertised = &info.vendor_prototype_config_commands;
+        if !advertised.is_empty() && !advertised.contains(&command.to_u64()) {
+            return Err(format!(
+                "{} is not supported by this firmware build",
+                feature.label()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the device described by `status` supports `feature`, with the reason if not.
+/// Without a status nothing is known and everything is allowed.
+pub fn support(status: Option<&FullDeviceStatus>, feature: Feature) -> Result<(), String> {
+    match status {
+        Some(status) => check(status.fido.as_ref(), feature),
+        None => Ok(()),
+    }
+}
+
+static DETECTED: Mutex<Option<Option<FidoDeviceInfo>>> = Mutex::new(None);
+
+/// Remembers the last device read so later operations can be checked without reading it again.
+pub(crate) fn record(status: Option<&FullDeviceStatus>) {
+    if let Some(version) = status.and_then(|s| FirmwareVersion::parse(&s.info.firmware_version)) {
+        if version > TESTED_FIRMWARE {
+            log::warn!(
+                "Firmware {} is newer than {}, the last release PicoForge was tested with",
+                version,
+                TESTED_FIRMWARE
+            );
+        }
+        for release in RELEASES {
+            for &feature in release.features {
+                if let Err(reason) = firmware_verified(version, feature) {
+                    log::warn!("{}; it will be tried anyway", reason);
+                }
+            }
+        }
+    }
+    *DETECTED.lock().unwrap_or_else(|e| e.into_inner()) = status.map(|s| s.fido.clone());
+}
+
+/// Fails with the reason when the last device read does not support `feature`.
+pub(crate) fn require(feature: Feature) -> Result<(), PFError> {
+    let detected = DETECTED.lock().unwrap_or_else(|e| e.into_inner());
+    match detected.as_ref() {
+        Some(fido) => check(fido.as_ref(), feature).map_err(PFError::Device),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every feature; the match fails to compile when a variant is added and not listed here.
+    fn all_features() -> Vec<Feature> {
+        let all = vec![
+            Feature::RescueConfig,
+            Feature::RescueSecureBoot,
+            Feature::RescueDeviceKey,
+            Feature::FidoPhysicalConfig,
+            Feature::FidoBackup,
+            Feature::FidoAuthEncryption,
+            Feature::FidoPinPolicy,
+            Feature::CredentialManagement,
+            Feature::LargeBlobs,
+            Feature::MinPinLength,
+            Feature::HmacSecret,
+            Feature::Registration,
+        ];
+        for feature in &all {
+            match feature {
+                Feature::RescueConfig
+                | Feature::RescueSecureBoot
+                | Feature::RescueDeviceKey
+                | Feature::FidoPhysicalConfig
+                | Feature::FidoBackup
+                | Feature::FidoAuthEncryption
+                | Feature::FidoPinPolicy
+                | Feature::CredentialManagement
+                | Feature::LargeBlobs
+                | Feature::MinPinLength
+                | Feature::HmacSecret
+                | Feature::Registration => {}
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn every_feature_has_a_matrix_row() {
+        for feature in all_features() {
+            assert!(requirement(feature).is_ok(), "{:?} has no row", feature);
+        }
+    }
+
+    #[test]
+    fn older_releases_are_not_refused() {
+        for feature in all_features() {
+            assert!(check(None, feature).is_ok());
+        }
+        let old = FirmwareVersion { major: 6, minor: 0 };
+        assert!(firmware_verified(old, Feature::RescueSecureBoot).is_err());
+        assert!(firmware_verified(TESTED_FIRMWARE, Feature::RescueSecureBoot).is_ok());
+        assert!(firmware_verified(old, Feature::HmacSecret).is_ok());
+    }
+}
@@ -9,6 +9,19 @@ pub enum PFError {
     Io(String),
     #[error("Device Error: {0}")]
     Device(String),
+    /// A CTAP2 response whose status byte was not success.
+    #[error("FIDO Operation Failed with Status: 0x{0:02X}")]
+    Ctap(u8),
+}
+
+impl PFError {
+    /// The CTAP2 status byte, if the device answered with one.
+    pub fn ctap_status(&self) -> Option<u8> {
+        match self {
+            PFError::Ctap(status) => Some(*status),
+            _ => None,
+        }
+    }
 }
 
 // Allow error to be serialized to string for Tauri
@@ -36,6 +49,10 @@ impl serde::Serialize for PFError {
                 state.serialize_field("type", "Device")?;
                 state.serialize_field("message", msg)?;
             }
+            PFError::Ctap(_) => {
+                state.serialize_field("type", "Ctap")?;
+                state.serialize_field("message", &self.to_string())?;
+            }
         }
         state.end()
     }
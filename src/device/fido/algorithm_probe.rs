@@ -0,0 +1,137 @@
+//! Signature algorithm support probe.
+//!
+//! GetInfo only advertises what the firmware was built with; whether an algorithm can actually be
+//! used also depends on the configured curves (secp256k1 is switched off by the rescue config, for
+//! example). Each algorithm is therefore tried with a throwaway non-discoverable credential. An
+//! unsupported algorithm is rejected before the key asks for presence, so only accepted ones need
+//! a touch.
+
+use super::constants::*;
+use super::cose;
+use super::hid::*;
+use super::self_test::{SELF_TEST_RP_ID, random_client_data_hash};
+use super::webauthn::{self, MakeCredentialRequest};
+use crate::device::{
+    error::PFError,
+    types::{AlgorithmSupport, CheckStatus},
+};
+use serde_cbor_2::Value;
+
+/// Signature algorithms worth probing, in display order.
+const PROBED_ALGORITHMS: &[CoseAlgorithm] = &[
+    CoseAlgorithm::ES256,
+    CoseAlgorithm::ESP256,
+    CoseAlgorithm::ES384,
+    CoseAlgorithm::ESP384,
+    CoseAlgorithm::ES512,
+    CoseAlgorithm::ESP512,
+    CoseAlgorithm::ES256K,
+    CoseAlgorithm::EdDSA,
+    CoseAlgorithm::Ed25519,
+    CoseAlgorithm::Ed448,
+    CoseAlgorithm::ESB256,
+    CoseAlgorithm::ESB384,
+    CoseAlgorithm::ESB512,
+    CoseAlgorithm::RS256,
+];
+
+fn probe_one(transport: &HidTransport, alg: i32) -> (CheckStatus, String) {
+    let client_data_hash = match random_client_data_hash() {
+        Ok(h) => h,
+        Err(e) => return (CheckStatus::Skipped, e.to_string()),
+    };
+
+    let result = webauthn::make_credential(
+        transport,
+        MakeCredentialRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &client_data_hash,
+            user_id: b"picoforge-probe",
+            user_name: "picoforge-probe",
+            algorithms: &[alg],
+            resident_key: false,
+            extensions: None,
+            pin_token: None,
+        },
+    );
+
+    match result {
+        Ok(attestation) => {
+            let Some(credential) = attestation.auth_data.credential.as_ref() else {
+                return (
+                    CheckStatus::Failed,
+                    "Accepted, but no credential was returned".into(),
+                );
+            };
+            let returned_alg = match &credential.public_key {
+                Value::Map(m) => match m.get(&Value::Integer(CoseKeyParam::Alg as i128)) {
+                    Some(Value::Integer(a)) => Some(*a as i32),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if returned_alg != Some(alg) {
+                return (
+                    CheckStatus::Failed,
+                    format!(
+                        "Accepted, but returned a key for algorithm {:?}",
+                        returned_alg
+                    ),
+                );
+            }
+
+            let detail = if cose::can_verify(alg) {
+                match webauthn::verify_attestation(&attestation, &client_data_hash) {
+                    Ok(_) => "Supported, attestation verified".to_string(),
+                    Err(e) => return (CheckStatus::Failed, format!("Supported, but {}", e)),
+                }
+            } else {
+                "Supported".to_string()
+            };
+            (CheckStatus::Passed, detail)
+        }
+        Err(e) => match e.ctap_status() {
+            Some(s) if s == Ctap2Error::UnsupportedAlgorithm as u8 => {
+                (CheckStatus::Failed, "Not supported".to_string())
+            }
+            Some(s) if s == Ctap2Error::UnsupportedOption as u8 => (
+                CheckStatus::Skipped,
+                "The key rejected the registration options".to_string(),
+            ),
+            Some(s) if s == Ctap2Error::PuatRequired as u8 => (
+                CheckStatus::Skipped,
+                "The key requires a PIN for every registration".to_string(),
+            ),
+            _ => (CheckStatus::Skipped, e.to_string()),
+        },
+    }
+}
+
+/// Tries every algorithm in [`PROBED_ALGORITHMS`] and compares the outcome with GetInfo.
+pub(crate) fn probe() -> Result<Vec<AlgorithmSupport>, PFError> {
+    log::info!("Probing signature algorithm support...");
+
+    let transport = HidTransport::open()?;
+    let advertised = super::read_device_info(&transport)
+        .map(|info| info.algorithms)
+        .unwrap_or_default();
+    log::debug!("GetInfo advertises algorithms {:?}", advertised);
+
+    let results = PROBED_ALGORITHMS
+        .iter()
+        .map(|alg| {
+            let id = *alg as i32;
+            let (status, detail) = probe_one(&transport, id);
+            log::debug!("Algorithm {} ({}): {:?} - {}", alg, id, status, detail);
+            AlgorithmSupport {
+                algorithm_id: id,
+                algorithm: alg.to_string(),
+                advertised: advertised.contains(&id),
+                status,
+                detail,
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
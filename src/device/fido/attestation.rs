@@ -0,0 +1,244 @@
+//! Attestation certificate inspection.
+//!
+//! A throwaway non-discoverable credential is registered to obtain a fresh attestation statement.
+//! The `x5c` chain of a packed statement is decoded and verified link by link, every issuer must be
+//! a CA inside its validity period, and the last certificate is matched against the trust anchors.
+//! Anchors are the vendor roots the imported MDS BLOB lists for the key's AAGUID plus any the user
+//! keeps in `attestation_anchors.pem` in the data directory. Stock pico-fido signs its attestation
+//! certificate with a key generated on the device and has no project-wide root, so it is reported
+//! as self-signed.
+
+use super::constants::*;
+use super::hid::HidTransport;
+use super::metadata;
+use super::self_test::{SELF_TEST_RP_ID, random_client_data_hash};
+use super::webauthn::{self, MakeCredentialRequest};
+use crate::device::{
+    error::PFError,
+    types::{AttestationCertificate, AttestationReport, AttestationTrust},
+};
+use directories::ProjectDirs;
+use serde_cbor_2::Value;
+use std::path::PathBuf;
+use x509_parser::{certificate::X509Certificate, pem::Pem};
+
+fn user_anchors_path() -> Option<PathBuf> {
+    ProjectDirs::from("in", "suyogtandel", "picoforge")
+        .map(|dirs| dirs.data_local_dir().join("attestation_anchors.pem"))
+}
+
+/// DER encodings of the MDS roots for `aaguid` and every user supplied trust anchor certificate.
+fn load_anchors(aaguid: &str) -> Vec<Vec<u8>> {
+    let mut anchors = metadata::attestation_roots(aaguid);
+    let Some(path) = user_anchors_path() else {
+        return anchors;
+    };
+    let Ok(pem_data) = std::fs::read(&path) else {
+        return anchors;
+    };
+    log::debug!("Loading attestation anchors from {}", path.display());
+
+    anchors.extend(
+        Pem::iter_from_buffer(&pem_data).filter_map(|pem| match pem {
+            Ok(pem) if pem.label == "CERTIFICATE" => Some(pem.contents),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Skipping unreadable attestation anchor: {}", e);
+                None
+            }
+        }),
+    );
+    anchors
+}
+
+fn oid_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "1.2.840.10045.2.1" => Some("ECDSA"),
+        "1.2.840.113549.1.1.1" => Some("RSA"),
+        "1.3.101.112" => Some("Ed25519"),
+        "1.3.101.113" => Some("Ed448"),
+        "1.2.840.10045.3.1.7" => Some("P-256"),
+        "1.3.132.0.34" => Some("P-384"),
+        "1.3.132.0.35" => Some("P-521"),
+        "1.3.132.0.10" => Some("secp256k1"),
+        _ => None,
+    }
+}
+
+fn key_algorithm(cert: &X509Certificate) -> String {
+    let algorithm = &cert.public_key().algorithm;
+    let oid = algorithm.algorithm.to_id_string();
+    let name = oid_name(&oid).map(str::to_string).unwrap_or(oid);
+
+    let curve = algorithm
+        .parameters
+        .as_ref()
+        .and_then(|p| p.as_oid().ok())
+        .map(|curve| curve.to_id_string());
+    match curve {
+        Some(curve) => format!(
+            "{} {}",
+            name,
+            oid_name(&curve).map(str::to_string).unwrap_or(curve)
+        ),
+        None => name,
+    }
+}
+
+pub(crate) fn describe(cert: &X509Certificate) -> AttestationCertificate {
+    AttestationCertificate {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        serial: cert.raw_serial_as_string(),
+        not_before: cert.validity().not_before.to_string(),
+        not_after: cert.validity().not_after.to_string(),
+        key_algorithm: key_algorithm(cert),
+        currently_valid: cert.validity().is_valid(),
+    }
+}
+
+fn is_self_signed(cert: &X509Certificate) -> bool {
+    cert.subject() == cert.issuer() && cert.verify_signature(None).is_ok()
+}
+
+/// Checks that `cert` may issue the certificates below it: it must be inside its validity period
+/// and carry basicConstraints with `cA` set and a path length admitting `intermediates_below`.
+pub(crate) fn check_issuer(
+    cert: &X509Certificate,
+    intermediates_below: usize,
+) -> Result<(), String> {
+    if !cert.validity().is_valid() {
+        return Err(format!("{} is outside its validity period", cert.subject()));
+    }
+    match cert.basic_constraints() {
+        Ok(Some(bc)) if bc.value.ca => match bc.value.path_len_constraint {
+            Some(max) if intermediates_below > max as usize => Err(format!(
+                "{} allows at most {} intermediate certificates below it",
+                cert.subject(),
+                max
+            )),
+            _ => Ok(()),
+        },
+        _ => Err(format!("{} is not a CA certificate", cert.subject())),
+    }
+}
+
+/// Verifies every link of `chain` (leaf first) and finds what it is anchored in.
+fn evaluate_chain(chain: &[X509Certificate], anchors: &[Vec<u8>]) -> AttestationTrust {
+    for pair in chain.windows(2) {
+        if pair[0]
+            .verify_signature(Some(pair[1].public_key()))
+            .is_err()
+        {
+            return AttestationTrust::Untrusted(format!(
+                "{} is not signed by {}",
+                pair[0].subject(),
+                pair[1].subject()
+            ));
+        }
+    }
+
+    for (i, cert) in chain.iter().enumerate().skip(1) {
+        if let Err(e) = check_issuer(cert, i - 1) {
+            return AttestationTrust::Untrusted(e);
+        }
+    }
+
+    let Some(last) = chain.last() else {
+        return AttestationTrust::NoCertificate;
+    };
+
+    for anchor_der in anchors {
+        let Ok((_, anchor)) = x509_parser::parse_x509_certificate(anchor_der) else {
+            continue;
+        };
+        let is_anchor =
+            last.subject() == anchor.subject() && last.public_key().raw == anchor.public_key().raw;
+        let signed_by_anchor = last.issuer() == anchor.subject()
+            && last.verify_signature(Some(anchor.public_key())).is_ok();
+        if signed_by_anchor && let Err(e) = check_issuer(&anchor, chain.len() - 1) {
+            return AttestationTrust::Untrusted(e);
+        }
+        if is_anchor || signed_by_anchor {
+            return AttestationTrust::Trusted(anchor.subject().to_string());
+        }
+    }
+
+    if is_self_signed(last) {
+        AttestationTrust::SelfSigned
+    } else {
+        AttestationTrust::Untrusted(format!("Issuer {} is not a known anchor", last.issuer()))
+    }
+}
+
+/// Registers a throwaway credential (one touch) and inspects its attestation statement.
+pub(crate) fn inspect() -> Result<AttestationReport, PFError> {
+    log::info!("Inspecting attestation certificate...");
+
+    let transport = HidTransport::open()?;
+    let client_data_hash = random_client_data_hash()?;
+    let attestation = webauthn::make_credential(
+        &transport,
+        MakeCredentialRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &client_data_hash,
+            user_id: b"picoforge-attestation",
+            user_name: "picoforge-attestation",
+            algorithms: &[CoseAlgorithm::ES256 as i32, CoseAlgorithm::EdDSA as i32],
+            resident_key: false,
+            extensions: None,
+            pin_token: None,
+        },
+    )?;
+
+    let (signature_valid, signature_detail) =
+        match webauthn::verify_attestation(&attestation, &client_data_hash) {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e),
+        };
+
+    let chain_der: Vec<Vec<u8>> = match attestation.att_stmt.get(&Value::Text("x5c".into())) {
+        Some(Value::Array(chain)) => chain
+            .iter()
+            .filter_map(|c| match c {
+                Value::Bytes(b) => Some(b.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut chain = Vec::new();
+    for der in &chain_der {
+        let (_, cert) = x509_parser::parse_x509_certificate(der)
+            .map_err(|e| PFError::Device(format!("Invalid attestation certificate: {}", e)))?;
+        chain.push(cert);
+    }
+
+    let aaguid = attestation
+        .auth_data
+        .credential
+        .as_ref()
+        .map(|c| hex::encode_upper(&c.aaguid))
+        .unwrap_or_default();
+    let trust = if chain.is_empty() {
+        AttestationTrust::NoCertificate
+    } else {
+        evaluate_chain(&chain, &load_anchors(&aaguid))
+    };
+    log::info!(
+        "Attestation: format '{}', {} certificates, {:?}",
+        attestation.fmt,
+        chain.len(),
+        trust
+    );
+
+    Ok(AttestationReport {
+        format: attestation.fmt.clone(),
+        aaguid,
+        signature_valid,
+        signature_detail,
+        certificates: chain.iter().map(describe).collect(),
+        trust,
+    })
+}
@@ -0,0 +1,374 @@
+//! Encrypted key storage ("auth encryption") for pico-fido.
+//!
+//! When enabled, the firmware keeps its device key encrypted under a 32-byte secret that only the
+//! host knows, and the key has to be unlocked after every power-up before it can sign anything.
+//! The secret never travels in the clear: each exchange is wrapped with a session key from the
+//! `ManageSecurityEnvironment` key agreement (ECDH P-256, HKDF-SHA256, ChaCha20-Poly1305 with the
+//! device's public point as associated data).
+//!
+//! Secrets are stored per device serial. The firmware does not report whether its storage is
+//! encrypted, so the state shown is what the device last answered to enable, disable or unlock.
+
+use super::constants::*;
+use super::hid::HidTransport;
+use crate::device::{
+    error::PFError,
+    types::{AuthEncryptionStatus, StorageEncryption},
+};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use directories::ProjectDirs;
+use ring::{aead, agreement, hkdf, rand::SecureRandom, rand::SystemRandom};
+use serde_cbor_2::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const SECRET_LEN: usize = 32;
+const MSE_HKDF_INFO: &[u8] = b"encryption key";
+const COSE_KTY_EC2: i128 = 2;
+
+/// Session established by the MSE key agreement, valid until the HID channel is closed.
+struct MseSession {
+    key: [u8; 32],
+    device_point: Vec<u8>,
+}
+
+/// Device answers seen this session, by serial.
+static OBSERVED: Mutex<Option<HashMap<String, StorageEncryption>>> = Mutex::new(None);
+
+fn observe(serial: &str, state: StorageEncryption) {
+    OBSERVED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(serial.to_string(), state);
+}
+
+fn observed(serial: &str) -> StorageEncryption {
+    OBSERVED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|m| m.get(serial).copied())
+        .unwrap_or(StorageEncryption::Unknown)
+}
+
+/// The secret is bound to the rescue serial, so a key without one cannot be enabled.
+fn check_serial(serial: &str) -> Result<(), PFError> {
+    if serial.is_empty() || serial == "?" || !serial.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(PFError::Device(
+            "The device serial number could not be read, so the unlock secret cannot be bound \
+             to this key"
+                .into(),
+        ));
+    }
+    Ok(())
+}
+
+fn secret_path(serial: &str) -> PathBuf {
+    ProjectDirs::from("in", "suyogtandel", "picoforge")
+        .map(|dirs| dirs.data_local_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(format!("auth_encryption-{}.key", serial))
+}
+
+fn load_secret(serial: &str) -> Result<Option<Vec<u8>>, PFError> {
+    check_serial(serial)?;
+    let path = secret_path(serial);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = std::fs::read_to_string(&path)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let secret = hex::decode(data.trim())
+        .map_err(|e| PFError::Io(format!("Unlock secret is corrupted: {}", e)))?;
+    if secret.len() != SECRET_LEN {
+        return Err(PFError::Io("Unlock secret has an invalid length".into()));
+    }
+    Ok(Some(secret))
+}
+
+/// Creates the secret file readable by the owner only; an existing file is never replaced.
+fn store_secret(serial: &str, secret: &[u8]) -> Result<(), PFError> {
+    let path = secret_path(serial);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| PFError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(hex::encode(secret).as_bytes()))
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+
+    log::info!("Unlock secret stored at {}", path.display());
+    Ok(())
+}
+
+fn key_agreement(transport: &HidTransport) -> Result<MseSession, PFError> {
+    log::debug!("Starting MSE key agreement...");
+    let rng = SystemRandom::new();
+
+    let private = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)
+        .map_err(|_| PFError::Io("Failed to generate ephemeral key".into()))?;
+    let public = private
+        .compute_public_key()
+        .map_err(|_| PFError::Io("Failed to compute ephemeral public key".into()))?;
+    // Uncompressed SEC1 point: 0x04 || x || y
+    let point = public.as_ref();
+
+    let mut cose_key = BTreeMap::new();
+    cose_key.insert(
+        Value::Integer(CoseKeyParam::Kty as i128),
+        Value::Integer(COSE_KTY_EC2),
+    );
+    cose_key.insert(
+        Value::Integer(CoseKeyParam::Alg as i128),
+        Value::Integer(CoseAlgorithm::EcdhEsHkdf256 as i128),
+    );
+    cose_key.insert(
+        Value::Integer(CoseKeyParam::Crv as i128),
+        Value::Integer(CoseCurve::P256 as i128),
+    );
+    cose_key.insert(
+        Value::Integer(CoseKeyParam::X as i128),
+        Value::Bytes(point[1..33].to_vec()),
+    );
+    cose_key.insert(
+        Value::Integer(CoseKeyParam::Y as i128),
+        Value::Bytes(point[33..65].to_vec()),
+    );
+
+    let mut sub_params = BTreeMap::new();
+    sub_params.insert(
+        Value::Integer(VendorSubParam::CoseKey as i128),
+        Value::Map(cose_key),
+    );
+
+    let response = transport.send_vendor_cbor(
+        VendorCommand::ManageSecurityEnvironment,
+        MseSubCommand::KeyAgreement as u8,
+        Some(sub_params),
+    )?;
+
+    let device_key = match &response {
+        Value::Map(m) => m.get(&Value::Integer(0x01)),
+        _ => None,
+    };
+    let (x, y) = match device_key {
+        Some(Value::Map(k)) => match (
+            k.get(&Value::Integer(CoseKeyParam::X as i128)),
+            k.get(&Value::Integer(CoseKeyParam::Y as i128)),
+        ) {
+            (Some(Value::Bytes(x)), Some(Value::Bytes(y))) => (x.clone(), y.clone()),
+            _ => {
+                return Err(PFError::Device(
+                    "Key agreement response is missing the device point".into(),
+                ));
+            }
+        },
+        _ => {
+            return Err(PFError::Device(
+                "Device returned an unexpected key agreement response".into(),
+            ));
+        }
+    };
+
+    let mut device_point = vec![0x04];
+    device_point.extend(&x);
+    device_point.extend(&y);
+
+    let peer = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, &device_point);
+    let key = agreement::agree_ephemeral(private, &peer, |shared| {
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, &[]).extract(shared);
+        let mut key = [0u8; 32];
+        prk.expand(&[MSE_HKDF_INFO], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut key))
+            .map(|_| key)
+    })
+    .map_err(|_| PFError::Device("Key agreement with the device failed".into()))?
+    .map_err(|_| PFError::Io("Failed to derive session key".into()))?;
+
+    log::debug!("MSE session established");
+    Ok(MseSession { key, device_point })
+}
+
+/// Encrypts `plaintext` for the device as `nonce || ciphertext || tag`.
+fn seal(session: &MseSession, plaintext: &[u8]) -> Result<Vec<u8>, PFError> {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| PFError::Io("Failed to generate nonce".into()))?;
+
+    let key = aead::LessSafeKey::new(
+        aead::UnboundKey::new(&aead::CHACHA20_POLY1305, &session.key)
+            .map_err(|_| PFError::Io("Invalid session key".into()))?,
+    );
+
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        aead::Nonce::assume_unique_for_key(nonce),
+        aead::Aad::from(session.device_point.as_slice()),
+        &mut in_out,
+    )
+    .map_err(|_| PFError::Io("Failed to encrypt unlock secret".into()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(in_out);
+    Ok(sealed)
+}
+
+fn config_pin_token(pin: &str) -> Result<Vec<u8>, PFError> {
+    let device = super::get_device().map_err(PFError::Device)?;
+    let token = device
+        .get_pinuv_auth_token_with_permission(pin, Permission::AuthenticatorConfiguration)
+        .map_err(|e| {
+            log::error!("Failed to get PIN token with ACFG permission: {:?}", e);
+            PFError::Device(format!("Failed to obtain PIN token: {:?}", e))
+        })?;
+    Ok(token.key)
+    // Library handle 'device' is dropped here, closing the HID session.
+}
+
+/// What the device last answered for `serial` and whether this computer holds its secret.
+pub(crate) fn status(serial: &str) -> AuthEncryptionStatus {
+    AuthEncryptionStatus {
+        state: observed(serial),
+        has_secret: matches!(load_secret(serial), Ok(Some(_))),
+        secret_path: secret_path(serial).display().to_string(),
+    }
+}
+
+/// Encrypts the device key with a new host secret. The secret is stored before the device is
+/// touched, so a failed write never leaves the key locked with a secret we do not have.
+pub(crate) fn enable(serial: &str, pin: String) -> Result<String, PFError> {
+    log::info!("Enabling FIDO storage encryption for {}...", serial);
+
+    if load_secret(serial)?.is_some() {
+        return Err(PFError::Device(
+            "This computer already holds an unlock secret for this key".into(),
+        ));
+    }
+
+    let mut secret = [0u8; SECRET_LEN];
+    SystemRandom::new()
+        .fill(&mut secret)
+        .map_err(|_| PFError::Io("Failed to generate unlock secret".into()))?;
+
+    let pin_token = config_pin_token(&pin)?;
+    store_secret(serial, &secret)?;
+
+    // Nothing has reached the device until the command is sent, so the secret can go again.
+    let prepared = (|| {
+        let transport = HidTransport::open()?;
+        let session = key_agreement(&transport)?;
+        let sealed = seal(&session, &secret)?;
+        Ok::<_, PFError>((transport, sealed))
+    })();
+    let (transport, sealed) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            log::error!("Failed to prepare storage encryption: {}", e);
+            let _ = std::fs::remove_file(secret_path(serial));
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = transport.send_vendor_config(
+        &pin_token,
+        VendorConfigCommand::AuthEncryptionEnable,
+        Value::Bytes(sealed),
+    ) {
+        log::error!("Failed to enable storage encryption: {}", e);
+        if e.ctap_status().is_some() {
+            // The device answered with an error status, so it did not take the secret.
+            let _ = std::fs::remove_file(secret_path(serial));
+        } else {
+            // The command may have been applied; the secret could be the only way back in.
+            observe(serial, StorageEncryption::Unknown);
+            return Err(PFError::Device(format!(
+                "{}. The device may have enabled encryption, so the unlock secret was kept at {}",
+                e,
+                secret_path(serial).display()
+            )));
+        }
+        return Err(e);
+    }
+
+    observe(serial, StorageEncryption::Encrypted);
+    log::info!("Storage encryption enabled");
+    Ok(
+        "Storage encryption enabled. The key must be unlocked from PicoForge after every power-up."
+            .into(),
+    )
+}
+
+/// Unlocks the device key for this power cycle.
+pub(crate) fn unlock(serial: &str) -> Result<String, PFError> {
+    log::info!("Unlocking FIDO storage of {}...", serial);
+
+    let secret = load_secret(serial)?.ok_or_else(|| {
+        PFError::Device("No unlock secret for this device is stored on this computer".into())
+    })?;
+
+    let transport = HidTransport::open()?;
+    let session = key_agreement(&transport)?;
+
+    let mut sub_params = BTreeMap::new();
+    sub_params.insert(
+        Value::Integer(VendorSubParam::VendorParam as i128),
+        Value::Bytes(seal(&session, &secret)?),
+    );
+
+    transport
+        .send_vendor_cbor(
+            VendorCommand::Unlock,
+            UnlockSubCommand::Unlock as u8,
+            Some(sub_params),
+        )
+        .map_err(|e| {
+            log::error!("Unlock vendor command failed: {}", e);
+            PFError::Device(format!("Failed to unlock device: {}", e))
+        })?;
+
+    observe(serial, StorageEncryption::Encrypted);
+    log::info!("Device storage unlocked");
+    Ok("Device unlocked".into())
+}
+
+/// Stores the device key in plain form again and forgets the host secret.
+pub(crate) fn disable(serial: &str, pin: String) -> Result<String, PFError> {
+    log::info!("Disabling FIDO storage encryption for {}...", serial);
+
+    // The firmware can only write back the plain key while it is unlocked.
+    unlock(serial)?;
+
+    let pin_token = config_pin_token(&pin)?;
+    let transport = HidTransport::open()?;
+    transport
+        .send_vendor_config(
+            &pin_token,
+            VendorConfigCommand::AuthEncryptionDisable,
+            Value::Integer(0),
+        )
+        .map_err(|e| {
+            log::error!("Failed to disable storage encryption: {}", e);
+            e
+        })?;
+    observe(serial, StorageEncryption::Plain);
+
+    let path = secret_path(serial);
+    std::fs::remove_file(&path)
+        .map_err(|e| PFError::Io(format!("Failed to remove {}: {}", path.display(), e)))?;
+
+    log::info!("Storage encryption disabled");
+    Ok("Storage encryption disabled".into())
+}
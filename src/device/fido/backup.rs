@@ -0,0 +1,279 @@
+//! Encrypted backup and restore of the device seed through the pico-fido `Backup` vendor command.
+//!
+//! The firmware never hands out the plain seed: `GetEncryptedBackup` returns the seed wrapped by the
+//! device, and `RestoreEncryptedBackup` loads such a blob into a (fresh) key. Some firmware builds
+//! expose the 24-word mnemonic variant instead of, or next to, the blob.
+//!
+//! Passkeys stored on the key are not part of the seed, so a restore is checked with a
+//! non-discoverable credential registered when the backup is taken: a key holding the same seed
+//! can still sign with it, under the same public key.
+
+use super::constants::*;
+use super::credentials;
+use super::hid::HidTransport;
+use super::self_test::random_client_data_hash;
+use super::webauthn::{self, GetAssertionRequest, MakeCredentialRequest};
+use crate::device::{
+    error::PFError,
+    types::{BackupVerification, FidoBackup, FidoBackupExport, SeedCheck},
+};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use serde_cbor_2::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const BACKUP_FORMAT_VERSION: u32 = 2;
+
+const SEED_CHECK_RP_ID: &str = "backup.picoforge.invalid";
+const SEED_CHECK_USER: &str = "picoforge-backup-check";
+
+fn pin_token(pin: Option<&str>, permission: Permission) -> Result<Option<Vec<u8>>, PFError> {
+    pin.map(|pin| credentials::pin_token(pin, permission))
+        .transpose()
+}
+
+/// Registers the seed check credential. Nothing is stored on the key.
+fn create_seed_check(pin: Option<&str>) -> Result<SeedCheck, PFError> {
+    let token = pin_token(pin, Permission::MakeCredential)?;
+    let transport = HidTransport::open()?;
+    let client_data_hash = random_client_data_hash()?;
+
+    let attestation = webauthn::make_credential(
+        &transport,
+        MakeCredentialRequest {
+            rp_id: SEED_CHECK_RP_ID,
+            client_data_hash: &client_data_hash,
+            user_id: SEED_CHECK_USER.as_bytes(),
+            user_name: SEED_CHECK_USER,
+            algorithms: &[CoseAlgorithm::ES256 as i32],
+            resident_key: false,
+            extensions: None,
+            pin_token: token.as_deref(),
+        },
+    )?;
+    let credential = attestation
+        .auth_data
+        .credential
+        .ok_or_else(|| PFError::Device("Seed check registration returned no credential".into()))?;
+    let public_key = serde_cbor_2::to_vec(&credential.public_key)
+        .map_err(|e| PFError::Io(format!("Failed to encode public key: {}", e)))?;
+
+    Ok(SeedCheck {
+        rp_id: SEED_CHECK_RP_ID.into(),
+        credential_id: hex::encode(credential.credential_id),
+        public_key: hex::encode(public_key),
+    })
+}
+
+/// Reads the encrypted seed backup from the device and registers the seed check credential, which
+/// needs a touch. The PIN is only required when the key asks for it to register credentials.
+pub(crate) fn create_backup(
+    serial: String,
+    pin: Option<String>,
+) -> Result<FidoBackupExport, PFError> {
+    log::info!("Creating encrypted FIDO backup...");
+
+    // The PIN token is fetched through the library, which opens its own HID session.
+    let seed_check = create_seed_check(pin.as_deref())?;
+
+    let transport = HidTransport::open()?;
+    let info = super::read_device_info(&transport)?;
+    let (aaguid, firmware_version) = (info.aaguid, info.firmware_version);
+
+    let response = transport
+        .send_vendor_cbor(
+            VendorCommand::Backup,
+            BackupSubCommand::GetEncryptedBackup as u8,
+            None,
+        )
+        .map_err(|e| {
+            log::error!("Backup vendor command failed: {}", e);
+            PFError::Device(format!("Failed to read backup from device: {}", e))
+        })?;
+
+    let map = match response {
+        Value::Map(m) => m,
+        _ => {
+            return Err(PFError::Device(
+                "Device returned an unexpected backup response".into(),
+            ));
+        }
+    };
+
+    let blob = match map.get(&Value::Integer(0x01)) {
+        Some(Value::Bytes(b)) => b.clone(),
+        _ => Vec::new(),
+    };
+
+    let mnemonic: Vec<String> = match map.get(&Value::Integer(0x02)) {
+        Some(Value::Array(words)) => words
+            .iter()
+            .filter_map(|w| match w {
+                Value::Text(t) => Some(t.clone()),
+                _ => None,
+            })
+            .collect(),
+        Some(Value::Text(t)) => t.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+
+    if blob.is_empty() && mnemonic.is_empty() {
+        return Err(PFError::Device(
+            "Device returned neither a backup blob nor a mnemonic".into(),
+        ));
+    }
+
+    log::info!(
+        "Backup read successfully ({} bytes, {} mnemonic words)",
+        blob.len(),
+        mnemonic.len()
+    );
+
+    Ok(FidoBackupExport {
+        backup: FidoBackup {
+            format_version: BACKUP_FORMAT_VERSION,
+            serial,
+            firmware_version,
+            aaguid,
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            encrypted_backup: hex::encode(&blob),
+            seed_check: Some(seed_check),
+        },
+        mnemonic,
+    })
+}
+
+/// Loads a backup into the device, replacing its current seed.
+pub(crate) fn restore_backup(
+    backup: &FidoBackup,
+    mnemonic: Option<String>,
+) -> Result<String, PFError> {
+    log::warn!(
+        "Restoring FIDO backup taken from device {} on {}",
+        backup.serial,
+        backup.created_at
+    );
+
+    let blob = hex::decode(&backup.encrypted_backup)
+        .map_err(|e| PFError::Io(format!("Backup file is corrupted: {}", e)))?;
+    let mnemonic = mnemonic
+        .map(|m| m.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|m| !m.is_empty());
+
+    if blob.is_empty() && mnemonic.is_none() {
+        return Err(PFError::Io(
+            "This backup only contains a mnemonic, enter the recovery words to restore it".into(),
+        ));
+    }
+
+    let mut sub_params = BTreeMap::new();
+    if !blob.is_empty() {
+        sub_params.insert(
+            Value::Integer(VendorSubParam::VendorParam as i128),
+            Value::Bytes(blob),
+        );
+    }
+    if let Some(words) = mnemonic {
+        sub_params.insert(
+            Value::Integer(VendorSubParam::VendorParamText as i128),
+            Value::Text(words),
+        );
+    }
+
+    let transport = HidTransport::open()?;
+    transport
+        .send_vendor_cbor(
+            VendorCommand::Backup,
+            BackupSubCommand::RestoreEncryptedBackup as u8,
+            Some(sub_params),
+        )
+        .map_err(|e| {
+            log::error!("Restore vendor command failed: {}", e);
+            PFError::Device(format!("Failed to restore backup: {}", e))
+        })?;
+
+    log::info!("Backup restored successfully");
+    Ok("Backup restored successfully. Unplug and re-plug the device.".into())
+}
+
+/// Asks the restored key to sign with the seed check credential and verifies the signature with
+/// the public key recorded in the backup.
+pub(crate) fn verify_restore(
+    backup: &FidoBackup,
+    pin: Option<String>,
+) -> Result<BackupVerification, PFError> {
+    let check = backup.seed_check.as_ref().ok_or_else(|| {
+        PFError::Io("This backup has no seed check, it was taken by an older version".into())
+    })?;
+    let credential_id = hex::decode(&check.credential_id)
+        .map_err(|e| PFError::Io(format!("Backup file is corrupted: {}", e)))?;
+    let public_key: Value = hex::decode(&check.public_key)
+        .ok()
+        .and_then(|bytes| serde_cbor_2::from_slice(&bytes).ok())
+        .ok_or_else(|| PFError::Io("Backup file has an invalid seed check key".into()))?;
+
+    let token = pin_token(pin.as_deref(), Permission::GetAssertion)?;
+    let transport = HidTransport::open()?;
+    let client_data_hash = random_client_data_hash()?;
+
+    let assertion = match webauthn::get_assertion(
+        &transport,
+        GetAssertionRequest {
+            rp_id: &check.rp_id,
+            client_data_hash: &client_data_hash,
+            allow_list: &[credential_id],
+            extensions: None,
+            pin_token: token.as_deref(),
+        },
+    ) {
+        Ok(assertion) => assertion,
+        Err(e) => {
+            log::warn!("Seed check assertion failed: {}", e);
+            return Ok(BackupVerification {
+                seed_restored: false,
+                detail: format!(
+                    "The key does not recognise the seed check credential: {}",
+                    e
+                ),
+            });
+        }
+    };
+
+    Ok(
+        match webauthn::verify_assertion(&public_key, &assertion, &client_data_hash) {
+            Ok(()) => BackupVerification {
+                seed_restored: true,
+                detail: "The key signs with the credential recorded in the backup".into(),
+            },
+            Err(e) => BackupVerification {
+                seed_restored: false,
+                detail: format!("Signature does not match the recorded key: {}", e),
+            },
+        },
+    )
+}
+
+pub(crate) fn save_backup(path: &Path, backup: &FidoBackup) -> Result<(), PFError> {
+    let data = serde_json::to_vec_pretty(backup)
+        .map_err(|e| PFError::Io(format!("Failed to encode backup: {}", e)))?;
+    std::fs::write(path, data)
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    log::info!("Backup written to {}", path.display());
+    Ok(())
+}
+
+pub(crate) fn load_backup(path: &Path) -> Result<FidoBackup, PFError> {
+    let data = std::fs::read(path)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let backup: FidoBackup = serde_json::from_slice(&data)
+        .map_err(|e| PFError::Io(format!("Not a PicoForge backup file: {}", e)))?;
+
+    if backup.format_version > BACKUP_FORMAT_VERSION {
+        return Err(PFError::Io(format!(
+            "Backup format version {} is newer than supported ({})",
+            backup.format_version, BACKUP_FORMAT_VERSION
+        )));
+    }
+
+    Ok(backup)
+}
@@ -97,6 +97,70 @@ pub enum ClientPinParam {
     PermissionsRpId = 0x0A,
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMgmtSubCommand {
+    GetCredsMetadata = 0x01,
+    EnumerateRpsBegin = 0x02,
+    EnumerateRpsGetNextRp = 0x03,
+    EnumerateCredentialsBegin = 0x04,
+    EnumerateCredentialsGetNextCredential = 0x05,
+    DeleteCredential = 0x06,
+    UpdateUserInformation = 0x07,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMgmtParam {
+    SubCommand = 0x01,
+    SubCommandParams = 0x02,
+    PinUvAuthProtocol = 0x03,
+    PinUvAuthParam = 0x04,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMgmtSubCommandParam {
+    RpIdHash = 0x01,
+    CredentialId = 0x02,
+    User = 0x03,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialMgmtResponseKey {
+    ExistingResidentCredentialsCount = 0x01,
+    MaxPossibleRemainingResidentCredentialsCount = 0x02,
+    Rp = 0x03,
+    RpIdHash = 0x04,
+    TotalRps = 0x05,
+    User = 0x06,
+    CredentialId = 0x07,
+    PublicKey = 0x08,
+    TotalCredentials = 0x09,
+    CredProtect = 0x0A,
+    LargeBlobKey = 0x0B,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeBlobsParam {
+    Get = 0x01,
+    Set = 0x02,
+    Offset = 0x03,
+    Length = 0x04,
+    PinUvAuthParam = 0x05,
+    PinUvAuthProtocol = 0x06,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargeBlobEntryKey {
+    Ciphertext = 0x01,
+    Nonce = 0x02,
+    OrigSize = 0x03,
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigParam {
@@ -210,6 +274,12 @@ pub enum MseSubCommand {
     KeyAgreement = 0x01,
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockSubCommand {
+    Unlock = 0x01,
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EnterpriseAttestationSubCommand {
@@ -250,7 +320,51 @@ bitflags::bitflags! {
     }
 }
 
+/// PHY tags from pico-keys-sdk `src/fs/phy.h`, used as keys by the PhysicalOptions response.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicalTag {
+    VidPid = 0x00,
+    LedGpio = 0x04,
+    LedBrightness = 0x05,
+    Opts = 0x06,
+    PresenceTimeout = 0x08,
+    UsbProduct = 0x09,
+    Curves = 0x0A,
+    LedDriver = 0x0C,
+}
+
 bitflags::bitflags! {
+    /// PHY_OPTS bits.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PhysicalOptionFlags: u16 {
+        const LED_DIMMABLE = 0x02;
+        const DISABLE_POWER_RESET = 0x04;
+        const LED_STEADY = 0x08;
+    }
+}
+
+bitflags::bitflags! {
+    /// PHY_CURVES bits.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PhysicalCurveFlags: u32 {
+        const SECP256K1 = 0x08;
+    }
+}
+
+bitflags::bitflags! {
+    /// Parameter of the `PinComplexityPolicy` vendor config command. Only ever written; GetInfo
+    /// does not echo it back.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PinComplexityFlags: u8 {
+        const NO_SEQUENCES = 0x01;
+        const NO_REPEATS = 0x02;
+        const MIXED_CLASSES = 0x04;
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct AuthenticatorFlags: u8 {
         const USER_PRESENT = 0x01;
         const USER_VERIFIED = 0x04;
@@ -288,6 +402,55 @@ pub enum CoseAlgorithm {
     ESB512 = -268,
 }
 
+impl CoseAlgorithm {
+    pub fn from_i32(val: i32) -> Option<Self> {
+        match val {
+            -7 => Some(Self::ES256),
+            -8 => Some(Self::EdDSA),
+            -9 => Some(Self::ESP256),
+            -19 => Some(Self::Ed25519),
+            -25 => Some(Self::EcdhEsHkdf256),
+            -35 => Some(Self::ES384),
+            -36 => Some(Self::ES512),
+            -47 => Some(Self::ES256K),
+            -51 => Some(Self::ESP384),
+            -52 => Some(Self::ESP512),
+            -53 => Some(Self::Ed448),
+            -257 => Some(Self::RS256),
+            -258 => Some(Self::RS384),
+            -259 => Some(Self::RS512),
+            -265 => Some(Self::ESB256),
+            -267 => Some(Self::ESB384),
+            -268 => Some(Self::ESB512),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CoseAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ES256 => write!(f, "ES256"),
+            Self::EdDSA => write!(f, "EdDSA"),
+            Self::ESP256 => write!(f, "ESP256"),
+            Self::Ed25519 => write!(f, "Ed25519"),
+            Self::EcdhEsHkdf256 => write!(f, "ECDH-ES+HKDF-256"),
+            Self::ES384 => write!(f, "ES384"),
+            Self::ES512 => write!(f, "ES512"),
+            Self::ES256K => write!(f, "ES256K"),
+            Self::ESP384 => write!(f, "ESP384"),
+            Self::ESP512 => write!(f, "ESP512"),
+            Self::Ed448 => write!(f, "Ed448"),
+            Self::RS256 => write!(f, "RS256"),
+            Self::RS384 => write!(f, "RS384"),
+            Self::RS512 => write!(f, "RS512"),
+            Self::ESB256 => write!(f, "ESB256"),
+            Self::ESB384 => write!(f, "ESB384"),
+            Self::ESB512 => write!(f, "ESB512"),
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CoseCurve {
@@ -304,6 +467,43 @@ pub enum CoseCurve {
     BP512R1 = 11,
 }
 
+impl CoseCurve {
+    pub fn from_u8(val: u8) -> Option<Self> {
+        match val {
+            1 => Some(Self::P256),
+            2 => Some(Self::P384),
+            3 => Some(Self::P521),
+            4 => Some(Self::X25519),
+            5 => Some(Self::X448),
+            6 => Some(Self::Ed25519),
+            7 => Some(Self::Ed448),
+            8 => Some(Self::P256K1),
+            9 => Some(Self::BP256R1),
+            10 => Some(Self::BP384R1),
+            11 => Some(Self::BP512R1),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CoseCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::P256 => write!(f, "P-256"),
+            Self::P384 => write!(f, "P-384"),
+            Self::P521 => write!(f, "P-521"),
+            Self::X25519 => write!(f, "X25519"),
+            Self::X448 => write!(f, "X448"),
+            Self::Ed25519 => write!(f, "Ed25519"),
+            Self::Ed448 => write!(f, "Ed448"),
+            Self::P256K1 => write!(f, "secp256k1"),
+            Self::BP256R1 => write!(f, "brainpoolP256r1"),
+            Self::BP384R1 => write!(f, "brainpoolP384r1"),
+            Self::BP512R1 => write!(f, "brainpoolP512r1"),
+        }
+    }
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CoseKeyParam {
@@ -0,0 +1,217 @@
+//! Conversion of COSE_Key public keys (RFC 9053) into X.509 SubjectPublicKeyInfo, and signature
+//! verification with `ring`.
+//!
+//! Only the key types the authenticator can hand out are covered: EC2 on the NIST, secp256k1 and
+//! brainpool curves, OKP (Ed25519/Ed448/X25519) and RSA. `ring` verifies a subset of those; the
+//! others are reported as unsupported rather than failed.
+
+use super::constants::*;
+use base64::Engine;
+use ring::signature::{self, VerificationAlgorithm};
+use serde_cbor_2::Value;
+use std::collections::BTreeMap;
+
+const COSE_KTY_OKP: i128 = 1;
+const COSE_KTY_EC2: i128 = 2;
+const COSE_KTY_RSA: i128 = 3;
+// RSA keys reuse the negative labels: -1 is the modulus, -2 the public exponent.
+const COSE_RSA_N: i128 = -1;
+const COSE_RSA_E: i128 = -2;
+
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+
+fn curve_oid(curve: CoseCurve) -> Option<&'static [u8]> {
+    match curve {
+        CoseCurve::P256 => Some(&[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07]),
+        CoseCurve::P384 => Some(&[0x2B, 0x81, 0x04, 0x00, 0x22]),
+        CoseCurve::P521 => Some(&[0x2B, 0x81, 0x04, 0x00, 0x23]),
+        CoseCurve::P256K1 => Some(&[0x2B, 0x81, 0x04, 0x00, 0x0A]),
+        CoseCurve::BP256R1 => Some(&[0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07]),
+        CoseCurve::BP384R1 => Some(&[0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0B]),
+        CoseCurve::BP512R1 => Some(&[0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0D]),
+        CoseCurve::X25519 => Some(&[0x2B, 0x65, 0x6E]),
+        CoseCurve::X448 => Some(&[0x2B, 0x65, 0x6F]),
+        CoseCurve::Ed25519 => Some(&[0x2B, 0x65, 0x70]),
+        CoseCurve::Ed448 => Some(&[0x2B, 0x65, 0x71]),
+    }
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&b| b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend(content);
+    out
+}
+
+fn der_sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    der(0x30, &items.concat())
+}
+
+fn der_bit_string(content: &[u8]) -> Vec<u8> {
+    let mut bits = vec![0x00];
+    bits.extend(content);
+    der(0x03, &bits)
+}
+
+fn der_unsigned_integer(value: &[u8]) -> Vec<u8> {
+    let trimmed: Vec<u8> = value.iter().copied().skip_while(|&b| b == 0).collect();
+    let mut content = Vec::with_capacity(trimmed.len() + 1);
+    if trimmed.first().is_none_or(|&b| b & 0x80 != 0) {
+        content.push(0x00);
+    }
+    content.extend(trimmed);
+    der(0x02, &content)
+}
+
+fn param(key: &BTreeMap<Value, Value>, label: i128) -> Option<&Value> {
+    key.get(&Value::Integer(label))
+}
+
+fn param_bytes(key: &BTreeMap<Value, Value>, label: i128) -> Option<&[u8]> {
+    match param(key, label) {
+        Some(Value::Bytes(b)) => Some(b),
+        _ => None,
+    }
+}
+
+fn param_curve(key: &BTreeMap<Value, Value>) -> Option<CoseCurve> {
+    match param(key, CoseKeyParam::Crv as i128) {
+        Some(Value::Integer(c)) => CoseCurve::from_u8(*c as u8),
+        _ => None,
+    }
+}
+
+/// The raw public key as `ring` expects it: the uncompressed SEC1 point for EC2 keys, the plain
+/// key bytes for OKP keys and a DER `RSAPublicKey` for RSA.
+pub(crate) fn raw_public_key(cose: &Value) -> Option<Vec<u8>> {
+    let Value::Map(key) = cose else {
+        return None;
+    };
+
+    match param(key, CoseKeyParam::Kty as i128) {
+        Some(Value::Integer(COSE_KTY_EC2)) => {
+            let x = param_bytes(key, CoseKeyParam::X as i128)?;
+            let y = param_bytes(key, CoseKeyParam::Y as i128)?;
+            let mut point = vec![0x04];
+            point.extend(x);
+            point.extend(y);
+            Some(point)
+        }
+        Some(Value::Integer(COSE_KTY_OKP)) => {
+            param_bytes(key, CoseKeyParam::X as i128).map(<[u8]>::to_vec)
+        }
+        Some(Value::Integer(COSE_KTY_RSA)) => {
+            let n = param_bytes(key, COSE_RSA_N)?;
+            let e = param_bytes(key, COSE_RSA_E)?;
+            Some(der_sequence(&[
+                der_unsigned_integer(n),
+                der_unsigned_integer(e),
+            ]))
+        }
+        _ => None,
+    }
+}
+
+/// DER-encoded SubjectPublicKeyInfo of a COSE public key.
+pub(crate) fn to_spki(cose: &Value) -> Option<Vec<u8>> {
+    let Value::Map(key) = cose else {
+        return None;
+    };
+    let raw = raw_public_key(cose)?;
+
+    let algorithm = match param(key, CoseKeyParam::Kty as i128) {
+        Some(Value::Integer(COSE_KTY_EC2)) => der_sequence(&[
+            der(0x06, OID_EC_PUBLIC_KEY),
+            der(0x06, curve_oid(param_curve(key)?)?),
+        ]),
+        Some(Value::Integer(COSE_KTY_OKP)) => {
+            der_sequence(&[der(0x06, curve_oid(param_curve(key)?)?)])
+        }
+        Some(Value::Integer(COSE_KTY_RSA)) => {
+            der_sequence(&[der(0x06, OID_RSA_ENCRYPTION), vec![0x05, 0x00]])
+        }
+        _ => return None,
+    };
+
+    Some(der_sequence(&[algorithm, der_bit_string(&raw)]))
+}
+
+/// PEM armour (`-----BEGIN <label>-----`) with 64 character lines.
+pub(crate) fn to_pem(label: &str, der: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+fn verification_algorithm(alg: i32) -> Option<&'static dyn VerificationAlgorithm> {
+    match CoseAlgorithm::from_i32(alg)? {
+        CoseAlgorithm::ES256 | CoseAlgorithm::ESP256 => Some(&signature::ECDSA_P256_SHA256_ASN1),
+        CoseAlgorithm::ES384 | CoseAlgorithm::ESP384 => Some(&signature::ECDSA_P384_SHA384_ASN1),
+        CoseAlgorithm::EdDSA | CoseAlgorithm::Ed25519 => Some(&signature::ED25519),
+        CoseAlgorithm::RS256 => Some(&signature::RSA_PKCS1_2048_8192_SHA256),
+        CoseAlgorithm::RS384 => Some(&signature::RSA_PKCS1_2048_8192_SHA384),
+        CoseAlgorithm::RS512 => Some(&signature::RSA_PKCS1_2048_8192_SHA512),
+        _ => None,
+    }
+}
+
+/// Whether [`verify`] can check signatures made with this COSE algorithm.
+pub(crate) fn can_verify(alg: i32) -> bool {
+    verification_algorithm(alg).is_some()
+}
+
+/// Verifies `signature` over `message` with a raw public key (see [`raw_public_key`]).
+pub(crate) fn verify_raw(
+    alg: i32,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    let algorithm = verification_algorithm(alg).ok_or_else(|| {
+        format!(
+            "{} signatures cannot be verified here",
+            CoseAlgorithm::from_i32(alg)
+                .map(|a| a.to_string())
+                .unwrap_or_else(|| alg.to_string())
+        )
+    })?;
+    signature::UnparsedPublicKey::new(algorithm, public_key)
+        .verify(message, signature)
+        .map_err(|_| "Signature does not match".to_string())
+}
+
+/// Verifies `signature` over `message` with a COSE public key, using the key's own algorithm.
+pub(crate) fn verify(cose: &Value, message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let Value::Map(key) = cose else {
+        return Err("Public key is not a COSE key".into());
+    };
+    let alg = match param(key, CoseKeyParam::Alg as i128) {
+        Some(Value::Integer(a)) => *a as i32,
+        _ => return Err("Public key has no algorithm".into()),
+    };
+    let public_key = raw_public_key(cose).ok_or("Unsupported public key type")?;
+    verify_raw(alg, &public_key, message, signature)
+}
+
+/// The raw subject public key of a DER certificate, in the form [`verify_raw`] expects.
+pub(crate) fn certificate_public_key(der: &[u8]) -> Option<Vec<u8>> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    Some(cert.public_key().subject_public_key.data.to_vec())
+}
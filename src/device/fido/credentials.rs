@@ -0,0 +1,489 @@
+//! Resident credential management over our own HID transport.
+//!
+//! `ctap-hid-fido2` only exposes the basic enumeration fields, so everything that needs the full
+//! authenticatorCredentialManagement responses (public keys, credProtect, largeBlobKey) goes
+//! through here instead.
+
+use super::constants::*;
+use super::hid::HidTransport;
+use crate::device::{
+    error::PFError,
+    types::{
+        CredentialDeleteResult, CredentialPublicKey, CredentialsMetadata, InventoryFormat,
+        PasskeyInventory, PasskeyInventoryEntry, StoredCredential,
+    },
+};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use serde_cbor_2::{Value, to_vec};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A resident credential as reported by enumerateCredentials.
+#[derive(Debug, Clone)]
+pub(crate) struct ResidentCredential {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub user_id: Vec<u8>,
+    pub user_name: String,
+    pub user_display_name: String,
+    pub credential_id: Vec<u8>,
+    pub public_key: Option<Value>,
+    pub cred_protect: Option<u8>,
+    pub large_blob_key: Option<Vec<u8>>,
+}
+
+/// Obtains a PIN token with the given permission through the library, then closes its session.
+pub(crate) fn pin_token(pin: &str, permission: Permission) -> Result<Vec<u8>, PFError> {
+    let device = super::get_device().map_err(PFError::Device)?;
+    let token = device
+        .get_pinuv_auth_token_with_permission(pin, permission)
+        .map_err(|e| {
+            log::error!("Failed to get PIN token: {:?}", e);
+            PFError::Device(format!("Failed to obtain PIN token: {:?}", e))
+        })?;
+    Ok(token.key)
+}
+
+impl ResidentCredential {
+    pub(crate) fn to_stored(&self) -> StoredCredential {
+        StoredCredential {
+            rp_id: self.rp_id.clone(),
+            rp_name: self.rp_name.clone(),
+            user_name: self.user_name.clone(),
+            user_display_name: self.user_display_name.clone(),
+            user_id: hex::encode(&self.user_id),
+            credential_id: hex::encode(&self.credential_id),
+            public_key: self.public_key.as_ref().and_then(decode_public_key),
+            cred_protect: self.cred_protect,
+            has_large_blob_key: self.large_blob_key.is_some(),
+        }
+    }
+}
+
+/// Decodes the algorithm, key type and curve of a COSE_Key and fingerprints its encoding.
+pub(crate) fn decode_public_key(cose: &Value) -> Option<CredentialPublicKey> {
+    let Value::Map(m) = cose else {
+        return None;
+    };
+    let int = |param: CoseKeyParam| match m.get(&Value::Integer(param as i128)) {
+        Some(Value::Integer(i)) => Some(*i),
+        _ => None,
+    };
+
+    let algorithm_id = int(CoseKeyParam::Alg)? as i32;
+    let algorithm = CoseAlgorithm::from_i32(algorithm_id)
+        .map(|a| a.to_string())
+        .unwrap_or_else(|| format!("Unknown ({})", algorithm_id));
+    let key_type = match int(CoseKeyParam::Kty) {
+        Some(1) => "OKP".to_string(),
+        Some(2) => "EC2".to_string(),
+        Some(3) => "RSA".to_string(),
+        Some(other) => format!("Unknown ({})", other),
+        None => "Unknown".to_string(),
+    };
+    let curve = int(CoseKeyParam::Crv).map(|c| {
+        CoseCurve::from_u8(c as u8)
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| format!("Unknown ({})", c))
+    });
+
+    let encoded = to_vec(cose).ok()?;
+    let fingerprint = ring::digest::digest(&ring::digest::SHA256, &encoded)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    Some(CredentialPublicKey {
+        algorithm_id,
+        algorithm,
+        key_type,
+        curve,
+        cose_key: hex::encode(&encoded),
+        fingerprint,
+        pem: super::cose::to_spki(cose).map(|der| super::cose::to_pem("PUBLIC KEY", &der)),
+    })
+}
+
+fn response_int(map: &BTreeMap<Value, Value>, key: CredentialMgmtResponseKey) -> Option<i128> {
+    match map.get(&Value::Integer(key as i128)) {
+        Some(Value::Integer(i)) => Some(*i),
+        _ => None,
+    }
+}
+
+fn response_bytes(map: &BTreeMap<Value, Value>, key: CredentialMgmtResponseKey) -> Option<Vec<u8>> {
+    match map.get(&Value::Integer(key as i128)) {
+        Some(Value::Bytes(b)) => Some(b.clone()),
+        _ => None,
+    }
+}
+
+fn entity_field(entity: Option<&Value>, field: &str) -> Option<Value> {
+    match entity {
+        Some(Value::Map(m)) => m.get(&Value::Text(field.into())).cloned(),
+        _ => None,
+    }
+}
+
+fn entity_text(entity: Option<&Value>, field: &str) -> String {
+    match entity_field(entity, field) {
+        Some(Value::Text(t)) => t,
+        _ => String::new(),
+    }
+}
+
+fn is_no_credentials(e: &PFError) -> bool {
+    e.ctap_status() == Some(Ctap2Error::NoCredentials as u8)
+}
+
+/// Enumerates every resident credential with the full credential management response.
+pub(crate) fn enumerate(
+    transport: &HidTransport,
+    pin_token: &[u8],
+) -> Result<Vec<ResidentCredential>, PFError> {
+    let first_rp = match transport.send_credential_management(
+        Some(pin_token),
+        CredentialMgmtSubCommand::EnumerateRpsBegin,
+        None,
+    ) {
+        Ok(r) => r,
+        Err(e) if is_no_credentials(&e) => {
+            log::info!("No credentials stored on device (CTAP2_ERR_NO_CREDENTIALS)");
+            return Ok(Vec::new());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let total_rps = response_int(&first_rp, CredentialMgmtResponseKey::TotalRps).unwrap_or(1);
+    let mut rps = vec![first_rp];
+    for _ in 1..total_rps {
+        rps.push(transport.send_credential_management(
+            None,
+            CredentialMgmtSubCommand::EnumerateRpsGetNextRp,
+            None,
+        )?);
+    }
+
+    let mut credentials = Vec::new();
+
+    for rp in &rps {
+        let rp_entity = rp.get(&Value::Integer(CredentialMgmtResponseKey::Rp as i128));
+        let rp_id = entity_text(rp_entity, "id");
+        let rp_name = entity_text(rp_entity, "name");
+        let Some(rp_id_hash) = response_bytes(rp, CredentialMgmtResponseKey::RpIdHash) else {
+            log::warn!("RP {} has no rpIDHash, skipping", rp_id);
+            continue;
+        };
+
+        let mut params = BTreeMap::new();
+        params.insert(
+            Value::Integer(CredentialMgmtSubCommandParam::RpIdHash as i128),
+            Value::Bytes(rp_id_hash.clone()),
+        );
+        let first_cred = transport.send_credential_management(
+            Some(pin_token),
+            CredentialMgmtSubCommand::EnumerateCredentialsBegin,
+            Some(params),
+        )?;
+
+        let total_creds =
+            response_int(&first_cred, CredentialMgmtResponseKey::TotalCredentials).unwrap_or(1);
+        let mut creds = vec![first_cred];
+        for _ in 1..total_creds {
+            creds.push(transport.send_credential_management(
+                None,
+                CredentialMgmtSubCommand::EnumerateCredentialsGetNextCredential,
+                None,
+            )?);
+        }
+
+        for cred in creds {
+            let user = cred.get(&Value::Integer(CredentialMgmtResponseKey::User as i128));
+            let user_id = match entity_field(user, "id") {
+                Some(Value::Bytes(b)) => b,
+                _ => Vec::new(),
+            };
+            let credential_id = match entity_field(
+                cred.get(&Value::Integer(
+                    CredentialMgmtResponseKey::CredentialId as i128,
+                )),
+                "id",
+            ) {
+                Some(Value::Bytes(b)) => b,
+                _ => Vec::new(),
+            };
+
+            credentials.push(ResidentCredential {
+                rp_id: rp_id.clone(),
+                rp_name: rp_name.clone(),
+                user_id,
+                user_name: entity_text(user, "name"),
+                user_display_name: entity_text(user, "displayName"),
+                credential_id,
+                public_key: cred
+                    .get(&Value::Integer(
+                        CredentialMgmtResponseKey::PublicKey as i128,
+                    ))
+                    .cloned(),
+                cred_protect: response_int(&cred, CredentialMgmtResponseKey::CredProtect)
+                    .map(|p| p as u8),
+                large_blob_key: response_bytes(&cred, CredentialMgmtResponseKey::LargeBlobKey),
+            });
+        }
+    }
+
+    Ok(credentials)
+}
+
+/// Reads getCredsMetadata: `(existing, maxPossibleRemaining)` resident credential counts.
+pub(crate) fn metadata(transport: &HidTransport, pin_token: &[u8]) -> Result<(u32, u32), PFError> {
+    let response = transport.send_credential_management(
+        Some(pin_token),
+        CredentialMgmtSubCommand::GetCredsMetadata,
+        None,
+    )?;
+
+    let existing = response_int(
+        &response,
+        CredentialMgmtResponseKey::ExistingResidentCredentialsCount,
+    )
+    .unwrap_or(0) as u32;
+    let remaining = response_int(
+        &response,
+        CredentialMgmtResponseKey::MaxPossibleRemainingResidentCredentialsCount,
+    )
+    .unwrap_or(0) as u32;
+
+    Ok((existing, remaining))
+}
+
+/// Reads the metadata and enumerates the credentials with one PIN token over one transport, so
+/// both describe the same state of the key.
+pub(crate) fn list_with_metadata(
+    pin: String,
+) -> Result<(Vec<StoredCredential>, CredentialsMetadata), PFError> {
+    let pin_token = pin_token(&pin, Permission::CredentialManagement)?;
+    let transport = HidTransport::open()?;
+    let (existing, remaining) = metadata(&transport, &pin_token)?;
+    let credentials = enumerate(&transport, &pin_token)?
+        .iter()
+        .map(|c| c.to_stored())
+        .collect();
+
+    log::debug!(
+        "Credential metadata: {} stored, {} remaining",
+        existing,
+        remaining
+    );
+
+    Ok((
+        credentials,
+        CredentialsMetadata {
+            existing_resident_credentials_count: existing,
+            max_possible_remaining_resident_credentials_count: remaining,
+            capacity: MAX_RESIDENT_CREDENTIALS as u32,
+        },
+    ))
+}
+
+/// Replaces the user name and display name of a resident credential, keeping its user id.
+pub(crate) fn update_user(
+    pin: String,
+    credential_id_hex: String,
+    user_id_hex: String,
+    name: String,
+    display_name: String,
+) -> Result<String, PFError> {
+    log::info!(
+        "Updating user information of credential {}...",
+        credential_id_hex
+    );
+
+    let credential_id = hex::decode(&credential_id_hex)
+        .map_err(|_| PFError::Io("Invalid Credential ID Hex string".into()))?;
+    let user_id =
+        hex::decode(&user_id_hex).map_err(|_| PFError::Io("Invalid User ID Hex string".into()))?;
+
+    let mut descriptor = BTreeMap::new();
+    descriptor.insert(Value::Text("id".into()), Value::Bytes(credential_id));
+    descriptor.insert(Value::Text("type".into()), Value::Text("public-key".into()));
+
+    let mut user = BTreeMap::new();
+    user.insert(Value::Text("id".into()), Value::Bytes(user_id));
+    user.insert(Value::Text("name".into()), Value::Text(name));
+    user.insert(Value::Text("displayName".into()), Value::Text(display_name));
+
+    let mut params = BTreeMap::new();
+    params.insert(
+        Value::Integer(CredentialMgmtSubCommandParam::CredentialId as i128),
+        Value::Map(descriptor),
+    );
+    params.insert(
+        Value::Integer(CredentialMgmtSubCommandParam::User as i128),
+        Value::Map(user),
+    );
+
+    let pin_token = pin_token(&pin, Permission::CredentialManagement)?;
+    let transport = HidTransport::open()?;
+    transport
+        .send_credential_management(
+            Some(&pin_token),
+            CredentialMgmtSubCommand::UpdateUserInformation,
+            Some(params),
+        )
+        .map_err(|e| {
+            log::error!("updateUserInformation failed: {}", e);
+            PFError::Device(format!("Failed to update user information: {}", e))
+        })?;
+
+    Ok("Passkey user updated".into())
+}
+
+pub(crate) fn delete_one(
+    transport: &HidTransport,
+    pin_token: &[u8],
+    credential_id: &[u8],
+) -> Result<(), PFError> {
+    let mut descriptor = BTreeMap::new();
+    descriptor.insert(
+        Value::Text("id".into()),
+        Value::Bytes(credential_id.to_vec()),
+    );
+    descriptor.insert(Value::Text("type".into()), Value::Text("public-key".into()));
+
+    let mut params = BTreeMap::new();
+    params.insert(
+        Value::Integer(CredentialMgmtSubCommandParam::CredentialId as i128),
+        Value::Map(descriptor),
+    );
+
+    transport.send_credential_management(
+        Some(pin_token),
+        CredentialMgmtSubCommand::DeleteCredential,
+        Some(params),
+    )?;
+    Ok(())
+}
+
+/// Deletes several credentials with a single PIN token. A failure on one credential does not stop
+/// the others; every outcome is reported back.
+pub(crate) fn delete_many(
+    pin: String,
+    credentials: Vec<StoredCredential>,
+) -> Result<Vec<CredentialDeleteResult>, PFError> {
+    log::info!("Deleting {} credentials...", credentials.len());
+
+    let pin_token = pin_token(&pin, Permission::CredentialManagement)?;
+    let transport = HidTransport::open()?;
+
+    let results: Vec<CredentialDeleteResult> = credentials
+        .into_iter()
+        .map(|cred| {
+            let outcome = hex::decode(&cred.credential_id)
+                .map_err(|_| PFError::Io("Invalid Credential ID Hex string".into()))
+                .and_then(|id| delete_one(&transport, &pin_token, &id));
+
+            let error = outcome.err().map(|e| {
+                log::error!("Failed to delete credential {}: {}", cred.credential_id, e);
+                e.to_string()
+            });
+
+            CredentialDeleteResult {
+                credential_id: cred.credential_id,
+                rp_id: cred.rp_id,
+                user_name: cred.user_name,
+                error,
+            }
+        })
+        .collect();
+
+    log::info!(
+        "Bulk delete finished: {}/{} deleted",
+        results.iter().filter(|r| r.error.is_none()).count(),
+        results.len()
+    );
+    Ok(results)
+}
+
+impl From<&StoredCredential> for PasskeyInventoryEntry {
+    fn from(cred: &StoredCredential) -> Self {
+        let key = cred.public_key.as_ref();
+        Self {
+            rp_id: cred.rp_id.clone(),
+            rp_name: cred.rp_name.clone(),
+            user_name: cred.user_name.clone(),
+            user_display_name: cred.user_display_name.clone(),
+            user_id: cred.user_id.clone(),
+            credential_id: cred.credential_id.clone(),
+            algorithm: key.map(|k| k.algorithm.clone()).unwrap_or_default(),
+            cred_protect: cred.cred_protect,
+            public_key_cose: key.map(|k| k.cose_key.clone()).unwrap_or_default(),
+            public_key_pem: key.and_then(|k| k.pem.clone()).unwrap_or_default(),
+            public_key_fingerprint: key.map(|k| k.fingerprint.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn inventory_csv(entries: &[PasskeyInventoryEntry]) -> String {
+    let mut out = String::from(
+        "rp_id,rp_name,user_name,user_display_name,user_id,credential_id,algorithm,cred_protect,public_key_cose,public_key_pem,public_key_fingerprint\n",
+    );
+    for e in entries {
+        let cred_protect = e.cred_protect.map(|p| p.to_string()).unwrap_or_default();
+        let row = [
+            e.rp_id.as_str(),
+            &e.rp_name,
+            &e.user_name,
+            &e.user_display_name,
+            &e.user_id,
+            &e.credential_id,
+            &e.algorithm,
+            &cred_protect,
+            &e.public_key_cose,
+            &e.public_key_pem,
+            &e.public_key_fingerprint,
+        ];
+        out.push_str(&row.map(csv_field).join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes the given credentials to `path` for auditing. No device access is needed.
+pub(crate) fn export_inventory(
+    path: &Path,
+    credentials: &[StoredCredential],
+    aaguid: Option<String>,
+    format: InventoryFormat,
+) -> Result<(), PFError> {
+    let entries: Vec<PasskeyInventoryEntry> = credentials.iter().map(Into::into).collect();
+
+    let contents = match format {
+        InventoryFormat::Json => serde_json::to_string_pretty(&PasskeyInventory {
+            exported_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            aaguid,
+            credentials: entries,
+        })
+        .map_err(|e| PFError::Io(e.to_string()))?,
+        InventoryFormat::Csv => inventory_csv(&entries),
+    };
+
+    std::fs::write(path, contents)
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    log::info!(
+        "Exported {} credentials to {}",
+        credentials.len(),
+        path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Decoding of the authenticatorGetInfo response (CTAP 2.1 and the CTAP 2.2 additions).
+//!
+//! The response is parsed here rather than through `ctap-hid-fido2`, which stops at the CTAP 2.0
+//! fields and drops everything it does not know.
+
+use super::pin_policy;
+use crate::device::types::FidoDeviceInfo;
+use serde_cbor_2::Value;
+use std::collections::{BTreeMap, HashMap};
+
+const VERSIONS: i128 = 0x01;
+const EXTENSIONS: i128 = 0x02;
+const AAGUID: i128 = 0x03;
+const OPTIONS: i128 = 0x04;
+const MAX_MSG_SIZE: i128 = 0x05;
+const PIN_UV_AUTH_PROTOCOLS: i128 = 0x06;
+const MAX_CREDENTIAL_COUNT_IN_LIST: i128 = 0x07;
+const MAX_CREDENTIAL_ID_LENGTH: i128 = 0x08;
+const TRANSPORTS: i128 = 0x09;
+const ALGORITHMS: i128 = 0x0A;
+const MAX_SERIALIZED_LARGE_BLOB_ARRAY: i128 = 0x0B;
+const FORCE_PIN_CHANGE: i128 = 0x0C;
+const MIN_PIN_LENGTH: i128 = 0x0D;
+const FIRMWARE_VERSION: i128 = 0x0E;
+const MAX_CRED_BLOB_LENGTH: i128 = 0x0F;
+const MAX_RP_IDS_FOR_SET_MIN_PIN_LENGTH: i128 = 0x10;
+const PREFERRED_PLATFORM_UV_ATTEMPTS: i128 = 0x11;
+const UV_MODALITY: i128 = 0x12;
+const CERTIFICATIONS: i128 = 0x13;
+const REMAINING_DISCOVERABLE_CREDENTIALS: i128 = 0x14;
+const VENDOR_PROTOTYPE_CONFIG_COMMANDS: i128 = 0x15;
+const ATTESTATION_FORMATS: i128 = 0x16;
+const UV_COUNT_SINCE_LAST_PIN_ENTRY: i128 = 0x17;
+const LONG_TOUCH_FOR_RESET: i128 = 0x18;
+const TRANSPORTS_FOR_RESET: i128 = 0x1A;
+const MAX_PIN_LENGTH: i128 = 0x1D;
+
+fn get(m: &BTreeMap<Value, Value>, key: i128) -> Option<&Value> {
+    m.get(&Value::Integer(key))
+}
+
+fn int(m: &BTreeMap<Value, Value>, key: i128) -> Option<i128> {
+    match get(m, key) {
+        Some(Value::Integer(i)) => Some(*i),
+        _ => None,
+    }
+}
+
+fn uint(m: &BTreeMap<Value, Value>, key: i128) -> Option<u32> {
+    int(m, key).and_then(|i| u32::try_from(i).ok())
+}
+
+fn flag(m: &BTreeMap<Value, Value>, key: i128) -> bool {
+    matches!(get(m, key), Some(Value::Bool(true)))
+}
+
+fn texts(m: &BTreeMap<Value, Value>, key: i128) -> Vec<String> {
+    match get(m, key) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| match v {
+                Value::Text(t) => Some(t.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn ints(m: &BTreeMap<Value, Value>, key: i128) -> Vec<i128> {
+    match get(m, key) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| match v {
+                Value::Integer(i) => Some(*i),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn algorithms(m: &BTreeMap<Value, Value>) -> Vec<i32> {
+    match get(m, ALGORITHMS) {
+        Some(Value::Array(params)) => params
+            .iter()
+            .filter_map(|p| match p {
+                Value::Map(p) => match p.get(&Value::Text("alg".into())) {
+                    Some(Value::Integer(alg)) => Some(*alg as i32),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn options(m: &BTreeMap<Value, Value>) -> HashMap<String, bool> {
+    match get(m, OPTIONS) {
+        Some(Value::Map(options)) => options
+            .iter()
+            .filter_map(|(k, v)| match (k, v) {
+                (Value::Text(k), Value::Bool(v)) => Some((k.clone(), *v)),
+                _ => None,
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+fn certifications(m: &BTreeMap<Value, Value>) -> BTreeMap<String, i64> {
+    match get(m, CERTIFICATIONS) {
+        Some(Value::Map(certs)) => certs
+            .iter()
+            .filter_map(|(k, v)| match (k, v) {
+                (Value::Text(k), Value::Integer(v)) => Some((k.clone(), *v as i64)),
+                _ => None,
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Decodes a GetInfo response map. Missing optional fields are left empty.
+pub(crate) fn decode(m: &BTreeMap<Value, Value>) -> FidoDeviceInfo {
+    // NOTE: Key 0x03 is AAGUID, not the unique device Serial.
+    let aaguid = match get(m, AAGUID) {
+        Some(Value::Bytes(b)) => hex::encode_upper(b),
+        _ => {
+            log::warn!("AAGUID not found in GetInfo response");
+            "Unknown".into()
+        }
+    };
+
+    let firmware_version = match int(m, FIRMWARE_VERSION) {
+        Some(i) => format!("{}.{}", (i >> 8) & 0xFF, i & 0xFF),
+        None => {
+            log::warn!("Firmware version not found in GetInfo response");
+            "Unknown".into()
+        }
+    };
+
+    FidoDeviceInfo {
+        versions: texts(m, VERSIONS),
+        extensions: texts(m, EXTENSIONS),
+        aaguid,
+        options: options(m),
+        max_msg_size: int(m, MAX_MSG_SIZE).unwrap_or(0) as i32,
+        pin_protocols: ints(m, PIN_UV_AUTH_PROTOCOLS)
+            .into_iter()
+            .map(|p| p as u32)
+            .collect(),
+        remaining_disc_creds: uint(m, REMAINING_DISCOVERABLE_CREDENTIALS),
+        min_pin_length: uint(m, MIN_PIN_LENGTH).unwrap_or(4),
+        firmware_version,
+        pin_complexity_policy: pin_policy::decode_policy(m),
+        algorithms: algorithms(m),
+        max_credential_count_in_list: uint(m, MAX_CREDENTIAL_COUNT_IN_LIST),
+        max_credential_id_length: uint(m, MAX_CREDENTIAL_ID_LENGTH),
+        transports: texts(m, TRANSPORTS),
+        max_serialized_large_blob_array: uint(m, MAX_SERIALIZED_LARGE_BLOB_ARRAY),
+        force_pin_change: flag(m, FORCE_PIN_CHANGE),
+        max_cred_blob_length: uint(m, MAX_CRED_BLOB_LENGTH),
+        max_rp_ids_for_set_min_pin_length: uint(m, MAX_RP_IDS_FOR_SET_MIN_PIN_LENGTH),
+        preferred_platform_uv_attempts: uint(m, PREFERRED_PLATFORM_UV_ATTEMPTS),
+        uv_modality: uint(m, UV_MODALITY),
+        certifications: certifications(m),
+        vendor_prototype_config_commands: ints(m, VENDOR_PROTOTYPE_CONFIG_COMMANDS)
+            .into_iter()
+            .map(|c| c as u64)
+            .collect(),
+        attestation_formats: texts(m, ATTESTATION_FORMATS),
+        uv_count_since_last_pin_entry: uint(m, UV_COUNT_SINCE_LAST_PIN_ENTRY),
+        long_touch_for_reset: flag(m, LONG_TOUCH_FOR_RESET),
+        transports_for_reset: texts(m, TRANSPORTS_FOR_RESET),
+        max_pin_length: uint(m, MAX_PIN_LENGTH),
+    }
+}
@@ -1,6 +1,9 @@
 use rand::RngExt;
 use serde_cbor_2::{Value, to_vec};
 use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use crate::device::error::PFError;
@@ -12,6 +15,7 @@ const HID_USAGE_PAGE_FIDO: u16 = 0xF1D0;
 const CTAPHID_CID_BROADCAST: u32 = 0xFFFFFFFF;
 const CTAPHID_INIT: u8 = 0x86;
 pub const CTAPHID_CBOR: u8 = 0x90;
+const CTAPHID_CANCEL: u8 = 0x91;
 const CTAPHID_ERROR: u8 = 0xBF;
 const CTAPHID_KEEPALIVE: u8 = 0xBB;
 
@@ -20,6 +24,64 @@ const HID_READ_TIMEOUT_MS: i32 = 10;
 const HID_INIT_READ_TIMEOUT_MS: i32 = 100;
 const HID_RESP_READ_TIMEOUT_MS: i32 = 2000;
 const HID_CONT_READ_TIMEOUT_MS: i32 = 500;
+const HID_SELECTION_POLL_MS: i32 = 100;
+
+/// HID path of the key picked with authenticatorSelection. Every transport (and the library,
+/// see `fido::get_device`) talks to this key while it stays plugged in.
+static ACTIVE_DEVICE: Mutex<Option<CString>> = Mutex::new(None);
+
+pub(crate) fn set_active_device(path: Option<CString>) {
+    *ACTIVE_DEVICE.lock().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+pub(crate) fn active_device() -> Option<CString> {
+    ACTIVE_DEVICE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// USB serial number of the key the user works with: the selected one, or the only one attached.
+pub(crate) fn target_serial() -> Option<String> {
+    let api = init_api().ok()?;
+    let devices = fido_devices(&api);
+    let info = match active_device() {
+        Some(path) => devices.iter().find(|d| d.path() == path.as_c_str())?,
+        None if devices.len() == 1 => devices.first()?,
+        None => return None,
+    };
+    info.serial_number()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// HID paths of every attached FIDO interface, without duplicates.
+fn fido_devices(api: &hidapi::HidApi) -> Vec<&hidapi::DeviceInfo> {
+    let mut seen = Vec::new();
+    api.device_list()
+        .filter(|d| d.usage_page() == HID_USAGE_PAGE_FIDO)
+        .filter(|d| {
+            if seen.contains(&d.path()) {
+                false
+            } else {
+                seen.push(d.path());
+                true
+            }
+        })
+        .collect()
+}
+
+fn init_api() -> Result<hidapi::HidApi, PFError> {
+    hidapi::HidApi::new().map_err(|e| {
+        log::error!("Failed to initialize HidApi: {}", e);
+        PFError::Device(format!("Failed to initialize HidApi: {}", e))
+    })
+}
+
+/// Number of FIDO keys currently attached.
+pub(crate) fn count_devices() -> usize {
+    init_api().map(|api| fido_devices(&api).len()).unwrap_or(0)
+}
 
 pub struct HidTransport {
     device: hidapi::HidDevice,
@@ -32,20 +94,50 @@ pub struct HidTransport {
 impl HidTransport {
     pub fn open() -> Result<Self, PFError> {
         log::info!("Attempting to open HID transport for FIDO device...");
-        let api = hidapi::HidApi::new().map_err(|e| {
-            log::error!("Failed to initialize HidApi: {}", e);
-            PFError::Device(format!("Failed to initialize HidApi: {}", e))
-        })?;
-
-        // Find device with FIDO Usage Page (0xF1D0)
-        let info = api
-            .device_list()
-            .find(|d| d.usage_page() == HID_USAGE_PAGE_FIDO)
+        let api = init_api()?;
+        let devices = fido_devices(&api);
+
+        // Prefer the key picked with authenticatorSelection, then the first with the FIDO Usage
+        // Page (0xF1D0).
+        let active = active_device();
+        let info = active
+            .as_deref()
+            .and_then(|path| devices.iter().find(|d| d.path() == path))
+            .or_else(|| {
+                if active.is_some() {
+                    log::warn!("Selected FIDO device is gone, falling back to the first one.");
+                }
+                devices.first()
+            })
             .ok_or_else(|| {
                 log::warn!("No FIDO device found with Usage Page 0xF1D0.");
                 PFError::NoDevice
             })?;
 
+        Self::open_info(&api, info)
+    }
+
+    /// Opens every attached FIDO key, skipping the ones that fail to open.
+    pub(crate) fn open_all() -> Result<Vec<(CString, Self)>, PFError> {
+        let api = init_api()?;
+        let transports: Vec<(CString, Self)> = fido_devices(&api)
+            .into_iter()
+            .filter_map(|info| match Self::open_info(&api, info) {
+                Ok(transport) => Some((info.path().to_owned(), transport)),
+                Err(e) => {
+                    log::warn!("Skipping FIDO device {:?}: {}", info.path(), e);
+                    None
+                }
+            })
+            .collect();
+
+        if transports.is_empty() {
+            return Err(PFError::NoDevice);
+        }
+        Ok(transports)
+    }
+
+    fn open_info(api: &hidapi::HidApi, info: &hidapi::DeviceInfo) -> Result<Self, PFError> {
         log::debug!(
             "Found FIDO device: VendorID=0x{:04X}, ProductID=0x{:04X}",
             info.vendor_id(),
@@ -59,7 +151,7 @@ impl HidTransport {
             .unwrap_or("Unknown FIDO Device")
             .to_string();
 
-        let device = info.open_device(&api).map_err(|e| {
+        let device = info.open_device(api).map_err(|e| {
             log::error!("Failed to open HID device: {}", e);
             PFError::Device(format!("Failed to open HID device: {}", e))
         })?;
@@ -323,10 +415,7 @@ impl HidTransport {
         let status = response_data[0];
         if status != 0x00 {
             log::error!("FIDO Operation returned failure status: 0x{:02X}", status);
-            return Err(PFError::Device(format!(
-                "FIDO Operation Failed with Status: 0x{:02X}",
-                status
-            )));
+            return Err(PFError::Ctap(status));
         }
 
         log::debug!(
@@ -338,6 +427,54 @@ impl HidTransport {
         Ok(response_data[1..].to_vec())
     }
 
+    /// Send a pico-fido vendor CBOR command (0xC1) and decode the response map.
+    ///
+    /// The request is encoded as `{ 0x01: subCommand, 0x02: subParams }`, with the
+    /// sub-parameter map omitted when `sub_params` is `None`. An empty response is
+    /// returned as `Value::Null`.
+    pub fn send_vendor_cbor(
+        &self,
+        vendor_cmd: VendorCommand,
+        sub_cmd: u8,
+        sub_params: Option<BTreeMap<Value, Value>>,
+    ) -> Result<Value, PFError> {
+        log::debug!(
+            "Sending vendor CBOR command {:?} (sub-command 0x{:02X})...",
+            vendor_cmd,
+            sub_cmd
+        );
+
+        let mut request = BTreeMap::new();
+        request.insert(
+            Value::Integer(VendorParam::VendorCommand as i128),
+            Value::Integer(sub_cmd as i128),
+        );
+        if let Some(params) = sub_params {
+            request.insert(
+                Value::Integer(VendorParam::VendorSubParams as i128),
+                Value::Map(params),
+            );
+        }
+
+        let request_cbor = to_vec(&Value::Map(request)).map_err(|e| {
+            log::error!("Failed to encode vendor command CBOR: {}", e);
+            PFError::Io(format!("CBOR encode error: {}", e))
+        })?;
+
+        let mut payload = vec![vendor_cmd as u8];
+        payload.extend(request_cbor);
+
+        let response = self.send_cbor(CTAP_VENDOR_CBOR_CMD, &payload)?;
+        if response.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        serde_cbor_2::from_slice(&response).map_err(|e| {
+            log::error!("Failed to parse vendor command response: {}", e);
+            PFError::Io(format!("Failed to parse vendor response CBOR: {}", e))
+        })
+    }
+
     pub fn send_vendor_config(
         &self,
         pin_token: &[u8],
@@ -498,6 +635,263 @@ impl HidTransport {
         }
     }
 
+    /// Send an authenticatorCredentialManagement (0x0A) command and decode the response map.
+    ///
+    /// When a PIN token is given the request is authenticated over
+    /// `subCommand || subCommandParams`, as required for every sub-command except the
+    /// `GetNext*` enumeration steps.
+    pub fn send_credential_management(
+        &self,
+        pin_token: Option<&[u8]>,
+        sub_cmd: CredentialMgmtSubCommand,
+        sub_params: Option<BTreeMap<Value, Value>>,
+    ) -> Result<BTreeMap<Value, Value>, PFError> {
+        log::debug!("Sending credential management command {:?}...", sub_cmd);
+
+        let sub_params = sub_params.map(Value::Map);
+        let sub_params_bytes = match &sub_params {
+            Some(params) => to_vec(params).map_err(|e| PFError::Io(e.to_string()))?,
+            None => Vec::new(),
+        };
+
+        let mut request = BTreeMap::new();
+        request.insert(
+            Value::Integer(CredentialMgmtParam::SubCommand as i128),
+            Value::Integer(sub_cmd as i128),
+        );
+        if let Some(params) = sub_params {
+            request.insert(
+                Value::Integer(CredentialMgmtParam::SubCommandParams as i128),
+                params,
+            );
+        }
+        if let Some(token) = pin_token {
+            let mut message = vec![sub_cmd as u8];
+            message.extend(&sub_params_bytes);
+            request.insert(
+                Value::Integer(CredentialMgmtParam::PinUvAuthProtocol as i128),
+                Value::Integer(1),
+            );
+            request.insert(
+                Value::Integer(CredentialMgmtParam::PinUvAuthParam as i128),
+                Value::Bytes(Self::authenticate(token, &message)),
+            );
+        }
+
+        let request_cbor = to_vec(&Value::Map(request)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![CtapCommand::CredentialMgmt as u8];
+        payload.extend(request_cbor);
+
+        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        if response.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        serde_cbor_2::from_slice(&response).map_err(|e| {
+            log::error!("Failed to parse credential management response: {}", e);
+            PFError::Io(format!("Failed to parse credential management CBOR: {}", e))
+        })
+    }
+
+    /// Read `length` bytes of the serialized large-blob array starting at `offset`.
+    pub fn send_large_blobs_get(&self, offset: usize, length: usize) -> Result<Vec<u8>, PFError> {
+        log::debug!(
+            "Reading large blob fragment (offset {}, length {})...",
+            offset,
+            length
+        );
+
+        let mut request = BTreeMap::new();
+        request.insert(
+            Value::Integer(LargeBlobsParam::Get as i128),
+            Value::Integer(length as i128),
+        );
+        request.insert(
+            Value::Integer(LargeBlobsParam::Offset as i128),
+            Value::Integer(offset as i128),
+        );
+
+        let request_cbor = to_vec(&Value::Map(request)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![CtapCommand::LargeBlobs as u8];
+        payload.extend(request_cbor);
+
+        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        let map: BTreeMap<Value, Value> = serde_cbor_2::from_slice(&response).map_err(|e| {
+            log::error!("Failed to parse largeBlobs response: {}", e);
+            PFError::Io(format!("Failed to parse largeBlobs CBOR: {}", e))
+        })?;
+
+        match map.get(&Value::Integer(0x01)) {
+            Some(Value::Bytes(b)) => Ok(b.clone()),
+            _ => Err(PFError::Device(
+                "largeBlobs response is missing the config fragment".into(),
+            )),
+        }
+    }
+
+    /// Write one fragment of the serialized large-blob array.
+    ///
+    /// `total_length` must be given with the first fragment (offset 0) only. The request is
+    /// authenticated over `32×0xff || 0x0c 0x00 || uint32LE(offset) || SHA-256(fragment)`.
+    pub fn send_large_blobs_set(
+        &self,
+        pin_token: &[u8],
+        offset: usize,
+        fragment: &[u8],
+        total_length: Option<usize>,
+    ) -> Result<(), PFError> {
+        log::debug!(
+            "Writing large blob fragment (offset {}, {} bytes)...",
+            offset,
+            fragment.len()
+        );
+
+        let mut message = vec![0xff; 32];
+        message.extend([CtapCommand::LargeBlobs as u8, 0x00]);
+        message.extend((offset as u32).to_le_bytes());
+        message.extend(ring::digest::digest(&ring::digest::SHA256, fragment).as_ref());
+
+        let mut request = BTreeMap::new();
+        request.insert(
+            Value::Integer(LargeBlobsParam::Set as i128),
+            Value::Bytes(fragment.to_vec()),
+        );
+        request.insert(
+            Value::Integer(LargeBlobsParam::Offset as i128),
+            Value::Integer(offset as i128),
+        );
+        if let Some(length) = total_length {
+            request.insert(
+                Value::Integer(LargeBlobsParam::Length as i128),
+                Value::Integer(length as i128),
+            );
+        }
+        request.insert(
+            Value::Integer(LargeBlobsParam::PinUvAuthParam as i128),
+            Value::Bytes(Self::authenticate(pin_token, &message)),
+        );
+        request.insert(
+            Value::Integer(LargeBlobsParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+
+        let request_cbor = to_vec(&Value::Map(request)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![CtapCommand::LargeBlobs as u8];
+        payload.extend(request_cbor);
+
+        self.send_cbor(CTAPHID_CBOR, &payload).map_err(|e| {
+            log::error!("Failed to write large blob fragment: {}", e);
+            PFError::Device(format!("largeBlobs write failed: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    /// Send authenticatorMakeCredential (0x01) and decode the attestation object map.
+    ///
+    /// The device waits for user presence, so this blocks until the key is touched.
+    pub fn send_make_credential(
+        &self,
+        params: BTreeMap<Value, Value>,
+    ) -> Result<BTreeMap<Value, Value>, PFError> {
+        log::debug!("Sending makeCredential...");
+        self.send_ctap_map(CtapCommand::MakeCredential, params)
+    }
+
+    /// Send authenticatorGetAssertion (0x02) and decode the assertion map.
+    pub fn send_get_assertion(
+        &self,
+        params: BTreeMap<Value, Value>,
+    ) -> Result<BTreeMap<Value, Value>, PFError> {
+        log::debug!("Sending getAssertion...");
+        self.send_ctap_map(CtapCommand::GetAssertion, params)
+    }
+
+    /// Send authenticatorSelection (0x0B) and wait until the key is touched or `cancel` is set.
+    ///
+    /// Returns `false` when the request was cancelled. Once `cancel` is seen a CTAPHID_CANCEL is
+    /// sent and the key answers with KEEPALIVE_CANCEL (0x2D), which stops it blinking.
+    pub fn send_selection(&self, cancel: &AtomicBool) -> Result<bool, PFError> {
+        log::debug!("Sending authenticatorSelection...");
+        self.write_cbor_request(CTAPHID_CBOR, &[CtapCommand::Selection as u8])?;
+
+        let mut cancel_sent = false;
+        let mut buf = [0u8; HID_REPORT_SIZE];
+        loop {
+            if !cancel_sent && cancel.load(Ordering::SeqCst) {
+                let mut report = [0u8; HID_REPORT_SIZE + 1];
+                report[1..5].copy_from_slice(&self.cid.to_be_bytes());
+                report[5] = CTAPHID_CANCEL;
+                self.device
+                    .write(&report[..])
+                    .map_err(|e| PFError::Io(format!("Failed to write CANCEL packet: {}", e)))?;
+                cancel_sent = true;
+            }
+
+            let n = self
+                .device
+                .read_timeout(&mut buf[..], HID_SELECTION_POLL_MS)
+                .map_err(|e| PFError::Io(format!("Failed to read selection response: {}", e)))?;
+            if n == 0
+                || u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) != self.cid
+                || buf[4] == CTAPHID_KEEPALIVE
+            {
+                continue;
+            }
+
+            if buf[4] == CTAPHID_ERROR {
+                return Err(PFError::Device(format!(
+                    "Device returned CTAP Error: 0x{:02X}",
+                    buf[5],
+                )));
+            }
+            if buf[4] != CTAPHID_CBOR {
+                return Err(PFError::Device(format!(
+                    "Unexpected command response: 0x{:02X} (Expected 0x{:02X})",
+                    buf[4], CTAPHID_CBOR
+                )));
+            }
+
+            return match buf[7] {
+                0x00 => Ok(true),
+                status if status == Ctap2Error::KeepaliveCancel as u8 => Ok(false),
+                status => Err(PFError::Ctap(status)),
+            };
+        }
+    }
+
+    /// Send authenticatorClientPIN (0x06) and decode the response map.
+    pub fn send_client_pin(
+        &self,
+        params: BTreeMap<Value, Value>,
+    ) -> Result<BTreeMap<Value, Value>, PFError> {
+        log::debug!("Sending clientPIN...");
+        self.send_ctap_map(CtapCommand::ClientPin, params)
+    }
+
+    fn send_ctap_map(
+        &self,
+        cmd: CtapCommand,
+        params: BTreeMap<Value, Value>,
+    ) -> Result<BTreeMap<Value, Value>, PFError> {
+        let request_cbor = to_vec(&Value::Map(params)).map_err(|e| PFError::Io(e.to_string()))?;
+        let mut payload = vec![cmd as u8];
+        payload.extend(request_cbor);
+
+        let response = self.send_cbor(CTAPHID_CBOR, &payload)?;
+        serde_cbor_2::from_slice(&response).map_err(|e| {
+            log::error!("Failed to parse {:?} response: {}", cmd, e);
+            PFError::Io(format!("Failed to parse {:?} CBOR: {}", cmd, e))
+        })
+    }
+
+    /// pinUvAuthProtocol 1 `authenticate`: HMAC-SHA-256 truncated to 16 bytes.
+    pub(crate) fn authenticate(pin_token: &[u8], message: &[u8]) -> Vec<u8> {
+        use ring::hmac;
+        let hmac_key = hmac::Key::new(hmac::HMAC_SHA256, pin_token);
+        hmac::sign(&hmac_key, message).as_ref()[0..16].to_vec()
+    }
+
     /// Helper to sign the authenticatorConfig command
     fn sign_config_command(
         &self,
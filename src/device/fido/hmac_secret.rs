@@ -0,0 +1,348 @@
+//! hmac-secret extension tester.
+//!
+//! Registers a throwaway credential with `hmac-secret`, then asks for the secret twice with the same
+//! salt. The salt travels encrypted under a pinUvAuthProtocol 1 shared secret (ECDH P-256 with the
+//! authenticator's key agreement key, SHA-256 of the x coordinate, AES-256-CBC with a zero IV) and
+//! the output comes back the same way. Disk unlock tools rely on the output being identical every
+//! time, so that is the main check.
+
+use super::constants::*;
+use super::credentials;
+use super::hid::HidTransport;
+use super::self_test::{SELF_TEST_RP_ID, random_client_data_hash};
+use super::webauthn::{self, GetAssertionRequest, MakeCredentialRequest};
+use crate::device::{
+    error::PFError,
+    types::{CheckStatus, DiagnosticCheck, HmacSecretReport},
+};
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::NoPadding};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use ring::{agreement, digest, hmac, rand::SystemRandom};
+use serde_cbor_2::Value;
+use std::collections::BTreeMap;
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+const HMAC_SECRET: &str = "hmac-secret";
+const COSE_KTY_EC2: i128 = 2;
+const PIN_PROTOCOL_ONE: i128 = 1;
+/// Prefix the WebAuthn PRF extension hashes in front of its inputs.
+const PRF_CONTEXT: &[u8] = b"WebAuthn PRF\x00";
+
+// hmac-secret getAssertion input keys.
+const KEY_AGREEMENT: i128 = 0x01;
+const SALT_ENC: i128 = 0x02;
+const SALT_AUTH: i128 = 0x03;
+const PIN_UV_AUTH_PROTOCOL: i128 = 0x04;
+
+/// Shared secret from a pinUvAuthProtocol 1 key agreement, plus the platform key to send along.
+struct SharedSecret {
+    key: [u8; 32],
+    platform_key: BTreeMap<Value, Value>,
+}
+
+impl SharedSecret {
+    fn encrypt(&self, data: &[u8]) -> Vec<u8> {
+        Aes256CbcEnc::new(&self.key.into(), &[0u8; 16].into())
+            .encrypt_padded_vec_mut::<NoPadding>(data)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, PFError> {
+        Aes256CbcDec::new(&self.key.into(), &[0u8; 16].into())
+            .decrypt_padded_vec_mut::<NoPadding>(data)
+            .map_err(|_| PFError::Device("hmac-secret output is not block aligned".into()))
+    }
+
+    fn authenticate(&self, data: &[u8]) -> Vec<u8> {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &self.key);
+        hmac::sign(&key, data).as_ref()[..16].to_vec()
+    }
+}
+
+fn key_agreement(transport: &HidTransport) -> Result<SharedSecret, PFError> {
+    let mut params = BTreeMap::new();
+    params.insert(
+        Value::Integer(ClientPinParam::PinUvAuthProtocol as i128),
+        Value::Integer(PIN_PROTOCOL_ONE),
+    );
+    params.insert(
+        Value::Integer(ClientPinParam::SubCommand as i128),
+        Value::Integer(ClientPinSubCommand::GetKeyAgreement as i128),
+    );
+    let response = transport.send_client_pin(params)?;
+
+    let (x, y) = match response.get(&Value::Integer(0x01)) {
+        Some(Value::Map(k)) => match (
+            k.get(&Value::Integer(CoseKeyParam::X as i128)),
+            k.get(&Value::Integer(CoseKeyParam::Y as i128)),
+        ) {
+            (Some(Value::Bytes(x)), Some(Value::Bytes(y))) => (x.clone(), y.clone()),
+            _ => {
+                return Err(PFError::Device(
+                    "Key agreement response is missing the device point".into(),
+                ));
+            }
+        },
+        _ => {
+            return Err(PFError::Device(
+                "Device returned an unexpected key agreement response".into(),
+            ));
+        }
+    };
+    let mut device_point = vec![0x04];
+    device_point.extend(&x);
+    device_point.extend(&y);
+
+    let private =
+        agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &SystemRandom::new())
+            .map_err(|_| PFError::Io("Failed to generate ephemeral key".into()))?;
+    let public = private
+        .compute_public_key()
+        .map_err(|_| PFError::Io("Failed to compute ephemeral public key".into()))?;
+    let point = public.as_ref();
+
+    let mut platform_key = BTreeMap::new();
+    platform_key.insert(
+        Value::Integer(CoseKeyParam::Kty as i128),
+        Value::Integer(COSE_KTY_EC2),
+    );
+    platform_key.insert(
+        Value::Integer(CoseKeyParam::Alg as i128),
+        Value::Integer(CoseAlgorithm::EcdhEsHkdf256 as i128),
+    );
+    platform_key.insert(
+        Value::Integer(CoseKeyParam::Crv as i128),
+        Value::Integer(CoseCurve::P256 as i128),
+    );
+    platform_key.insert(
+        Value::Integer(CoseKeyParam::X as i128),
+        Value::Bytes(point[1..33].to_vec()),
+    );
+    platform_key.insert(
+        Value::Integer(CoseKeyParam::Y as i128),
+        Value::Bytes(point[33..65].to_vec()),
+    );
+
+    let peer = agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, &device_point);
+    let key = agreement::agree_ephemeral(private, &peer, |z| {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(digest::digest(&digest::SHA256, z).as_ref());
+        key
+    })
+    .map_err(|_| PFError::Device("Key agreement with the device failed".into()))?;
+
+    Ok(SharedSecret { key, platform_key })
+}
+
+/// A 64 character hex string is used as the salt itself; anything else is treated as a WebAuthn
+/// PRF input and hashed the way browsers do.
+fn derive_salt(input: &str) -> ([u8; 32], &'static str) {
+    let trimmed = input.trim();
+    if trimmed.len() == 64
+        && let Ok(raw) = hex::decode(trimmed)
+    {
+        let mut salt = [0u8; 32];
+        salt.copy_from_slice(&raw);
+        return (salt, "Raw salt");
+    }
+
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(PRF_CONTEXT);
+    context.update(input.as_bytes());
+    let mut salt = [0u8; 32];
+    salt.copy_from_slice(context.finish().as_ref());
+    (salt, "PRF input")
+}
+
+fn check(name: &str, result: Result<String, String>) -> DiagnosticCheck {
+    let (status, detail) = match result {
+        Ok(detail) => (CheckStatus::Passed, detail),
+        Err(detail) => (CheckStatus::Failed, detail),
+    };
+    DiagnosticCheck {
+        name: name.into(),
+        status,
+        detail,
+    }
+}
+
+/// A PIN token for one operation. The library needs the device to itself, so this has to run
+/// before our transport is opened.
+fn token_for(pin: &Option<String>, permission: Permission) -> Result<Option<Vec<u8>>, PFError> {
+    match pin {
+        Some(pin) => credentials::pin_token(pin, permission).map(Some),
+        None => Ok(None),
+    }
+}
+
+struct Evaluation {
+    output: Vec<u8>,
+    user_verified: bool,
+}
+
+fn evaluate(
+    pin: &Option<String>,
+    credential_id: &[u8],
+    salt: &[u8; 32],
+) -> Result<Evaluation, PFError> {
+    let token = token_for(pin, Permission::GetAssertion)?;
+    let transport = HidTransport::open()?;
+    let shared = key_agreement(&transport)?;
+
+    let salt_enc = shared.encrypt(salt);
+    let mut input = BTreeMap::new();
+    input.insert(
+        Value::Integer(KEY_AGREEMENT),
+        Value::Map(shared.platform_key.clone()),
+    );
+    input.insert(
+        Value::Integer(SALT_AUTH),
+        Value::Bytes(shared.authenticate(&salt_enc)),
+    );
+    input.insert(Value::Integer(SALT_ENC), Value::Bytes(salt_enc));
+    input.insert(
+        Value::Integer(PIN_UV_AUTH_PROTOCOL),
+        Value::Integer(PIN_PROTOCOL_ONE),
+    );
+    let mut extensions = BTreeMap::new();
+    extensions.insert(Value::Text(HMAC_SECRET.into()), Value::Map(input));
+
+    let client_data_hash = random_client_data_hash()?;
+    let allow_list = [credential_id.to_vec()];
+    let assertion = webauthn::get_assertion(
+        &transport,
+        GetAssertionRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &client_data_hash,
+            allow_list: &allow_list,
+            extensions: Some(extensions),
+            pin_token: token.as_deref(),
+        },
+    )?;
+
+    let encrypted = match assertion
+        .auth_data
+        .extensions
+        .as_ref()
+        .and_then(|e| e.get(&Value::Text(HMAC_SECRET.into())))
+    {
+        Some(Value::Bytes(b)) => b.clone(),
+        _ => {
+            return Err(PFError::Device(
+                "Assertion carries no hmac-secret output".into(),
+            ));
+        }
+    };
+
+    Ok(Evaluation {
+        output: shared.decrypt(&encrypted)?,
+        user_verified: assertion
+            .auth_data
+            .flags
+            .contains(AuthenticatorFlags::USER_VERIFIED),
+    })
+}
+
+/// Runs the test. The key has to be touched three times: once to register, twice to evaluate.
+pub(crate) fn run(salt_input: &str, pin: Option<String>) -> Result<HmacSecretReport, PFError> {
+    log::info!("Starting hmac-secret test...");
+    let (salt, salt_source) = derive_salt(salt_input);
+    let mut checks = Vec::new();
+
+    let mc_token = token_for(&pin, Permission::MakeCredential)?;
+    let transport = HidTransport::open()?;
+    let client_data_hash = random_client_data_hash()?;
+    let mut extensions = BTreeMap::new();
+    extensions.insert(Value::Text(HMAC_SECRET.into()), Value::Bool(true));
+
+    let attestation = webauthn::make_credential(
+        &transport,
+        MakeCredentialRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &client_data_hash,
+            user_id: b"picoforge-hmac-secret",
+            user_name: "picoforge-hmac-secret",
+            algorithms: &[CoseAlgorithm::ES256 as i32, CoseAlgorithm::EdDSA as i32],
+            resident_key: false,
+            extensions: Some(extensions),
+            pin_token: mc_token.as_deref(),
+        },
+    )?;
+    drop(transport);
+
+    let Some(credential) = attestation.auth_data.credential.as_ref() else {
+        return Err(PFError::Device(
+            "Registration returned no credential".into(),
+        ));
+    };
+    let enabled = matches!(
+        attestation
+            .auth_data
+            .extensions
+            .as_ref()
+            .and_then(|e| e.get(&Value::Text(HMAC_SECRET.into()))),
+        Some(Value::Bool(true))
+    );
+    checks.push(check(
+        "Register with hmac-secret",
+        if enabled {
+            Ok("The credential was created with hmac-secret enabled".into())
+        } else {
+            Err("The key did not confirm hmac-secret for the credential".into())
+        },
+    ));
+
+    let first = evaluate(&pin, &credential.credential_id, &salt);
+    let first = match first {
+        Ok(first) => {
+            checks.push(check(
+                "First evaluation",
+                if first.output.len() == 32 {
+                    Ok(format!("32 byte output, UV={}", first.user_verified as u8))
+                } else {
+                    Err(format!("Expected 32 bytes, got {}", first.output.len()))
+                },
+            ));
+            first
+        }
+        Err(e) => {
+            log::error!("hmac-secret evaluation failed: {}", e);
+            checks.push(check("First evaluation", Err(e.to_string())));
+            return Ok(HmacSecretReport {
+                salt_source: salt_source.into(),
+                user_verified: false,
+                checks,
+                output: String::new(),
+            });
+        }
+    };
+
+    match evaluate(&pin, &credential.credential_id, &salt) {
+        Ok(second) => {
+            checks.push(check(
+                "Deterministic output",
+                if second.output == first.output {
+                    Ok("Both evaluations returned the same secret".into())
+                } else if second.user_verified != first.user_verified {
+                    Err("Outputs differ because only one evaluation was user verified".into())
+                } else {
+                    Err("The same salt produced different secrets".into())
+                },
+            ));
+        }
+        Err(e) => checks.push(check("Deterministic output", Err(e.to_string()))),
+    }
+
+    let report = HmacSecretReport {
+        salt_source: salt_source.into(),
+        user_verified: first.user_verified,
+        checks,
+        output: hex::encode(&first.output),
+    };
+    log::info!(
+        "hmac-secret test finished: {}",
+        if report.passed() { "passed" } else { "failed" }
+    );
+    Ok(report)
+}
@@ -0,0 +1,278 @@
+//! CTAP 2.1 large-blob storage (authenticatorLargeBlobs, 0x0C).
+//!
+//! The device stores one serialized CBOR array followed by the first 16 bytes of its SHA-256.
+//! Every entry is `{1: ciphertext, 2: nonce, 3: origSize}`, encrypted with AES-256-GCM under the
+//! largeBlobKey of the credential that owns it (associated data `"blob" || uint64LE(origSize)`)
+//! and DEFLATE-compressed before encryption. Entries are matched to credentials by trying every
+//! key, as the array itself carries no owner.
+
+use super::constants::*;
+use super::credentials::{self, ResidentCredential};
+use super::hid::*;
+use crate::device::{
+    error::PFError,
+    types::{LargeBlobEntry, LargeBlobStorage},
+};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use ring::{aead, digest};
+use serde_cbor_2::{Value, from_slice, to_vec};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+const TRAILER_LEN: usize = 16;
+/// GetInfo key for maxSerializedLargeBlobArray.
+const GET_INFO_MAX_LARGE_BLOB_ARRAY: i128 = 0x0B;
+
+fn trailer(data: &[u8]) -> Vec<u8> {
+    digest::digest(&digest::SHA256, data).as_ref()[..TRAILER_LEN].to_vec()
+}
+
+fn read_max_size(transport: &HidTransport) -> usize {
+    let info = transport
+        .send_cbor(CTAPHID_CBOR, &[CtapCommand::GetInfo as u8])
+        .ok()
+        .and_then(|res| from_slice::<BTreeMap<Value, Value>>(&res).ok());
+
+    match info
+        .as_ref()
+        .and_then(|m| m.get(&Value::Integer(GET_INFO_MAX_LARGE_BLOB_ARRAY)))
+    {
+        Some(Value::Integer(size)) => *size as usize,
+        _ => MAX_LARGE_BLOB_SIZE,
+    }
+}
+
+/// Reads the raw array. Returns the entries, the serialized size and whether the trailer matched.
+fn read_array(transport: &HidTransport) -> Result<(Vec<Value>, usize, bool), PFError> {
+    let mut data = Vec::new();
+    loop {
+        let fragment = transport.send_large_blobs_get(data.len(), MAX_FRAGMENT_LENGTH)?;
+        let len = fragment.len();
+        data.extend(fragment);
+        if len < MAX_FRAGMENT_LENGTH {
+            break;
+        }
+    }
+
+    if data.len() <= TRAILER_LEN {
+        log::warn!("Large blob array is shorter than its trailer");
+        return Ok((Vec::new(), data.len(), false));
+    }
+
+    let (array_bytes, stored_trailer) = data.split_at(data.len() - TRAILER_LEN);
+    if trailer(array_bytes) != stored_trailer {
+        log::warn!("Large blob array failed the integrity check");
+        return Ok((Vec::new(), data.len(), false));
+    }
+
+    match from_slice(array_bytes) {
+        Ok(Value::Array(entries)) => Ok((entries, data.len(), true)),
+        _ => {
+            log::warn!("Large blob array is not a CBOR array");
+            Ok((Vec::new(), data.len(), false))
+        }
+    }
+}
+
+fn write_array(
+    transport: &HidTransport,
+    pin_token: &[u8],
+    entries: Vec<Value>,
+) -> Result<(), PFError> {
+    let mut data = to_vec(&Value::Array(entries)).map_err(|e| PFError::Io(e.to_string()))?;
+    data.extend(trailer(&data));
+
+    let max = read_max_size(transport);
+    if data.len() > max {
+        return Err(PFError::Device(format!(
+            "Large blob array needs {} bytes but the device only holds {}",
+            data.len(),
+            max
+        )));
+    }
+
+    for (i, fragment) in data.chunks(MAX_FRAGMENT_LENGTH).enumerate() {
+        let offset = i * MAX_FRAGMENT_LENGTH;
+        let total_length = (offset == 0).then_some(data.len());
+        transport.send_large_blobs_set(pin_token, offset, fragment, total_length)?;
+    }
+
+    log::info!("Wrote large blob array ({} bytes)", data.len());
+    Ok(())
+}
+
+fn decrypt_entry(entry: &BTreeMap<Value, Value>, key: &[u8]) -> Option<Vec<u8>> {
+    let ciphertext = match entry.get(&Value::Integer(LargeBlobEntryKey::Ciphertext as i128)) {
+        Some(Value::Bytes(b)) => b,
+        _ => return None,
+    };
+    let nonce = match entry.get(&Value::Integer(LargeBlobEntryKey::Nonce as i128)) {
+        Some(Value::Bytes(b)) => b,
+        _ => return None,
+    };
+    let orig_size = match entry.get(&Value::Integer(LargeBlobEntryKey::OrigSize as i128)) {
+        Some(Value::Integer(i)) => *i as u64,
+        _ => return None,
+    };
+
+    let mut aad = b"blob".to_vec();
+    aad.extend(orig_size.to_le_bytes());
+
+    let key = aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_256_GCM, key).ok()?);
+    let nonce = aead::Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut in_out = ciphertext.clone();
+    let compressed = key
+        .open_in_place(nonce, aead::Aad::from(aad), &mut in_out)
+        .ok()?;
+
+    let mut data = Vec::with_capacity(orig_size as usize);
+    flate2::read::DeflateDecoder::new(&compressed[..])
+        .read_to_end(&mut data)
+        .ok()?;
+    Some(data)
+}
+
+/// Identifies an entry by the SHA-256 of its ciphertext and nonce, so a delete cannot hit a
+/// different entry after the array was rewritten. Malformed entries hash their CBOR encoding.
+fn fingerprint(entry: &Value) -> String {
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    let field = |key: LargeBlobEntryKey| match entry {
+        Value::Map(map) => match map.get(&Value::Integer(key as i128)) {
+            Some(Value::Bytes(b)) => Some(b.as_slice()),
+            _ => None,
+        },
+        _ => None,
+    };
+    match (
+        field(LargeBlobEntryKey::Ciphertext),
+        field(LargeBlobEntryKey::Nonce),
+    ) {
+        (Some(ciphertext), Some(nonce)) => {
+            ctx.update(ciphertext);
+            ctx.update(nonce);
+        }
+        _ => ctx.update(&to_vec(entry).unwrap_or_default()),
+    }
+    hex::encode(ctx.finish())
+}
+
+fn describe_entry(
+    index: usize,
+    entry: &Value,
+    credentials: &[ResidentCredential],
+) -> LargeBlobEntry {
+    let Value::Map(map) = entry else {
+        return LargeBlobEntry {
+            index,
+            fingerprint: fingerprint(entry),
+            stored_size: 0,
+            orig_size: 0,
+            rp_id: None,
+            user_name: None,
+            credential_id: None,
+            data: None,
+        };
+    };
+
+    let stored_size = match map.get(&Value::Integer(LargeBlobEntryKey::Ciphertext as i128)) {
+        Some(Value::Bytes(b)) => b.len(),
+        _ => 0,
+    };
+    let orig_size = match map.get(&Value::Integer(LargeBlobEntryKey::OrigSize as i128)) {
+        Some(Value::Integer(i)) => *i as u64,
+        _ => 0,
+    };
+
+    let owner = credentials.iter().find_map(|cred| {
+        let key = cred.large_blob_key.as_ref()?;
+        decrypt_entry(map, key).map(|data| (cred, data))
+    });
+
+    LargeBlobEntry {
+        index,
+        fingerprint: fingerprint(entry),
+        stored_size,
+        orig_size,
+        rp_id: owner.as_ref().map(|(c, _)| c.rp_id.clone()),
+        user_name: owner.as_ref().map(|(c, _)| c.user_name.clone()),
+        credential_id: owner.as_ref().map(|(c, _)| hex::encode(&c.credential_id)),
+        data: owner.map(|(_, data)| data),
+    }
+}
+
+/// Reads and decrypts the large-blob array. The PIN is needed to learn the credentials' keys.
+pub(crate) fn read_storage(pin: String) -> Result<LargeBlobStorage, PFError> {
+    log::info!("Reading large blob storage...");
+
+    let pin_token = credentials::pin_token(&pin, Permission::CredentialManagement)?;
+    let transport = HidTransport::open()?;
+    let credentials = credentials::enumerate(&transport, &pin_token)?;
+
+    let (entries, used_bytes, integrity_ok) = read_array(&transport)?;
+    let max_bytes = read_max_size(&transport);
+
+    let entries: Vec<LargeBlobEntry> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| describe_entry(i, entry, &credentials))
+        .collect();
+
+    log::info!(
+        "Large blob storage: {} entries, {}/{} bytes",
+        entries.len(),
+        used_bytes,
+        max_bytes
+    );
+
+    Ok(LargeBlobStorage {
+        entries,
+        used_bytes,
+        max_bytes,
+        integrity_ok,
+    })
+}
+
+/// Removes the entry with the given [`fingerprint`] and writes the remainder back.
+pub(crate) fn delete_entry(pin: String, fingerprint_hex: &str) -> Result<String, PFError> {
+    log::info!("Deleting large blob entry {}...", fingerprint_hex);
+
+    let pin_token = credentials::pin_token(&pin, Permission::LargeBlobWrite)?;
+    let transport = HidTransport::open()?;
+
+    let (mut entries, _, integrity_ok) = read_array(&transport)?;
+    if !integrity_ok {
+        return Err(PFError::Device(
+            "Large blob array is corrupted, clear it instead".into(),
+        ));
+    }
+    let Some(index) = entries
+        .iter()
+        .position(|entry| fingerprint(entry) == fingerprint_hex)
+    else {
+        return Err(PFError::Device(
+            "Large blob entry no longer exists, reload the list".into(),
+        ));
+    };
+
+    entries.remove(index);
+    write_array(&transport, &pin_token, entries)?;
+    Ok("Large blob deleted".into())
+}
+
+/// Replaces the array with an empty one.
+pub(crate) fn clear(pin: String) -> Result<String, PFError> {
+    log::info!("Clearing large blob storage...");
+
+    let pin_token = credentials::pin_token(&pin, Permission::LargeBlobWrite)?;
+    let transport = HidTransport::open()?;
+    write_array(&transport, &pin_token, Vec::new())?;
+    Ok("Large blob storage cleared".into())
+}
+
+pub(crate) fn export_entry(path: &Path, data: &[u8]) -> Result<(), PFError> {
+    std::fs::write(path, data)
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    log::info!("Large blob exported to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,379 @@
+//! Offline lookup in a FIDO Metadata Service (MDS3) BLOB.
+//!
+//! The BLOB is a JWT downloaded by the user from the FIDO Alliance. Its signature is checked with
+//! the leaf of the `x5c` header chain, every link of that chain is verified, each issuer must be a
+//! CA inside its validity period, and the chain must end in the MDS root: the bundled GlobalSign
+//! Root CA - R3, or a root the user placed in `mds_root.pem` in the data directory (for example the
+//! conformance test root). A BLOB that passes is copied to the data directory so later lookups
+//! work without the file; anything else is rejected.
+
+use super::attestation;
+use super::cose;
+use crate::device::{
+    error::PFError,
+    types::{AuthenticatorMetadata, MetadataBlobInfo, MetadataIcon, MetadataLookup},
+};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use directories::ProjectDirs;
+use ring::signature;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use x509_parser::{certificate::X509Certificate, pem::Pem};
+
+/// GlobalSign Root CA - R3, the root of the production FIDO MDS3 signing chain.
+/// SHA-256 fingerprint:
+/// CB:B5:22:D7:B7:F1:27:AD:6A:01:13:86:5B:DF:1C:D4:10:2E:7D:07:59:AF:63:5A:7C:F4:72:0D:C9:63:C5:3B.
+const MDS_ROOT: &str = include_str!("../../../static/mds/globalsign_root_r3.pem");
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    x5c: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlobPayload {
+    no: u64,
+    next_update: String,
+    #[serde(default)]
+    entries: Vec<BlobEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlobEntry {
+    aaguid: Option<String>,
+    metadata_statement: Option<MetadataStatement>,
+    #[serde(default)]
+    status_reports: Vec<StatusReport>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataStatement {
+    #[serde(default)]
+    description: String,
+    authenticator_version: Option<u64>,
+    icon: Option<String>,
+    #[serde(default)]
+    attestation_root_certificates: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusReport {
+    status: String,
+    effective_date: Option<String>,
+}
+
+fn data_dir() -> PathBuf {
+    ProjectDirs::from("in", "suyogtandel", "picoforge")
+        .map(|dirs| dirs.data_local_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn stored_blob_path() -> PathBuf {
+    data_dir().join("mds3_blob.jwt")
+}
+
+fn root_path() -> PathBuf {
+    data_dir().join("mds_root.pem")
+}
+
+/// Whether a verified BLOB has been imported before.
+pub(crate) fn has_stored_blob() -> bool {
+    stored_blob_path().exists()
+}
+
+fn decode_segment(segment: &str, what: &str) -> Result<Vec<u8>, PFError> {
+    URL_SAFE_NO_PAD
+        .decode(segment.trim_end_matches('='))
+        .map_err(|e| PFError::Io(format!("BLOB {} is not valid base64url: {}", what, e)))
+}
+
+/// DER encodings of the bundled MDS root and any the user added.
+fn load_roots() -> Vec<Vec<u8>> {
+    let mut data = MDS_ROOT.as_bytes().to_vec();
+    if let Ok(user) = std::fs::read(root_path()) {
+        log::debug!("Loading extra MDS roots from {}", root_path().display());
+        data.push(b'\n');
+        data.extend(user);
+    }
+    Pem::iter_from_buffer(&data)
+        .filter_map(Result::ok)
+        .filter(|pem| pem.label == "CERTIFICATE")
+        .map(|pem| pem.contents)
+        .collect()
+}
+
+/// Finds the root `chain` ends in and returns its subject.
+fn find_anchor(chain: &[X509Certificate], roots: &[Vec<u8>]) -> Result<String, PFError> {
+    let last = chain
+        .last()
+        .ok_or_else(|| PFError::Io("BLOB header has no signing certificate".into()))?;
+    for root_der in roots {
+        let Ok((_, root)) = x509_parser::parse_x509_certificate(root_der) else {
+            log::warn!("Skipping an MDS root that is not a valid certificate");
+            continue;
+        };
+        if last.public_key().raw == root.public_key().raw {
+            return Ok(root.subject().to_string());
+        }
+        if last.issuer() == root.subject() && last.verify_signature(Some(root.public_key())).is_ok()
+        {
+            attestation::check_issuer(&root, chain.len() - 1).map_err(PFError::Io)?;
+            return Ok(root.subject().to_string());
+        }
+    }
+    Err(PFError::Io(format!(
+        "BLOB signing chain does not end in the FIDO MDS root (issuer {})",
+        last.issuer()
+    )))
+}
+
+/// Verifies the JWT signature and signing chain and returns the decoded payload.
+fn verify_blob(jwt: &str) -> Result<(BlobPayload, MetadataBlobInfo), PFError> {
+    let jwt = jwt.trim();
+    let mut parts = jwt.split('.');
+    let (Some(header_b64), Some(payload_b64), Some(signature_b64), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(PFError::Io("File is not a JWT".into()));
+    };
+
+    let header: JwtHeader = serde_json::from_slice(&decode_segment(header_b64, "header")?)
+        .map_err(|e| PFError::Io(format!("Invalid BLOB header: {}", e)))?;
+    let signature_bytes = decode_segment(signature_b64, "signature")?;
+
+    let chain_der = header
+        .x5c
+        .iter()
+        .map(|c| STANDARD.decode(c))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| PFError::Io(format!("Invalid certificate in BLOB header: {}", e)))?;
+    let mut chain = Vec::new();
+    for der in &chain_der {
+        let (_, cert) = x509_parser::parse_x509_certificate(der)
+            .map_err(|e| PFError::Io(format!("Invalid certificate in BLOB header: {}", e)))?;
+        chain.push(cert);
+    }
+    let leaf = chain
+        .first()
+        .ok_or_else(|| PFError::Io("BLOB header has no signing certificate".into()))?;
+
+    if !leaf.validity().is_valid() {
+        return Err(PFError::Io(format!(
+            "Signing certificate {} is outside its validity period",
+            leaf.subject()
+        )));
+    }
+    for pair in chain.windows(2) {
+        if pair[0]
+            .verify_signature(Some(pair[1].public_key()))
+            .is_err()
+        {
+            return Err(PFError::Io(format!(
+                "{} is not signed by {}",
+                pair[0].subject(),
+                pair[1].subject()
+            )));
+        }
+    }
+    for (i, cert) in chain.iter().enumerate().skip(1) {
+        attestation::check_issuer(cert, i - 1).map_err(PFError::Io)?;
+    }
+    let root = find_anchor(&chain, &load_roots())?;
+
+    let algorithm: &dyn signature::VerificationAlgorithm = match header.alg.as_str() {
+        "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+        "ES256" => &signature::ECDSA_P256_SHA256_FIXED,
+        "ES384" => &signature::ECDSA_P384_SHA384_FIXED,
+        other => {
+            return Err(PFError::Io(format!(
+                "Unsupported BLOB signature algorithm {}",
+                other
+            )));
+        }
+    };
+    let public_key = cose::certificate_public_key(&chain_der[0])
+        .ok_or_else(|| PFError::Io("Unreadable signing key".into()))?;
+    let signed = &jwt[..header_b64.len() + 1 + payload_b64.len()];
+    signature::UnparsedPublicKey::new(algorithm, public_key)
+        .verify(signed.as_bytes(), &signature_bytes)
+        .map_err(|_| PFError::Io("BLOB signature does not match its certificate".into()))?;
+
+    let payload: BlobPayload = serde_json::from_slice(&decode_segment(payload_b64, "payload")?)
+        .map_err(|e| PFError::Io(format!("Invalid BLOB payload: {}", e)))?;
+
+    let info = MetadataBlobInfo {
+        number: payload.no,
+        next_update: payload.next_update.clone(),
+        entries: payload.entries.len(),
+        signer: leaf.subject().to_string(),
+    };
+    log::info!(
+        "MDS BLOB #{} verified ({} entries, signed by {}, root {})",
+        info.number,
+        info.entries,
+        info.signer,
+        root
+    );
+    Ok((payload, info))
+}
+
+fn normalize_aaguid(aaguid: &str) -> String {
+    aaguid.replace('-', "").to_ascii_lowercase()
+}
+
+/// Turns `FIDO_CERTIFIED_L1plus` into `FIDO Certified L1+`.
+fn status_label(status: &str) -> String {
+    status
+        .split('_')
+        .map(|word| match word {
+            "FIDO" => word.to_string(),
+            w if w.starts_with('L') && w[1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                w.replace("plus", "+")
+            }
+            w => {
+                let lower = w.to_ascii_lowercase();
+                let mut chars = lower.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_certification_status(status: &str) -> bool {
+    status.starts_with("FIDO_CERTIFIED") || status == "NOT_FIDO_CERTIFIED" || status == "REVOKED"
+}
+
+fn decode_icon(icon: &str) -> Option<MetadataIcon> {
+    let rest = icon.strip_prefix("data:")?;
+    let (mime_type, data) = rest.split_once(";base64,")?;
+    Some(MetadataIcon {
+        mime_type: mime_type.to_string(),
+        data: STANDARD.decode(data).ok()?,
+    })
+}
+
+fn to_metadata(aaguid: &str, entry: &BlobEntry) -> AuthenticatorMetadata {
+    let mut reports: Vec<&StatusReport> = entry.status_reports.iter().collect();
+    // ISO 8601 dates sort correctly as strings; reports without a date go first.
+    reports.sort_by(|a, b| a.effective_date.cmp(&b.effective_date));
+
+    let latest = reports.last();
+    let certification = reports
+        .iter()
+        .rev()
+        .find(|r| is_certification_status(&r.status))
+        .map(|r| status_label(&r.status))
+        .unwrap_or_else(|| "Not FIDO Certified".to_string());
+
+    let statement = entry.metadata_statement.as_ref();
+    let attestation_roots = statement
+        .map(|s| {
+            s.attestation_root_certificates
+                .iter()
+                .filter_map(|c| STANDARD.decode(c).ok())
+                .filter_map(|der| {
+                    x509_parser::parse_x509_certificate(&der)
+                        .ok()
+                        .map(|(_, cert)| attestation::describe(&cert))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    AuthenticatorMetadata {
+        aaguid: aaguid.to_string(),
+        description: statement.map(|s| s.description.clone()).unwrap_or_default(),
+        authenticator_version: statement.and_then(|s| s.authenticator_version),
+        certification,
+        latest_status: latest.map(|r| status_label(&r.status)).unwrap_or_default(),
+        status_date: latest.and_then(|r| r.effective_date.clone()),
+        icon: statement
+            .and_then(|s| s.icon.as_deref())
+            .and_then(decode_icon),
+        attestation_roots,
+    }
+}
+
+fn find_entry<'a>(payload: &'a BlobPayload, aaguid: &str) -> Option<&'a BlobEntry> {
+    let wanted = normalize_aaguid(aaguid);
+    payload.entries.iter().find(|e| {
+        e.aaguid
+            .as_deref()
+            .is_some_and(|a| normalize_aaguid(a) == wanted)
+    })
+}
+
+fn lookup_in(jwt: &str, aaguid: &str) -> Result<MetadataLookup, PFError> {
+    let (payload, blob) = verify_blob(jwt)?;
+    let entry =
+        find_entry(&payload, aaguid).map(|e| to_metadata(e.aaguid.as_deref().unwrap_or(aaguid), e));
+
+    if entry.is_none() {
+        log::info!("AAGUID {} is not listed in the MDS BLOB", aaguid);
+    }
+    Ok(MetadataLookup {
+        aaguid: aaguid.to_string(),
+        blob,
+        entry,
+    })
+}
+
+/// Verifies the BLOB at `path`, stores it for later lookups and looks up `aaguid` in it.
+pub(crate) fn import_blob(path: &Path, aaguid: &str) -> Result<MetadataLookup, PFError> {
+    let jwt = std::fs::read_to_string(path)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let lookup = lookup_in(&jwt, aaguid)?;
+
+    let stored = stored_blob_path();
+    if let Some(dir) = stored.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| PFError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    std::fs::write(&stored, jwt.trim())
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", stored.display(), e)))?;
+    log::info!("MDS BLOB stored at {}", stored.display());
+
+    Ok(lookup)
+}
+
+/// Looks up `aaguid` in the previously imported BLOB, verifying it again.
+pub(crate) fn lookup(aaguid: &str) -> Result<MetadataLookup, PFError> {
+    let stored = stored_blob_path();
+    let jwt = std::fs::read_to_string(&stored)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", stored.display(), e)))?;
+    lookup_in(&jwt, aaguid)
+}
+
+/// DER attestation roots the stored BLOB lists for `aaguid`, empty when there is no usable BLOB.
+pub(crate) fn attestation_roots(aaguid: &str) -> Vec<Vec<u8>> {
+    let Ok(jwt) = std::fs::read_to_string(stored_blob_path()) else {
+        return Vec::new();
+    };
+    let payload = match verify_blob(&jwt) {
+        Ok((payload, _)) => payload,
+        Err(e) => {
+            log::warn!("Stored MDS BLOB not used for attestation roots: {}", e);
+            return Vec::new();
+        }
+    };
+    find_entry(&payload, aaguid)
+        .and_then(|e| e.metadata_statement.as_ref())
+        .map(|s| {
+            s.attestation_root_certificates
+                .iter()
+                .filter_map(|c| STANDARD.decode(c).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -1,28 +1,53 @@
+pub mod algorithm_probe;
+pub mod attestation;
+pub mod auth_encryption;
+pub mod backup;
 pub mod constants;
+pub mod cose;
+pub mod credentials;
+pub mod get_info;
 pub mod hid;
+pub mod hmac_secret;
+pub mod large_blob;
+pub mod metadata;
+pub mod physical;
+pub mod pin_policy;
+pub mod selection;
+pub mod self_test;
+pub mod ssh;
+pub mod webauthn;
 
 use crate::{
     device::error::PFError,
     device::types::{
-        AppConfig, AppConfigInput, DeviceInfo, DeviceMethod, FidoDeviceInfo, FullDeviceStatus,
-        StoredCredential,
+        AppConfigInput, ConfigWriteReport, DeviceInfo, DeviceMethod, FidoDeviceInfo, FieldSource,
+        FlashField, FullDeviceStatus, StatusProvenance, StoredCredential,
     },
 };
 use constants::*;
 use ctap_hid_fido2::{
-    Cfg, FidoKeyHidFactory,
+    Cfg, FidoKeyHidFactory, HidParam,
     fidokey::{FidoKeyHid, pin::Permission},
     public_key_credential_descriptor::PublicKeyCredentialDescriptor,
 };
 use hid::*;
 use serde_cbor_2::{Value, from_slice, to_vec};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 
 // Fido functions that require pin: ( Uses ctap_hid_fido2 crate)
 
 fn get_device() -> Result<FidoKeyHid, String> {
     let cfg = Cfg::init();
-    FidoKeyHidFactory::create(&cfg).map_err(|e| {
+    // The library refuses to pick when several keys are attached, so point it at the one the
+    // user selected by touch.
+    let device = match hid::active_device() {
+        Some(path) => FidoKeyHidFactory::create_by_params(
+            &[HidParam::Path(path.to_string_lossy().into_owned())],
+            &cfg,
+        ),
+        None => FidoKeyHidFactory::create(&cfg),
+    };
+    device.map_err(|e| {
         format!(
             "Could not connect to FIDO device. Is it plugged in? Error: {:?}",
             e
@@ -31,28 +56,14 @@ fn get_device() -> Result<FidoKeyHid, String> {
 }
 
 pub(crate) fn get_fido_info() -> Result<FidoDeviceInfo, String> {
-    let device = get_device()?;
+    let transport = HidTransport::open().map_err(|e| {
+        format!(
+            "Could not connect to FIDO device. Is it plugged in? Error: {}",
+            e
+        )
+    })?;
 
-    let info = device
-        .get_info()
-        .map_err(|e| format!("Error reading device info: {:?}", e))?;
-
-    let options_map: HashMap<String, bool> = info.options.into_iter().collect();
-
-    Ok(FidoDeviceInfo {
-        versions: info.versions,
-        extensions: info.extensions,
-        aaguid: hex::encode_upper(info.aaguid),
-        options: options_map,
-        max_msg_size: info.max_msg_size,
-        pin_protocols: info.pin_uv_auth_protocols,
-        min_pin_length: info.min_pin_length,
-        firmware_version: format!(
-            "{}.{}",
-            (info.firmware_version >> 8) & 0xFF,
-            info.firmware_version & 0xFF
-        ),
-    })
+    read_device_info(&transport).map_err(|e| format!("Error reading device info: {}", e))
 }
 
 pub(crate) fn change_fido_pin(
@@ -119,46 +130,17 @@ pub(crate) fn set_min_pin_length(
 }
 
 pub(crate) fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, String> {
-    let device = get_device()?;
+    // Enumerated over our own transport: the library drops the public key, credProtect and
+    // largeBlobKey fields of the enumerateCredentials response.
+    let pin_token = credentials::pin_token(&pin, Permission::CredentialManagement)
+        .map_err(|e| e.to_string())?;
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
 
-    let rps = match device.credential_management_enumerate_rps(Some(&pin)) {
-        Ok(rps) => rps,
-        Err(e) => {
-            // CTAP2_ERR_NO_CREDENTIALS (0x2E) means no credentials exist - return empty list
-            let err_str = format!("{:?}", e);
-            if err_str.contains("0x2E") || err_str.contains("NO_CREDENTIALS") {
-                log::info!("No credentials stored on device (CTAP2_ERR_NO_CREDENTIALS)");
-                return Ok(Vec::new());
-            }
-            return Err(format!("Failed to enumerate Relying Parties: {:?}", e));
-        }
-    };
+    let resident = credentials::enumerate(&transport, &pin_token)
+        .map_err(|e| format!("Failed to enumerate credentials: {}", e))?;
 
-    let mut all_credentials = Vec::new();
-
-    for rp in rps {
-        let creds = device
-            .credential_management_enumerate_credentials(Some(&pin), &rp.rpid_hash)
-            .map_err(|e| {
-                format!(
-                    "Failed to enumerate credentials for RP {}: {:?}",
-                    rp.public_key_credential_rp_entity.id, e
-                )
-            })?;
-
-        for cred in creds {
-            all_credentials.push(StoredCredential {
-                credential_id: hex::encode(&cred.public_key_credential_descriptor.id),
-                rp_id: rp.public_key_credential_rp_entity.id.clone(),
-                rp_name: rp.public_key_credential_rp_entity.name.clone(),
-                user_name: cred.public_key_credential_user_entity.name.clone(),
-                user_display_name: cred.public_key_credential_user_entity.display_name.clone(),
-                user_id: hex::encode(&cred.public_key_credential_user_entity.id).clone(),
-            });
-        }
-    }
-
-    Ok(all_credentials)
+    Ok(resident.iter().map(|c| c.to_stored()).collect())
 }
 
 pub(crate) fn delete_credential(pin: String, credential_id_hex: String) -> Result<String, String> {
@@ -193,40 +175,73 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
         }
     })?;
 
-    let (aaguid_str, fw_version) = read_device_info(&transport)?;
+    let info = read_device_info(&transport)?;
 
     log::info!(
         "Device identified: AAGUID={}, FW={}",
-        aaguid_str,
-        fw_version
+        info.aaguid,
+        info.firmware_version
     );
 
-    let (used, total) = read_memory_stats(&transport)?;
+    let memory = read_memory_stats(&transport)?;
     log::debug!(
-        "Memory Stats: Used={}KB, Total={}KB",
-        used / 1024,
-        total / 1024
+        "Memory Stats: Used={:?}B, Total={:?}B, Free={:?}B, Files={:?}, Flash={:?}B",
+        memory.used,
+        memory.total,
+        memory.free,
+        memory.files,
+        memory.flash_size
     );
 
-    let config = read_physical_config(&transport)?;
+    let mut unknown_fields = Vec::new();
+    let mut figure = |value: Option<u32>, field: FlashField, scale: u32| {
+        value.map(|v| v / scale).unwrap_or_else(|| {
+            unknown_fields.push(field);
+            0
+        })
+    };
+    let flash_used = figure(memory.used, FlashField::Used, 1024);
+    let flash_total = figure(memory.total, FlashField::Total, 1024);
+    let flash_free = figure(memory.free, FlashField::Free, 1024);
+    let flash_chip_size = figure(memory.flash_size, FlashField::ChipSize, 1024);
+    let file_count = figure(memory.files, FlashField::FileCount, 1);
+    if !unknown_fields.is_empty() {
+        log::warn!("Memory stats do not report: {:?}", unknown_fields);
+    }
+
+    let config = physical::read(&transport)?;
 
     log::info!("Successfully read all device details.");
 
     Ok(FullDeviceStatus {
         info: DeviceInfo {
             serial: "?".to_string(), // Serial number is not available through fido
-            flash_used: used / 1024,
-            flash_total: total / 1024,
-            firmware_version: fw_version,
+            flash_used,
+            flash_total,
+            flash_free,
+            flash_chip_size,
+            file_count,
+            firmware_version: info.firmware_version.clone(),
+            unknown_fields,
         },
         config,
+        // Secure boot state is only readable through the rescue interface.
         secure_boot: false,
         secure_lock: false,
         method: DeviceMethod::Fido,
+        fido: Some(info),
+        provenance: StatusProvenance {
+            serial: FieldSource::Unverified,
+            firmware_version: FieldSource::Fido,
+            flash: FieldSource::Fido,
+            config: FieldSource::Fido,
+            secure_boot: FieldSource::Unverified,
+            fido: FieldSource::Fido,
+        },
     })
 }
 
-fn read_device_info(transport: &HidTransport) -> Result<(String, String), PFError> {
+fn read_device_info(transport: &HidTransport) -> Result<FidoDeviceInfo, PFError> {
     log::debug!("Sending GetInfo command (0x04)...");
     let info_payload = [CtapCommand::GetInfo as u8];
     let info_res = transport
@@ -238,50 +253,24 @@ fn read_device_info(transport: &HidTransport) -> Result<(String, String), PFErro
 
     log::debug!("GetInfo response received ({} bytes)", info_res.len());
 
-    let info_val: Value = from_slice(&info_res).map_err(|e| {
+    let info_map: BTreeMap<Value, Value> = from_slice(&info_res).map_err(|e| {
         log::error!("Failed to parse GetInfo CBOR: {}", e);
         PFError::Io(e.to_string())
     })?;
 
-    // NOTE: Key 0x03 is AAGUID, not the unique device Serial.
-    let aaguid_str = if let Value::Map(m) = &info_val {
-        m.get(&Value::Integer(0x03))
-            .and_then(|v| {
-                if let Value::Bytes(b) = v {
-                    Some(hex::encode_upper(b))
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| {
-                log::warn!("AAGUID not found in GetInfo response");
-                "Unknown".into()
-            })
-    } else {
-        "Unknown".into()
-    };
-
-    let fw_version = if let Value::Map(m) = &info_val {
-        m.get(&Value::Integer(0x0E))
-            .and_then(|v| {
-                if let Value::Integer(i) = v {
-                    Some(format!("{}.{}", (i >> 8) & 0xFF, i & 0xFF))
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| {
-                log::warn!("Firmware version not found in GetInfo response");
-                "Unknown".into()
-            })
-    } else {
-        "Unknown".into()
-    };
+    Ok(get_info::decode(&info_map))
+}
 
-    Ok((aaguid_str, fw_version))
+/// Memory vendor command response, sizes in bytes. Fields the firmware left out are `None`.
+struct MemoryStats {
+    free: Option<u32>,
+    used: Option<u32>,
+    total: Option<u32>,
+    files: Option<u32>,
+    flash_size: Option<u32>,
 }
 
-fn read_memory_stats(transport: &HidTransport) -> Result<(u32, u32), PFError> {
+fn read_memory_stats(transport: &HidTransport) -> Result<MemoryStats, PFError> {
     log::debug!("Preparing Memory Stats vendor command...");
 
     let mut mem_req = BTreeMap::new();
@@ -315,74 +304,25 @@ fn read_memory_stats(transport: &HidTransport) -> Result<(u32, u32), PFError> {
         BTreeMap::new()
     };
 
-    let used = mem_map
-        .get(&(MemoryResponseKey::UsedSpace as i128))
-        .cloned()
-        .unwrap_or(0) as u32;
-    let total = mem_map
-        .get(&(MemoryResponseKey::TotalSpace as i128))
-        .cloned()
-        .unwrap_or(0) as u32;
-
-    Ok((used, total))
-}
-
-fn read_physical_config(transport: &HidTransport) -> Result<AppConfig, PFError> {
-    log::debug!("Preparing Physical Config vendor command...");
-
-    // FIX: Only arguments in CBOR map
-    let mut phy_params = BTreeMap::new();
-    phy_params.insert(
-        Value::Integer(1), // Sub-command key
-        Value::Integer(PhysicalOptionsSubCommand::GetOptions as i128),
-    );
-
-    let phy_cbor = to_vec(&Value::Map(phy_params)).map_err(|e| {
-        log::error!("Failed to encode Physical Config CBOR: {}", e);
-        PFError::Io(format!("CBOR encode error: {}", e))
-    })?;
-
-    let mut phy_payload = vec![VendorCommand::PhysicalOptions as u8];
-    phy_payload.extend(phy_cbor);
-
-    log::debug!("Sending Physical Config command...");
-    let phy_res = transport
-        .send_cbor(CTAP_VENDOR_CBOR_CMD, &phy_payload)
-        .unwrap_or_else(|e| {
-            log::warn!("Failed to fetch physical config (Vendor Cmd): {}", e);
-            Vec::new()
-        });
-
-    let mut config = AppConfig {
-        vid: format!("{:04X}", transport.vid),
-        pid: format!("{:04X}", transport.pid),
-        product_name: transport.product_name.clone(),
-        ..Default::default()
+    let get = |key: MemoryResponseKey| {
+        mem_map
+            .get(&(key as i128))
+            .and_then(|v| u32::try_from(*v).ok())
     };
 
-    if let Ok(Value::Map(m)) = from_slice(&phy_res) {
-        log::debug!("Parsed Physical Config map successfully");
-        if let Some(Value::Integer(v)) = m.get(&Value::Text("gpio".into())) {
-            config.led_gpio = *v as u8;
-        } else {
-            log::warn!("No led_gpio in CBOR map");
-        }
-
-        if let Some(Value::Integer(v)) = m.get(&Value::Text("brightness".into())) {
-            config.led_brightness = *v as u8;
-        } else {
-            log::warn!("No led_brightness in CBOR map");
-        }
-    } else if !phy_res.is_empty() {
-        log::warn!("Physical config response was not a valid CBOR map");
-    } else {
-        log::debug!("Physical config response was empty or already handled.");
-    }
-
-    Ok(config)
+    Ok(MemoryStats {
+        free: get(MemoryResponseKey::FreeSpace),
+        used: get(MemoryResponseKey::UsedSpace),
+        total: get(MemoryResponseKey::TotalSpace),
+        files: get(MemoryResponseKey::NumFiles),
+        flash_size: get(MemoryResponseKey::FlashSize),
+    })
 }
 
-pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<String, PFError> {
+pub fn write_config(
+    config: AppConfigInput,
+    pin: Option<String>,
+) -> Result<ConfigWriteReport, PFError> {
     log::info!("Starting FIDO write_config...");
 
     let pin_val = pin.as_deref().ok_or_else(|| {
@@ -427,76 +367,15 @@ pub fn write_config(config: AppConfigInput, pin: Option<String>) -> Result<Strin
         PFError::Device(format!("Could not open HID transport: {}", e))
     })?;
 
-    // VID/PID config
-    if let (Some(vid_str), Some(pid_str)) = (&config.vid, &config.pid) {
-        let vid = u16::from_str_radix(vid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
-        let pid = u16::from_str_radix(pid_str, 16).map_err(|e| PFError::Io(e.to_string()))?;
-        let vidpid = ((vid as u32) << 16) | (pid as u32);
-        transport.send_vendor_config(
-            &pin_token,
-            VendorConfigCommand::PhysicalVidPid,
-            Value::Integer(vidpid as i128),
-        )?;
-    } else {
-        log::info!("VID/PID configuration not provided, skipping update.");
-    }
-
-    // LED GPIO config
-    if let Some(gpio) = config.led_gpio {
-        transport.send_vendor_config(
-            &pin_token,
-            VendorConfigCommand::PhysicalLedGpio,
-            Value::Integer(gpio as i128),
-        )?;
-    } else {
-        log::info!("LED GPIO configuration not provided, skipping update.");
-    }
-
-    // LED brightness config
-    if let Some(brightness) = config.led_brightness {
-        transport.send_vendor_config(
-            &pin_token,
-            VendorConfigCommand::PhysicalLedBrightness,
-            Value::Integer(brightness as i128),
-        )?;
-    } else {
-        log::info!("LED brightness configuration not provided, skipping update.");
-    }
-
-    // Options config
-    let mut opts = 0u16;
-    if config.led_dimmable.unwrap_or(false) {
-        opts |= 0x02; // PHY_OPT_DIMM
-    }
-    if !config.power_cycle_on_reset.unwrap_or(true) {
-        opts |= 0x04; // PHY_OPT_DISABLE_POWER_RESET
-    }
-    if config.led_steady.unwrap_or(false) {
-        opts |= 0x08; // PHY_OPT_LED_STEADY
-    }
-    // Touch_timeout config
-    if let Some(timeout) = config.touch_timeout {
-        transport
-            .send_vendor_config(
-                &pin_token,
-                VendorConfigCommand::PhysicalOptions,
-                Value::Integer(timeout as i128),
-            )
-            .ok();
-    } else {
-        log::info!("Touch timeout configuration not provided, skipping update.");
-    }
-
-    transport.send_vendor_config(
-        &pin_token,
-        VendorConfigCommand::PhysicalOptions,
-        Value::Integer(opts as i128),
-    )?;
-
-    // ToDo : Product name configuration is not implemented in pico-fido firmware (cbor_config.c)?
+    let supported = read_device_info(&transport)
+        .map(|info| info.vendor_prototype_config_commands)
+        .unwrap_or_default();
+    let report = physical::write(&transport, &pin_token, &config, &supported);
+    log::info!(
+        "FIDO write_config finished: {} of {} fields failed",
+        report.failed(),
+        report.results.len()
+    );
 
-    Ok(
-    "Configuration updated successfully! Unplug and re-plug the device to apply VID/PID changes."
-      .to_string(),
-  )
+    Ok(report)
 }
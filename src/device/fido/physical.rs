@@ -0,0 +1,298 @@
+//! The PhysicalOptions vendor command: LED, touch and curve settings over FIDO.
+//!
+//! The response map is keyed by field name (`gpio`, `brightness`, ...) or, on other firmware
+//! builds, by the PHY tag number the rescue interface uses. Fields missing from the response are
+//! listed in `AppConfig::unknown_fields` instead of being silently defaulted.
+//!
+//! Writes go through authenticatorConfig vendor commands, one per field, and each field's outcome
+//! is reported separately.
+
+use super::constants::*;
+use super::hid::HidTransport;
+use crate::device::{
+    error::PFError,
+    types::{AppConfig, AppConfigInput, ConfigField, ConfigWriteReport},
+};
+use serde_cbor_2::{Value, from_slice, to_vec};
+use std::collections::BTreeMap;
+
+fn field<'a>(m: &'a BTreeMap<Value, Value>, name: &str, tag: PhysicalTag) -> Option<&'a Value> {
+    m.get(&Value::Text(name.into()))
+        .or_else(|| m.get(&Value::Integer(tag as i128)))
+}
+
+fn int_field(m: &BTreeMap<Value, Value>, name: &str, tag: PhysicalTag) -> Option<i128> {
+    match field(m, name, tag) {
+        Some(Value::Integer(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn u8_field(m: &BTreeMap<Value, Value>, name: &str, tag: PhysicalTag) -> Option<u8> {
+    int_field(m, name, tag).and_then(|v| u8::try_from(v).ok())
+}
+
+/// Fills `config` from a PhysicalOptions response map.
+pub(crate) fn decode(m: &BTreeMap<Value, Value>, config: &mut AppConfig) {
+    let mut unknown = Vec::new();
+
+    match u8_field(m, "gpio", PhysicalTag::LedGpio) {
+        Some(v) => config.led_gpio = v,
+        None => unknown.push(ConfigField::LedGpio),
+    }
+    match u8_field(m, "brightness", PhysicalTag::LedBrightness) {
+        Some(v) => config.led_brightness = v,
+        None => unknown.push(ConfigField::LedBrightness),
+    }
+    match u8_field(m, "up_btn", PhysicalTag::PresenceTimeout) {
+        Some(v) => config.touch_timeout = v,
+        None => unknown.push(ConfigField::TouchTimeout),
+    }
+    match u8_field(m, "led_driver", PhysicalTag::LedDriver) {
+        Some(v) => config.led_driver = Some(v),
+        None => unknown.push(ConfigField::LedDriver),
+    }
+
+    match int_field(m, "opts", PhysicalTag::Opts).and_then(|v| u16::try_from(v).ok()) {
+        Some(v) => {
+            let opts = PhysicalOptionFlags::from_bits_truncate(v);
+            config.led_dimmable = opts.contains(PhysicalOptionFlags::LED_DIMMABLE);
+            config.power_cycle_on_reset = !opts.contains(PhysicalOptionFlags::DISABLE_POWER_RESET);
+            config.led_steady = opts.contains(PhysicalOptionFlags::LED_STEADY);
+        }
+        None => unknown.extend([
+            ConfigField::LedDimmable,
+            ConfigField::PowerCycleOnReset,
+            ConfigField::LedSteady,
+        ]),
+    }
+
+    match int_field(m, "curves", PhysicalTag::Curves).and_then(|v| u32::try_from(v).ok()) {
+        Some(v) => {
+            config.enable_secp256k1 =
+                PhysicalCurveFlags::from_bits_truncate(v).contains(PhysicalCurveFlags::SECP256K1);
+        }
+        None => unknown.push(ConfigField::EnableSecp256k1),
+    }
+
+    if let Some(Value::Text(name)) = field(m, "product", PhysicalTag::UsbProduct) {
+        config.product_name = name.trim_matches(char::from(0)).to_string();
+    }
+
+    if !unknown.is_empty() {
+        log::warn!("Physical config does not report: {:?}", unknown);
+    }
+    config.unknown_fields = unknown;
+}
+
+/// Reads the physical configuration. VID, PID and product name come from the USB descriptors
+/// unless the firmware reports them.
+pub(crate) fn read(transport: &HidTransport) -> Result<AppConfig, PFError> {
+    log::debug!("Preparing Physical Config vendor command...");
+
+    let mut phy_params = BTreeMap::new();
+    phy_params.insert(
+        Value::Integer(1), // Sub-command key
+        Value::Integer(PhysicalOptionsSubCommand::GetOptions as i128),
+    );
+
+    let phy_cbor = to_vec(&Value::Map(phy_params)).map_err(|e| {
+        log::error!("Failed to encode Physical Config CBOR: {}", e);
+        PFError::Io(format!("CBOR encode error: {}", e))
+    })?;
+
+    let mut phy_payload = vec![VendorCommand::PhysicalOptions as u8];
+    phy_payload.extend(phy_cbor);
+
+    log::debug!("Sending Physical Config command...");
+    let phy_res = transport
+        .send_cbor(CTAP_VENDOR_CBOR_CMD, &phy_payload)
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to fetch physical config (Vendor Cmd): {}", e);
+            Vec::new()
+        });
+
+    let mut config = AppConfig {
+        vid: format!("{:04X}", transport.vid),
+        pid: format!("{:04X}", transport.pid),
+        product_name: transport.product_name.clone(),
+        ..Default::default()
+    };
+
+    let map = match from_slice(&phy_res) {
+        Ok(Value::Map(m)) => {
+            log::debug!("Parsed Physical Config map successfully");
+            m
+        }
+        _ => {
+            if phy_res.is_empty() {
+                log::debug!("Physical config response was empty.");
+            } else {
+                log::warn!("Physical config response was not a valid CBOR map");
+            }
+            BTreeMap::new()
+        }
+    };
+    decode(&map, &mut config);
+
+    Ok(config)
+}
+
+/// The vendor config command that sets `field`. pico-fido has no command for the product name,
+/// touch timeout, LED driver or curves; those can only be changed through the rescue interface.
+pub(crate) fn command_for(field: ConfigField) -> Option<VendorConfigCommand> {
+    match field {
+        ConfigField::VidPid => Some(VendorConfigCommand::PhysicalVidPid),
+        ConfigField::LedGpio => Some(VendorConfigCommand::PhysicalLedGpio),
+        ConfigField::LedBrightness => Some(VendorConfigCommand::PhysicalLedBrightness),
+        ConfigField::LedDimmable | ConfigField::PowerCycleOnReset | ConfigField::LedSteady => {
+            Some(VendorConfigCommand::PhysicalOptions)
+        }
+        ConfigField::ProductName
+        | ConfigField::TouchTimeout
+        | ConfigField::LedDriver
+        | ConfigField::EnableSecp256k1 => None,
+    }
+}
+
+fn send(
+    transport: &HidTransport,
+    pin_token: &[u8],
+    field: ConfigField,
+    supported: &[u64],
+    param: Result<Value, String>,
+) -> Result<(), String> {
+    let command = command_for(field)
+        .ok_or_else(|| "Not configurable over FIDO, use rescue mode".to_string())?;
+    // An empty list means the firmware does not advertise its commands, so just try.
+    if !supported.is_empty() && !supported.contains(&command.to_u64()) {
+        return Err("Not supported by this firmware".into());
+    }
+    transport
+        .send_vendor_config(pin_token, command, param?)
+        .map_err(|e| e.to_string())
+}
+
+fn vid_pid(vid: &str, pid: &str) -> Result<Value, String> {
+    let vid = u16::from_str_radix(vid, 16).map_err(|_| format!("Invalid VID {}", vid))?;
+    let pid = u16::from_str_radix(pid, 16).map_err(|_| format!("Invalid PID {}", pid))?;
+    Ok(Value::Integer((((vid as u32) << 16) | pid as u32) as i128))
+}
+
+/// Sends every field set in `input`. `supported` is the GetInfo vendorPrototypeConfigCommands list.
+pub(crate) fn write(
+    transport: &HidTransport,
+    pin_token: &[u8],
+    input: &AppConfigInput,
+    supported: &[u64],
+) -> ConfigWriteReport {
+    let mut report = ConfigWriteReport::default();
+    let mut write = |field: ConfigField, param: Result<Value, String>| {
+        let result = send(transport, pin_token, field, supported, param);
+        if let Err(e) = &result {
+            log::warn!("Failed to write {}: {}", field.label(), e);
+        }
+        report.record(field, &result);
+        result
+    };
+
+    if let (Some(vid), Some(pid)) = (&input.vid, &input.pid) {
+        let _ = write(ConfigField::VidPid, vid_pid(vid, pid));
+    }
+    if let Some(name) = &input.product_name {
+        let _ = write(ConfigField::ProductName, Ok(Value::Text(name.clone())));
+    }
+    if let Some(gpio) = input.led_gpio {
+        let _ = write(ConfigField::LedGpio, Ok(Value::Integer(gpio as i128)));
+    }
+    if let Some(brightness) = input.led_brightness {
+        let _ = write(
+            ConfigField::LedBrightness,
+            Ok(Value::Integer(brightness as i128)),
+        );
+    }
+    if let Some(timeout) = input.touch_timeout {
+        let _ = write(
+            ConfigField::TouchTimeout,
+            Ok(Value::Integer(timeout as i128)),
+        );
+    }
+    if let Some(driver) = input.led_driver {
+        let _ = write(ConfigField::LedDriver, Ok(Value::Integer(driver as i128)));
+    }
+    if let Some(enabled) = input.enable_secp256k1 {
+        let curves = if enabled {
+            PhysicalCurveFlags::SECP256K1
+        } else {
+            PhysicalCurveFlags::empty()
+        };
+        let _ = write(
+            ConfigField::EnableSecp256k1,
+            Ok(Value::Integer(curves.bits() as i128)),
+        );
+    }
+
+    // The three option flags share one PHY_OPTS word; flags not being changed keep their
+    // current value. When that value is unknown nothing is written, as a guess would silently
+    // change a setting the user did not touch.
+    let option_fields = [
+        (ConfigField::LedDimmable, input.led_dimmable),
+        (ConfigField::PowerCycleOnReset, input.power_cycle_on_reset),
+        (ConfigField::LedSteady, input.led_steady),
+    ];
+    let changed: Vec<ConfigField> = option_fields
+        .iter()
+        .filter_map(|(field, value)| value.map(|_| *field))
+        .collect();
+    if let Some((&first, rest)) = changed.split_first() {
+        let current = if changed.len() < option_fields.len() {
+            Some(read(transport).map_err(|e| e.to_string()))
+        } else {
+            None
+        };
+        let resolve = |field: ConfigField,
+                       value: Option<bool>,
+                       current_value: fn(&AppConfig) -> bool|
+         -> Result<bool, String> {
+            match (value, &current) {
+                (Some(value), _) => Ok(value),
+                (None, Some(Ok(c))) if c.is_known(field) => Ok(current_value(c)),
+                (None, Some(Err(e))) => Err(format!("Could not read the current options: {}", e)),
+                _ => Err(format!(
+                    "The current {} setting is unknown, so the shared options word was not \
+                     written",
+                    field.label()
+                )),
+            }
+        };
+        let opts = (|| {
+            let mut opts = PhysicalOptionFlags::empty();
+            opts.set(
+                PhysicalOptionFlags::LED_DIMMABLE,
+                resolve(ConfigField::LedDimmable, input.led_dimmable, |c| {
+                    c.led_dimmable
+                })?,
+            );
+            opts.set(
+                PhysicalOptionFlags::DISABLE_POWER_RESET,
+                !resolve(
+                    ConfigField::PowerCycleOnReset,
+                    input.power_cycle_on_reset,
+                    |c| c.power_cycle_on_reset,
+                )?,
+            );
+            opts.set(
+                PhysicalOptionFlags::LED_STEADY,
+                resolve(ConfigField::LedSteady, input.led_steady, |c| c.led_steady)?,
+            );
+            Ok(Value::Integer(opts.bits() as i128))
+        })();
+
+        let result = write(first, opts);
+        for field in rest {
+            report.record(*field, &result);
+        }
+    }
+
+    report
+}
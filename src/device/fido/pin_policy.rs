@@ -0,0 +1,122 @@
+//! PIN complexity policy of the pico-fido firmware.
+//!
+//! The policy is written with the `PinComplexityPolicy` vendor config command as a
+//! [`PinComplexityFlags`] bitmask. GetInfo only reports whether a policy is in force
+//! (`pinComplexityPolicy`, 0x1B, a boolean in CTAP 2.2) and where it is described
+//! (`pinComplexityPolicyURL`, 0x1C), not which rules it contains. Local validation therefore only
+//! applies the rules the user picked in this session and leaves the rest to the key, which rejects
+//! a non-compliant PIN with `CTAP2_ERR_PIN_POLICY_VIOLATION`.
+
+use super::constants::*;
+use super::hid::*;
+use crate::device::types::PinComplexityPolicy;
+use ctap_hid_fido2::fidokey::pin::Permission;
+use serde_cbor_2::Value;
+use std::collections::BTreeMap;
+
+const GET_INFO_PIN_COMPLEXITY_POLICY: i128 = 0x1B;
+const GET_INFO_PIN_COMPLEXITY_POLICY_URL: i128 = 0x1C;
+
+fn to_flags(policy: &PinComplexityPolicy) -> PinComplexityFlags {
+    let mut flags = PinComplexityFlags::empty();
+    flags.set(PinComplexityFlags::NO_SEQUENCES, policy.no_sequences);
+    flags.set(PinComplexityFlags::NO_REPEATS, policy.no_repeats);
+    flags.set(PinComplexityFlags::MIXED_CLASSES, policy.mixed_classes);
+    flags
+}
+
+/// Reads the policy out of a decoded GetInfo response.
+pub(crate) fn decode_policy(m: &BTreeMap<Value, Value>) -> PinComplexityPolicy {
+    let url = match m.get(&Value::Integer(GET_INFO_PIN_COMPLEXITY_POLICY_URL)) {
+        Some(Value::Bytes(b)) => Some(String::from_utf8_lossy(b).into_owned()),
+        Some(Value::Text(t)) => Some(t.clone()),
+        _ => None,
+    };
+
+    PinComplexityPolicy {
+        enforced: matches!(
+            m.get(&Value::Integer(GET_INFO_PIN_COMPLEXITY_POLICY)),
+            Some(Value::Bool(true))
+        ),
+        url,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn set_policy(pin: String, policy: PinComplexityPolicy) -> Result<String, String> {
+    log::info!("Setting PIN complexity policy: {:?}", policy);
+
+    let pin_token = {
+        let device = super::get_device()?;
+        device
+            .get_pinuv_auth_token_with_permission(&pin, Permission::AuthenticatorConfiguration)
+            .map_err(|e| format!("Failed to obtain PIN token: {:?}", e))?
+            .key
+        // Library handle 'device' is dropped here, closing the HID session.
+    };
+
+    let transport =
+        HidTransport::open().map_err(|e| format!("Could not open HID transport: {}", e))?;
+
+    transport
+        .send_vendor_config(
+            &pin_token,
+            VendorConfigCommand::PinComplexityPolicy,
+            Value::Integer(to_flags(&policy).bits() as i128),
+        )
+        .map_err(|e| format!("Failed to set PIN complexity policy: {}", e))?;
+
+    Ok("PIN complexity policy updated".into())
+}
+
+/// Checks a new PIN against the minimum length and the complexity rules known locally.
+///
+/// A policy read from GetInfo carries no rules, so this only rejects what is certain to fail and
+/// the key remains the authority on everything else.
+pub(crate) fn validate_pin(
+    pin: &str,
+    min_pin_length: u32,
+    policy: &PinComplexityPolicy,
+) -> Result<(), String> {
+    let chars: Vec<char> = pin.chars().collect();
+
+    if chars.len() < min_pin_length as usize {
+        return Err(format!(
+            "PIN must be at least {} characters",
+            min_pin_length
+        ));
+    }
+    if pin.len() > 63 {
+        return Err("PIN must be at most 63 bytes".into());
+    }
+
+    if policy.no_repeats && chars.windows(2).all(|w| w[0] == w[1]) {
+        return Err("PIN must not repeat a single character".into());
+    }
+
+    if policy.no_sequences && chars.len() > 2 {
+        let steps: Vec<i64> = chars
+            .windows(2)
+            .map(|w| w[1] as i64 - w[0] as i64)
+            .collect();
+        if steps.iter().all(|&s| s == 1) || steps.iter().all(|&s| s == -1) {
+            return Err("PIN must not be a sequence like 123456 or abcdef".into());
+        }
+    }
+
+    if policy.mixed_classes {
+        let classes = [
+            chars.iter().any(|c| c.is_ascii_digit()),
+            chars.iter().any(|c| c.is_lowercase()),
+            chars.iter().any(|c| c.is_uppercase()),
+            chars.iter().any(|c| !c.is_alphanumeric()),
+        ];
+        if classes.iter().filter(|&&present| present).count() < 2 {
+            return Err(
+                "PIN must mix at least two of digits, lowercase, uppercase and symbols".into(),
+            );
+        }
+    }
+
+    Ok(())
+}
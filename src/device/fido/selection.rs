@@ -0,0 +1,67 @@
+//! Picking one of several attached keys by touch (authenticatorSelection).
+//!
+//! Every key is asked to blink at the same time. The first one touched wins and becomes the
+//! active device; the others are sent CTAPHID_CANCEL so they stop blinking.
+
+use super::hid::{self, HidTransport};
+use crate::device::{error::PFError, types::SelectedAuthenticator};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+
+/// Waits for the user to touch one of the attached keys and makes it the active device.
+pub(crate) fn select() -> Result<SelectedAuthenticator, PFError> {
+    let transports = HidTransport::open_all()?;
+    log::info!(
+        "Sending authenticatorSelection to {} FIDO devices...",
+        transports.len()
+    );
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let mut workers = Vec::new();
+
+    for (path, transport) in transports {
+        let cancel = cancel.clone();
+        let tx = tx.clone();
+        workers.push(thread::spawn(move || {
+            let result = transport.send_selection(&cancel);
+            let selected = SelectedAuthenticator {
+                product_name: transport.product_name.clone(),
+                vid: format!("{:04X}", transport.vid),
+                pid: format!("{:04X}", transport.pid),
+            };
+            let _ = tx.send((path, selected, result));
+        }));
+    }
+    drop(tx);
+
+    let mut winner = None;
+    let mut last_error = None;
+    for (path, selected, result) in rx.iter() {
+        match result {
+            Ok(true) if winner.is_none() => {
+                log::info!("{} was touched", selected.product_name);
+                cancel.store(true, Ordering::SeqCst);
+                winner = Some((path, selected));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Selection failed on {}: {}", selected.product_name, e);
+                last_error = Some(e);
+            }
+        }
+        // Without a touch every key eventually times out, so the channel always drains.
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    match winner {
+        Some((path, selected)) => {
+            hid::set_active_device(Some(path));
+            Ok(selected)
+        }
+        None => Err(last_error.unwrap_or_else(|| PFError::Device("No key was touched".into()))),
+    }
+}
@@ -0,0 +1,245 @@
+//! Authenticator self-test: a full makeCredential / getAssertion round trip against a synthetic
+//! relying party, checked on the host.
+//!
+//! With a PIN the test credential is discoverable and removed again through credential
+//! management; without one it is a non-discoverable credential and nothing is left on the key.
+
+use super::constants::*;
+use super::credentials;
+use super::hid::HidTransport;
+use super::webauthn::{self, GetAssertionRequest, MakeCredentialRequest};
+use crate::device::{
+    error::PFError,
+    types::{CheckStatus, DiagnosticCheck, SelfTestReport},
+};
+use ctap_hid_fido2::fidokey::pin::Permission;
+use ring::{digest, rand::SecureRandom, rand::SystemRandom};
+
+pub(crate) const SELF_TEST_RP_ID: &str = "selftest.picoforge.invalid";
+const SELF_TEST_USER: &str = "picoforge-self-test";
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], PFError> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| PFError::Io("Failed to generate random bytes".into()))?;
+    Ok(bytes)
+}
+
+/// A client data hash over a random challenge, standing in for the browser's clientDataJSON.
+pub(crate) fn random_client_data_hash() -> Result<Vec<u8>, PFError> {
+    let challenge = random_bytes::<32>()?;
+    Ok(digest::digest(&digest::SHA256, &challenge)
+        .as_ref()
+        .to_vec())
+}
+
+fn check(name: &str, result: Result<String, String>) -> DiagnosticCheck {
+    let (status, detail) = match result {
+        Ok(detail) => (CheckStatus::Passed, detail),
+        Err(detail) => (CheckStatus::Failed, detail),
+    };
+    DiagnosticCheck {
+        name: name.into(),
+        status,
+        detail,
+    }
+}
+
+fn skipped(name: &str, detail: &str) -> DiagnosticCheck {
+    DiagnosticCheck {
+        name: name.into(),
+        status: CheckStatus::Skipped,
+        detail: detail.into(),
+    }
+}
+
+fn check_rp_id_hash(rp_id_hash: &[u8]) -> Result<String, String> {
+    let expected = digest::digest(&digest::SHA256, SELF_TEST_RP_ID.as_bytes());
+    if rp_id_hash == expected.as_ref() {
+        Ok("Matches SHA-256 of the RP ID".into())
+    } else {
+        Err("Does not match SHA-256 of the RP ID".into())
+    }
+}
+
+fn check_flags(flags: AuthenticatorFlags, required: AuthenticatorFlags) -> Result<String, String> {
+    let detail = format!(
+        "UP={} UV={} AT={} ED={}",
+        flags.contains(AuthenticatorFlags::USER_PRESENT) as u8,
+        flags.contains(AuthenticatorFlags::USER_VERIFIED) as u8,
+        flags.contains(AuthenticatorFlags::ATTESTED_CREDENTIAL_DATA) as u8,
+        flags.contains(AuthenticatorFlags::EXTENSION_DATA) as u8,
+    );
+    if flags.contains(required) {
+        Ok(detail)
+    } else {
+        Err(format!("Missing required flags ({})", detail))
+    }
+}
+
+/// Runs the round trip. The key has to be touched twice, once per operation.
+pub(crate) fn run(pin: Option<String>) -> Result<SelfTestReport, PFError> {
+    log::info!("Starting authenticator self-test...");
+    let mut checks = Vec::new();
+
+    let mc_token = match &pin {
+        Some(pin) => Some(credentials::pin_token(pin, Permission::MakeCredential)?),
+        None => None,
+    };
+    let resident_key = mc_token.is_some();
+
+    let transport = HidTransport::open()?;
+    let user_id = random_bytes::<16>()?;
+    let mc_hash = random_client_data_hash()?;
+
+    let attestation = match webauthn::make_credential(
+        &transport,
+        MakeCredentialRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &mc_hash,
+            user_id: &user_id,
+            user_name: SELF_TEST_USER,
+            algorithms: &[CoseAlgorithm::ES256 as i32, CoseAlgorithm::EdDSA as i32],
+            resident_key,
+            extensions: None,
+            pin_token: mc_token.as_deref(),
+        },
+    ) {
+        Ok(a) => a,
+        Err(e) => {
+            log::error!("Self-test makeCredential failed: {}", e);
+            checks.push(check("Make credential", Err(e.to_string())));
+            return Ok(SelfTestReport { checks });
+        }
+    };
+
+    let Some(credential) = attestation.auth_data.credential.clone() else {
+        checks.push(check(
+            "Make credential",
+            Err("Response has no attested credential data".into()),
+        ));
+        return Ok(SelfTestReport { checks });
+    };
+    checks.push(check(
+        "Make credential",
+        Ok(format!(
+            "{} credential, {} byte ID, format '{}'",
+            if resident_key {
+                "Discoverable"
+            } else {
+                "Non-discoverable"
+            },
+            credential.credential_id.len(),
+            attestation.fmt
+        )),
+    ));
+    checks.push(check(
+        "Registration RP ID hash",
+        check_rp_id_hash(&attestation.auth_data.rp_id_hash),
+    ));
+
+    let mut required =
+        AuthenticatorFlags::USER_PRESENT | AuthenticatorFlags::ATTESTED_CREDENTIAL_DATA;
+    if resident_key {
+        required |= AuthenticatorFlags::USER_VERIFIED;
+    }
+    checks.push(check(
+        "Registration flags",
+        check_flags(attestation.auth_data.flags, required),
+    ));
+    checks.push(check(
+        "Attestation signature",
+        webauthn::verify_attestation(&attestation, &mc_hash),
+    ));
+
+    let ga_hash = random_client_data_hash()?;
+    let allow_list = [credential.credential_id.clone()];
+    match webauthn::get_assertion(
+        &transport,
+        GetAssertionRequest {
+            rp_id: SELF_TEST_RP_ID,
+            client_data_hash: &ga_hash,
+            allow_list: &allow_list,
+            extensions: None,
+            pin_token: None,
+        },
+    ) {
+        Ok(assertion) => {
+            checks.push(check(
+                "Get assertion",
+                if assertion.credential_id.is_empty()
+                    || assertion.credential_id == credential.credential_id
+                {
+                    Ok("Returned the test credential".into())
+                } else {
+                    Err("Returned a different credential".into())
+                },
+            ));
+            checks.push(check(
+                "Assertion RP ID hash",
+                check_rp_id_hash(&assertion.auth_data.rp_id_hash),
+            ));
+            checks.push(check(
+                "Assertion flags",
+                check_flags(assertion.auth_data.flags, AuthenticatorFlags::USER_PRESENT),
+            ));
+            checks.push(check(
+                "Assertion signature",
+                webauthn::verify_assertion(&credential.public_key, &assertion, &ga_hash)
+                    .map(|_| "Verified with the credential public key".into()),
+            ));
+
+            let (before, after) = (
+                attestation.auth_data.sign_count,
+                assertion.auth_data.sign_count,
+            );
+            checks.push(if before == 0 && after == 0 {
+                skipped(
+                    "Signature counter",
+                    "The authenticator does not use counters",
+                )
+            } else {
+                check(
+                    "Signature counter",
+                    if after > before {
+                        Ok(format!("Increased from {} to {}", before, after))
+                    } else {
+                        Err(format!("Did not increase ({} then {})", before, after))
+                    },
+                )
+            });
+        }
+        Err(e) => {
+            log::error!("Self-test getAssertion failed: {}", e);
+            checks.push(check("Get assertion", Err(e.to_string())));
+        }
+    }
+
+    // The library opens its own HID session to get the PIN token, so close ours first.
+    drop(transport);
+
+    checks.push(match &pin {
+        Some(pin) => check(
+            "Delete test credential",
+            credentials::pin_token(pin, Permission::CredentialManagement)
+                .and_then(|token| {
+                    let transport = HidTransport::open()?;
+                    credentials::delete_one(&transport, &token, &credential.credential_id)
+                })
+                .map(|_| "Removed from the device".into())
+                .map_err(|e| e.to_string()),
+        ),
+        None => skipped(
+            "Delete test credential",
+            "Non-discoverable credential, nothing was stored",
+        ),
+    });
+
+    let report = SelfTestReport { checks };
+    log::info!(
+        "Self-test finished: {}",
+        if report.passed() { "passed" } else { "failed" }
+    );
+    Ok(report)
+}
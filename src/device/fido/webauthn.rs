@@ -0,0 +1,376 @@
+//! Raw authenticatorMakeCredential / authenticatorGetAssertion round trips.
+//!
+//! Used by the diagnostics, which need the untouched authenticator data and signatures that
+//! `ctap-hid-fido2` does not hand back.
+
+use super::constants::*;
+use super::cose;
+use super::hid::HidTransport;
+use crate::device::error::PFError;
+use serde::Deserialize;
+use serde_cbor_2::Value;
+use std::collections::BTreeMap;
+
+const RP_ID_HASH_LEN: usize = 32;
+const AAGUID_LEN: usize = 16;
+
+/// Parsed authenticator data (WebAuthn §6.1).
+#[derive(Debug, Clone)]
+pub(crate) struct AuthenticatorData {
+    pub raw: Vec<u8>,
+    pub rp_id_hash: Vec<u8>,
+    pub flags: AuthenticatorFlags,
+    pub sign_count: u32,
+    pub credential: Option<AttestedCredential>,
+    pub extensions: Option<BTreeMap<Value, Value>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AttestedCredential {
+    pub aaguid: Vec<u8>,
+    pub credential_id: Vec<u8>,
+    pub public_key: Value,
+}
+
+/// Decodes one CBOR item from the front of `data` and returns it with the bytes it used.
+fn read_cbor_item(data: &[u8]) -> Result<(Value, usize), PFError> {
+    let mut de = serde_cbor_2::Deserializer::from_slice(data);
+    let value = Value::deserialize(&mut de)
+        .map_err(|e| PFError::Io(format!("Invalid CBOR in authenticator data: {}", e)))?;
+    Ok((value, de.byte_offset()))
+}
+
+impl AuthenticatorData {
+    pub(crate) fn parse(raw: &[u8]) -> Result<Self, PFError> {
+        if raw.len() < RP_ID_HASH_LEN + 5 {
+            return Err(PFError::Device("Authenticator data is truncated".into()));
+        }
+
+        let rp_id_hash = raw[..RP_ID_HASH_LEN].to_vec();
+        let flags = AuthenticatorFlags::from_bits_retain(raw[RP_ID_HASH_LEN]);
+        let sign_count = u32::from_be_bytes(
+            raw[RP_ID_HASH_LEN + 1..RP_ID_HASH_LEN + 5]
+                .try_into()
+                .unwrap(),
+        );
+        let mut rest = &raw[RP_ID_HASH_LEN + 5..];
+
+        let credential = if flags.contains(AuthenticatorFlags::ATTESTED_CREDENTIAL_DATA) {
+            if rest.len() < AAGUID_LEN + 2 {
+                return Err(PFError::Device(
+                    "Attested credential data is truncated".into(),
+                ));
+            }
+            let aaguid = rest[..AAGUID_LEN].to_vec();
+            let id_len = u16::from_be_bytes([rest[AAGUID_LEN], rest[AAGUID_LEN + 1]]) as usize;
+            rest = &rest[AAGUID_LEN + 2..];
+            if rest.len() < id_len {
+                return Err(PFError::Device("Credential ID is truncated".into()));
+            }
+            let credential_id = rest[..id_len].to_vec();
+            rest = &rest[id_len..];
+
+            let (public_key, used) = read_cbor_item(rest)?;
+            rest = &rest[used..];
+
+            Some(AttestedCredential {
+                aaguid,
+                credential_id,
+                public_key,
+            })
+        } else {
+            None
+        };
+
+        let extensions = if flags.contains(AuthenticatorFlags::EXTENSION_DATA) {
+            match read_cbor_item(rest)?.0 {
+                Value::Map(m) => Some(m),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            raw: raw.to_vec(),
+            rp_id_hash,
+            flags,
+            sign_count,
+            credential,
+            extensions,
+        })
+    }
+}
+
+/// Result of authenticatorMakeCredential.
+#[derive(Debug, Clone)]
+pub(crate) struct Attestation {
+    pub fmt: String,
+    pub auth_data: AuthenticatorData,
+    pub att_stmt: BTreeMap<Value, Value>,
+}
+
+/// Result of authenticatorGetAssertion.
+#[derive(Debug, Clone)]
+pub(crate) struct Assertion {
+    pub credential_id: Vec<u8>,
+    pub auth_data: AuthenticatorData,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MakeCredentialRequest<'a> {
+    pub rp_id: &'a str,
+    pub client_data_hash: &'a [u8],
+    pub user_id: &'a [u8],
+    pub user_name: &'a str,
+    /// COSE algorithm identifiers in order of preference.
+    pub algorithms: &'a [i32],
+    pub resident_key: bool,
+    pub extensions: Option<BTreeMap<Value, Value>>,
+    pub pin_token: Option<&'a [u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GetAssertionRequest<'a> {
+    pub rp_id: &'a str,
+    pub client_data_hash: &'a [u8],
+    pub allow_list: &'a [Vec<u8>],
+    pub extensions: Option<BTreeMap<Value, Value>>,
+    pub pin_token: Option<&'a [u8]>,
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn credential_descriptor(id: &[u8]) -> Value {
+    let mut descriptor = BTreeMap::new();
+    descriptor.insert(text("id"), Value::Bytes(id.to_vec()));
+    descriptor.insert(text("type"), text("public-key"));
+    Value::Map(descriptor)
+}
+
+fn response_bytes(map: &BTreeMap<Value, Value>, key: i128) -> Option<Vec<u8>> {
+    match map.get(&Value::Integer(key)) {
+        Some(Value::Bytes(b)) => Some(b.clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn make_credential(
+    transport: &HidTransport,
+    request: MakeCredentialRequest,
+) -> Result<Attestation, PFError> {
+    let mut rp = BTreeMap::new();
+    rp.insert(text("id"), text(request.rp_id));
+    rp.insert(text("name"), text(request.rp_id));
+
+    let mut user = BTreeMap::new();
+    user.insert(text("id"), Value::Bytes(request.user_id.to_vec()));
+    user.insert(text("name"), text(request.user_name));
+    user.insert(text("displayName"), text(request.user_name));
+
+    let params = request
+        .algorithms
+        .iter()
+        .map(|alg| {
+            let mut param = BTreeMap::new();
+            param.insert(text("alg"), Value::Integer(*alg as i128));
+            param.insert(text("type"), text("public-key"));
+            Value::Map(param)
+        })
+        .collect();
+
+    let mut options = BTreeMap::new();
+    options.insert(text("rk"), Value::Bool(request.resident_key));
+
+    let mut map = BTreeMap::new();
+    map.insert(
+        Value::Integer(MakeCredentialParam::ClientDataHash as i128),
+        Value::Bytes(request.client_data_hash.to_vec()),
+    );
+    map.insert(
+        Value::Integer(MakeCredentialParam::Rp as i128),
+        Value::Map(rp),
+    );
+    map.insert(
+        Value::Integer(MakeCredentialParam::User as i128),
+        Value::Map(user),
+    );
+    map.insert(
+        Value::Integer(MakeCredentialParam::PubKeyCredParams as i128),
+        Value::Array(params),
+    );
+    if let Some(extensions) = request.extensions {
+        map.insert(
+            Value::Integer(MakeCredentialParam::Extensions as i128),
+            Value::Map(extensions),
+        );
+    }
+    map.insert(
+        Value::Integer(MakeCredentialParam::Options as i128),
+        Value::Map(options),
+    );
+    if let Some(token) = request.pin_token {
+        map.insert(
+            Value::Integer(MakeCredentialParam::PinUvAuthParam as i128),
+            Value::Bytes(HidTransport::authenticate(token, request.client_data_hash)),
+        );
+        map.insert(
+            Value::Integer(MakeCredentialParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+    }
+
+    let response = transport.send_make_credential(map)?;
+
+    let fmt = match response.get(&Value::Integer(0x01)) {
+        Some(Value::Text(t)) => t.clone(),
+        _ => String::new(),
+    };
+    let auth_data = response_bytes(&response, 0x02)
+        .ok_or_else(|| PFError::Device("makeCredential response has no authData".into()))?;
+    let att_stmt = match response.get(&Value::Integer(0x03)) {
+        Some(Value::Map(m)) => m.clone(),
+        _ => BTreeMap::new(),
+    };
+
+    Ok(Attestation {
+        fmt,
+        auth_data: AuthenticatorData::parse(&auth_data)?,
+        att_stmt,
+    })
+}
+
+pub(crate) fn get_assertion(
+    transport: &HidTransport,
+    request: GetAssertionRequest,
+) -> Result<Assertion, PFError> {
+    let mut options = BTreeMap::new();
+    options.insert(text("up"), Value::Bool(true));
+
+    let mut map = BTreeMap::new();
+    map.insert(
+        Value::Integer(GetAssertionParam::RpId as i128),
+        text(request.rp_id),
+    );
+    map.insert(
+        Value::Integer(GetAssertionParam::ClientDataHash as i128),
+        Value::Bytes(request.client_data_hash.to_vec()),
+    );
+    if !request.allow_list.is_empty() {
+        map.insert(
+            Value::Integer(GetAssertionParam::AllowList as i128),
+            Value::Array(
+                request
+                    .allow_list
+                    .iter()
+                    .map(|id| credential_descriptor(id))
+                    .collect(),
+            ),
+        );
+    }
+    if let Some(extensions) = request.extensions {
+        map.insert(
+            Value::Integer(GetAssertionParam::Extensions as i128),
+            Value::Map(extensions),
+        );
+    }
+    map.insert(
+        Value::Integer(GetAssertionParam::Options as i128),
+        Value::Map(options),
+    );
+    if let Some(token) = request.pin_token {
+        map.insert(
+            Value::Integer(GetAssertionParam::PinUvAuthParam as i128),
+            Value::Bytes(HidTransport::authenticate(token, request.client_data_hash)),
+        );
+        map.insert(
+            Value::Integer(GetAssertionParam::PinUvAuthProtocol as i128),
+            Value::Integer(1),
+        );
+    }
+
+    let response = transport.send_get_assertion(map)?;
+
+    let credential_id = match response.get(&Value::Integer(0x01)) {
+        Some(Value::Map(m)) => match m.get(&text("id")) {
+            Some(Value::Bytes(b)) => b.clone(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    let auth_data = response_bytes(&response, 0x02)
+        .ok_or_else(|| PFError::Device("getAssertion response has no authData".into()))?;
+    let signature = response_bytes(&response, 0x03)
+        .ok_or_else(|| PFError::Device("getAssertion response has no signature".into()))?;
+
+    Ok(Assertion {
+        credential_id,
+        auth_data: AuthenticatorData::parse(&auth_data)?,
+        signature,
+    })
+}
+
+/// Verifies a "packed" attestation statement. Full attestation is checked against the leaf
+/// certificate in `x5c`, self attestation against the new credential's own key.
+pub(crate) fn verify_attestation(
+    attestation: &Attestation,
+    client_data_hash: &[u8],
+) -> Result<String, String> {
+    if attestation.fmt == "none" {
+        return Ok("No attestation requested".into());
+    }
+    if attestation.fmt != "packed" {
+        return Err(format!(
+            "Unsupported attestation format '{}'",
+            attestation.fmt
+        ));
+    }
+
+    let stmt = &attestation.att_stmt;
+    let alg = match stmt.get(&text("alg")) {
+        Some(Value::Integer(a)) => *a as i32,
+        _ => return Err("Attestation statement has no algorithm".into()),
+    };
+    let signature = match stmt.get(&text("sig")) {
+        Some(Value::Bytes(b)) => b,
+        _ => return Err("Attestation statement has no signature".into()),
+    };
+
+    let mut message = attestation.auth_data.raw.clone();
+    message.extend(client_data_hash);
+
+    match stmt.get(&text("x5c")) {
+        Some(Value::Array(chain)) => {
+            let Some(Value::Bytes(leaf)) = chain.first() else {
+                return Err("Attestation certificate chain is empty".into());
+            };
+            let public_key = cose::certificate_public_key(leaf)
+                .ok_or("Could not read the attestation certificate")?;
+            cose::verify_raw(alg, &public_key, &message, signature)?;
+            Ok(format!("Full attestation ({} certificates)", chain.len()))
+        }
+        _ => {
+            let credential = attestation
+                .auth_data
+                .credential
+                .as_ref()
+                .ok_or("No attested credential data")?;
+            cose::verify(&credential.public_key, &message, signature)?;
+            Ok("Self attestation".into())
+        }
+    }
+}
+
+/// Verifies an assertion signature over `authData || clientDataHash`.
+pub(crate) fn verify_assertion(
+    public_key: &Value,
+    assertion: &Assertion,
+    client_data_hash: &[u8],
+) -> Result<(), String> {
+    let mut message = assertion.auth_data.raw.clone();
+    message.extend(client_data_hash);
+    cose::verify(public_key, &message, &assertion.signature)
+}
@@ -1,32 +1,99 @@
 //! Tauri Commands to interact with the pico-fido firmware via rescue and fido protocols.
 #![allow(unused)]
 
-use crate::{device::error::PFError, device::fido, device::rescue, device::types::*};
+use crate::{
+    device::boot_signing, device::capabilities, device::error::PFError, device::fido,
+    device::rescue, device::status, device::types::*,
+};
 
+/// Reads the device through both interfaces and merges the results.
 pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
-    match rescue::read_device_details() {
-        Ok(status) => Ok(status),
-        Err(e) => {
-            log::warn!("Rescue method failed: {}. Falling back to FIDO...", e);
-            fido::read_device_details()
-        }
-    }
+    let status = status::merge(rescue::read_device_details(), fido::read_device_details());
+    capabilities::record(status.as_ref().ok());
+    status
+}
+
+/// Whether `status` supports `feature`, with the reason to show when it does not.
+pub fn feature_support(status: Option<&FullDeviceStatus>, feature: Feature) -> Result<(), String> {
+    capabilities::support(status, feature)
+}
+
+/// Whether `field` can be written over FIDO; the others need the rescue interface.
+pub fn fido_config_writable(field: ConfigField) -> bool {
+    fido::physical::command_for(field).is_some()
 }
 
 pub fn write_config(
     config: AppConfigInput,
     method: DeviceMethod,
     pin: Option<String>,
-) -> Result<String, PFError> {
+) -> Result<ConfigWriteReport, PFError> {
     if method == DeviceMethod::Fido {
+        capabilities::require(Feature::FidoPhysicalConfig)?;
         fido::write_config(config, pin)
     } else {
-        rescue::write_config(config)
+        // The rescue interface writes every field in one APDU, so they succeed or fail together.
+        capabilities::require(Feature::RescueConfig)?;
+        let fields = config.fields();
+        rescue::write_config(config).map(|_| ConfigWriteReport::succeeded(fields))
     }
 }
 
-pub fn enable_secure_boot(lock: bool) -> Result<String, PFError> {
-    rescue::enable_secure_boot(lock)
+/// Boot key slots selectable for secure boot.
+pub use rescue::constants::RP2350_BOOT_KEY_SLOTS as BOOT_KEY_SLOTS;
+
+pub fn secure_boot_preflight() -> Result<SecureBootPreflight, PFError> {
+    capabilities::require(Feature::RescueSecureBoot)?;
+    rescue::secure_boot_preflight()
+}
+
+/// `confirmed_key_hash` is the OTP hash the user confirmed is burned into `boot_key`.
+pub fn enable_secure_boot(
+    boot_key: u8,
+    lock: bool,
+    confirm_serial: String,
+    confirmed_key_hash: String,
+) -> Result<SecureBootState, PFError> {
+    capabilities::require(Feature::RescueSecureBoot)?;
+    rescue::enable_secure_boot(boot_key, lock, &confirm_serial, &confirmed_key_hash)
+}
+
+pub fn generate_boot_key(path: std::path::PathBuf) -> Result<BootKeyInfo, PFError> {
+    boot_signing::generate_key(&path)
+}
+
+pub fn load_boot_key(path: std::path::PathBuf) -> Result<BootKeyInfo, PFError> {
+    boot_signing::load_key(&path)
+}
+
+pub fn seal_firmware(
+    key_path: std::path::PathBuf,
+    input: std::path::PathBuf,
+    output: std::path::PathBuf,
+) -> Result<SealedImage, PFError> {
+    boot_signing::seal_uf2(&key_path, &input, &output)
+}
+
+pub fn verify_device_authenticity() -> Result<DeviceAuthenticity, PFError> {
+    capabilities::require(Feature::RescueDeviceKey)?;
+    rescue::keydev::verify_authenticity()
+}
+
+pub fn upload_device_certificate(path: std::path::PathBuf) -> Result<String, PFError> {
+    capabilities::require(Feature::RescueDeviceKey)?;
+    rescue::keydev::upload_certificate(&path)
+}
+
+pub fn count_authenticators() -> usize {
+    fido::hid::count_devices()
+}
+
+pub fn has_selected_authenticator() -> bool {
+    fido::hid::active_device().is_some()
+}
+
+pub fn select_authenticator() -> Result<SelectedAuthenticator, PFError> {
+    fido::selection::select()
 }
 
 pub(crate) fn get_fido_info() -> Result<FidoDeviceInfo, String> {
@@ -44,6 +111,7 @@ pub(crate) fn set_min_pin_length(
     current_pin: String,
     min_pin_length: u8,
 ) -> Result<String, String> {
+    capabilities::require(Feature::MinPinLength).map_err(|e| e.to_string())?;
     fido::set_min_pin_length(current_pin, min_pin_length)
 }
 
@@ -52,9 +120,189 @@ pub fn reboot(to_bootsel: bool) -> Result<String, PFError> {
 }
 
 pub fn get_credentials(pin: String) -> Result<Vec<StoredCredential>, String> {
+    capabilities::require(Feature::CredentialManagement).map_err(|e| e.to_string())?;
     fido::get_credentials(pin)
 }
 
 pub fn delete_credential(pin: String, credential_id: String) -> Result<String, String> {
+    capabilities::require(Feature::CredentialManagement).map_err(|e| e.to_string())?;
     fido::delete_credential(pin, credential_id)
 }
+
+pub fn delete_credentials(
+    pin: String,
+    credentials: Vec<StoredCredential>,
+) -> Result<Vec<CredentialDeleteResult>, PFError> {
+    capabilities::require(Feature::CredentialManagement)?;
+    fido::credentials::delete_many(pin, credentials)
+}
+
+pub fn export_passkey_inventory(
+    path: std::path::PathBuf,
+    credentials: Vec<StoredCredential>,
+    aaguid: Option<String>,
+    format: InventoryFormat,
+) -> Result<(), PFError> {
+    fido::credentials::export_inventory(&path, &credentials, aaguid, format)
+}
+
+pub fn update_credential_user(
+    pin: String,
+    credential_id: String,
+    user_id: String,
+    name: String,
+    display_name: String,
+) -> Result<String, PFError> {
+    capabilities::require(Feature::CredentialManagement)?;
+    fido::credentials::update_user(pin, credential_id, user_id, name, display_name)
+}
+
+pub fn generate_ssh_key(
+    pin: String,
+    request: SshKeyRequest,
+    path: std::path::PathBuf,
+) -> Result<String, PFError> {
+    capabilities::require(Feature::CredentialManagement)?;
+    fido::ssh::generate(&pin, request, &path)
+}
+
+pub fn export_ssh_keys(
+    credentials: Vec<StoredCredential>,
+    dir: std::path::PathBuf,
+) -> Result<SshExport, PFError> {
+    fido::ssh::export_resident(&credentials, &dir)
+}
+
+pub fn get_credentials_with_metadata(
+    pin: String,
+) -> Result<(Vec<StoredCredential>, CredentialsMetadata), PFError> {
+    capabilities::require(Feature::CredentialManagement)?;
+    fido::credentials::list_with_metadata(pin)
+}
+
+pub fn run_self_test(pin: Option<String>) -> Result<SelfTestReport, PFError> {
+    capabilities::require(Feature::Registration)?;
+    // With a PIN the test credential is discoverable and removed through credential management.
+    if pin.is_some() {
+        capabilities::require(Feature::CredentialManagement)?;
+    }
+    fido::self_test::run(pin)
+}
+
+pub fn test_hmac_secret(
+    salt_input: String,
+    pin: Option<String>,
+) -> Result<HmacSecretReport, PFError> {
+    capabilities::require(Feature::HmacSecret)?;
+    fido::hmac_secret::run(&salt_input, pin)
+}
+
+pub fn probe_algorithms() -> Result<Vec<AlgorithmSupport>, PFError> {
+    capabilities::require(Feature::Registration)?;
+    fido::algorithm_probe::probe()
+}
+
+pub fn inspect_attestation() -> Result<AttestationReport, PFError> {
+    capabilities::require(Feature::Registration)?;
+    fido::attestation::inspect()
+}
+
+pub fn import_metadata_blob(
+    path: std::path::PathBuf,
+    aaguid: String,
+) -> Result<MetadataLookup, PFError> {
+    fido::metadata::import_blob(&path, &aaguid)
+}
+
+pub fn lookup_metadata(aaguid: String) -> Result<Option<MetadataLookup>, PFError> {
+    if !fido::metadata::has_stored_blob() {
+        return Ok(None);
+    }
+    fido::metadata::lookup(&aaguid).map(Some)
+}
+
+pub fn create_fido_backup(
+    serial: String,
+    pin: Option<String>,
+) -> Result<FidoBackupExport, PFError> {
+    capabilities::require(Feature::FidoBackup)?;
+    fido::backup::create_backup(serial, pin)
+}
+
+pub fn restore_fido_backup(
+    backup: FidoBackup,
+    mnemonic: Option<String>,
+) -> Result<String, PFError> {
+    capabilities::require(Feature::FidoBackup)?;
+    fido::backup::restore_backup(&backup, mnemonic)
+}
+
+pub fn verify_fido_restore(
+    backup: FidoBackup,
+    pin: Option<String>,
+) -> Result<BackupVerification, PFError> {
+    capabilities::require(Feature::FidoBackup)?;
+    fido::backup::verify_restore(&backup, pin)
+}
+
+pub fn save_fido_backup(path: std::path::PathBuf, backup: FidoBackup) -> Result<(), PFError> {
+    fido::backup::save_backup(&path, &backup)
+}
+
+pub fn load_fido_backup(path: std::path::PathBuf) -> Result<FidoBackup, PFError> {
+    fido::backup::load_backup(&path)
+}
+
+pub fn get_auth_encryption_status(serial: &str) -> AuthEncryptionStatus {
+    fido::auth_encryption::status(serial)
+}
+
+pub fn enable_auth_encryption(serial: String, pin: String) -> Result<String, PFError> {
+    capabilities::require(Feature::FidoAuthEncryption)?;
+    fido::auth_encryption::enable(&serial, pin)
+}
+
+pub fn disable_auth_encryption(serial: String, pin: String) -> Result<String, PFError> {
+    capabilities::require(Feature::FidoAuthEncryption)?;
+    fido::auth_encryption::disable(&serial, pin)
+}
+
+pub fn unlock_device(serial: String) -> Result<String, PFError> {
+    capabilities::require(Feature::FidoAuthEncryption)?;
+    fido::auth_encryption::unlock(&serial)
+}
+
+pub(crate) fn set_pin_complexity_policy(
+    current_pin: String,
+    policy: PinComplexityPolicy,
+) -> Result<String, String> {
+    capabilities::require(Feature::FidoPinPolicy).map_err(|e| e.to_string())?;
+    fido::pin_policy::set_policy(current_pin, policy)
+}
+
+pub(crate) fn validate_new_pin(
+    pin: &str,
+    min_pin_length: u32,
+    policy: &PinComplexityPolicy,
+) -> Result<(), String> {
+    fido::pin_policy::validate_pin(pin, min_pin_length, policy)
+}
+
+pub fn read_large_blobs(pin: String) -> Result<LargeBlobStorage, PFError> {
+    capabilities::require(Feature::LargeBlobs)?;
+    fido::large_blob::read_storage(pin)
+}
+
+pub fn delete_large_blob(pin: String, fingerprint: String) -> Result<String, PFError> {
+    capabilities::require(Feature::LargeBlobs)?;
+    fido::large_blob::delete_entry(pin, &fingerprint)
+}
+
+pub fn clear_large_blobs(pin: String) -> Result<String, PFError> {
+    capabilities::require(Feature::LargeBlobs)?;
+    fido::large_blob::clear(pin)
+}
+
+pub fn export_large_blob(path: std::path::PathBuf, data: Vec<u8>) -> Result<(), PFError> {
+    fido::large_blob::export_entry(&path, &data)
+}
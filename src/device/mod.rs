@@ -1,5 +1,8 @@
+pub mod boot_signing;
+pub mod capabilities;
 pub mod error;
 pub mod fido;
 pub mod io;
 pub mod rescue;
+pub mod status;
 pub mod types;
@@ -78,6 +78,14 @@ pub enum SecureLockParam {
     Lock = 0x01,
 }
 
+/// MCU identifiers in the first byte of the SELECT response (`PICO_MCU`)
+pub const MCU_RP2040: u8 = 0x01;
+pub const MCU_RP2350: u8 = 0x02;
+pub const MCU_ESP32: u8 = 0x03;
+
+/// Number of BOOTKEY slots in the RP2350 OTP
+pub const RP2350_BOOT_KEY_SLOTS: u8 = 4;
+
 /// Default P2 value when not used
 pub const P2_UNUSED: u8 = 0x00;
 
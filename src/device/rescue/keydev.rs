@@ -0,0 +1,193 @@
+//! Device key operations of the rescue applet (`KEYDEV_SIGN`).
+//!
+//! The device key is the P-256 key the firmware generates once and keeps in OTP-protected flash.
+//! Signing a fresh challenge with it and checking the signature against its public key proves the
+//! key answering is the one whose public key we have, and the key is pinned per serial so a swapped
+//! device is noticed on the next check.
+
+use super::{connect_and_select, constants::*};
+use crate::device::{
+    error::PFError,
+    types::{DeviceAuthenticity, DeviceKeyPin},
+};
+use directories::ProjectDirs;
+use ring::{
+    digest,
+    rand::{SecureRandom, SystemRandom},
+    signature,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use x509_parser::pem::Pem;
+
+const CHALLENGE_LEN: usize = 32;
+const P256_POINT_LEN: usize = 65;
+
+fn pins_path() -> PathBuf {
+    ProjectDirs::from("in", "suyogtandel", "picoforge")
+        .map(|dirs| dirs.data_local_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("device_keys.json")
+}
+
+/// Serial to hex public key of every device verified so far.
+fn load_pins() -> BTreeMap<String, String> {
+    std::fs::read_to_string(pins_path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn store_pins(pins: &BTreeMap<String, String>) -> Result<(), PFError> {
+    let path = pins_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| PFError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let data = serde_json::to_string_pretty(pins).map_err(|e| PFError::Io(e.to_string()))?;
+    std::fs::write(&path, data)
+        .map_err(|e| PFError::Io(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+fn fingerprint(public_key: &[u8]) -> String {
+    digest::digest(&digest::SHA256, public_key)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Sends a KEYDEV_SIGN APDU, using extended length when `data` does not fit a short one.
+fn keydev_apdu(card: &pcsc::Card, param: SignParam, data: &[u8]) -> Result<Vec<u8>, PFError> {
+    let mut apdu = vec![
+        APDU_CLA_PROPRIETARY,
+        RescueInstruction::KeyDevSign as u8,
+        param as u8,
+        P2_UNUSED,
+    ];
+    if data.is_empty() {
+        apdu.push(0x00); // Le
+    } else if data.len() <= 0xFF {
+        apdu.push(data.len() as u8);
+        apdu.extend_from_slice(data);
+        apdu.push(0x00); // Le
+    } else {
+        let len = u16::try_from(data.len())
+            .map_err(|_| PFError::Io("Data is too large for one APDU".into()))?;
+        apdu.push(0x00);
+        apdu.extend_from_slice(&len.to_be_bytes());
+        apdu.extend_from_slice(data);
+    }
+
+    let mut rx_buf = [0; 512];
+    let rx = card.transmit(&apdu, &mut rx_buf)?;
+    if !rx.ends_with(&SW_SUCCESS) {
+        return Err(PFError::Device(format!(
+            "Device key command {:?} failed: {:02X?}",
+            param, rx
+        )));
+    }
+    Ok(rx[..rx.len() - 2].to_vec())
+}
+
+fn read_public_key(card: &pcsc::Card) -> Result<Vec<u8>, PFError> {
+    let key = keydev_apdu(card, SignParam::GetPublicKey, &[])?;
+    if key.len() != P256_POINT_LEN || key[0] != 0x04 {
+        return Err(PFError::Device(format!(
+            "Unexpected device public key ({} bytes)",
+            key.len()
+        )));
+    }
+    Ok(key)
+}
+
+fn serial_from_select(select_resp: &[u8]) -> Option<String> {
+    (select_resp.len() >= 14).then(|| hex::encode_upper(&select_resp[4..12]))
+}
+
+/// Has the device key sign a random challenge, verifies the signature and checks the key against
+/// the one pinned for this serial. Without a serial the key is checked but never pinned, since
+/// every such device would share one entry.
+pub fn verify_authenticity() -> Result<DeviceAuthenticity, PFError> {
+    let (card, select_resp) = connect_and_select()?;
+    let serial = serial_from_select(&select_resp);
+    let public_key = read_public_key(&card)?;
+
+    let mut challenge = [0u8; CHALLENGE_LEN];
+    SystemRandom::new()
+        .fill(&mut challenge)
+        .map_err(|_| PFError::Io("Failed to generate a challenge".into()))?;
+
+    // The firmware signs the 32 bytes it is given as a SHA-256 digest, so send the hash of the
+    // challenge and let ring hash the challenge itself when verifying.
+    let challenge_digest = digest::digest(&digest::SHA256, &challenge);
+    let sig = keydev_apdu(&card, SignParam::SignData, challenge_digest.as_ref())?;
+    let signature_valid =
+        signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, &public_key)
+            .verify(&challenge, &sig)
+            .is_ok();
+
+    let public_key_hex = hex::encode_upper(&public_key);
+    let mut pins = load_pins();
+    let pin = match serial.as_ref().map(|s| pins.get(s)) {
+        None => DeviceKeyPin::NoSerial,
+        Some(Some(known)) if *known == public_key_hex => DeviceKeyPin::Matches,
+        Some(Some(known)) => DeviceKeyPin::Changed {
+            previous_fingerprint: hex::decode(known)
+                .map(|k| fingerprint(&k))
+                .unwrap_or_else(|_| known.clone()),
+        },
+        Some(None) => DeviceKeyPin::FirstSeen,
+    };
+    // Only pin keys that proved possession; a changed key is never overwritten silently.
+    if let Some(serial) = &serial
+        && signature_valid
+        && pin == DeviceKeyPin::FirstSeen
+    {
+        pins.insert(serial.clone(), public_key_hex.clone());
+        store_pins(&pins)?;
+    }
+
+    log::info!(
+        "Device key check for {:?}: signature valid: {}, pin: {:?}",
+        serial,
+        signature_valid,
+        pin
+    );
+
+    Ok(DeviceAuthenticity {
+        serial,
+        fingerprint: fingerprint(&public_key),
+        public_key: public_key_hex,
+        signature_valid,
+        pin,
+    })
+}
+
+/// Stores a certificate for the device key. The certificate must certify the device's own key.
+pub fn upload_certificate(path: &Path) -> Result<String, PFError> {
+    let data = std::fs::read(path)
+        .map_err(|e| PFError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    let der = match Pem::iter_from_buffer(&data)
+        .filter_map(Result::ok)
+        .find(|pem| pem.label == "CERTIFICATE")
+    {
+        Some(pem) => pem.contents,
+        None => data,
+    };
+    let (_, cert) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| PFError::Io(format!("Not a valid certificate: {}", e)))?;
+
+    let (card, _) = connect_and_select()?;
+    let public_key = read_public_key(&card)?;
+    if cert.public_key().subject_public_key.data.as_ref() != public_key.as_slice() {
+        return Err(PFError::Io(
+            "The certificate is for a different key than this device's".into(),
+        ));
+    }
+
+    keydev_apdu(&card, SignParam::UploadCert, &der)?;
+    log::info!("Uploaded device certificate {}", cert.subject());
+    Ok(format!("Certificate for {} uploaded", cert.subject()))
+}
@@ -3,13 +3,55 @@
 //! For more details checkout the [pico-key-sdk](https://github.com/polhenarejos/pico-keys-sdk/blob/main/src/rescue.c)
 
 pub mod constants;
-
-use crate::device::{error::PFError, rescue::constants::*, types::*};
+pub mod keydev;
+
+use crate::device::{
+    capabilities::{self, FirmwareVersion},
+    error::PFError,
+    fido::hid,
+    rescue::constants::*,
+    types::*,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use pcsc::{Context, Protocols, Scope, ShareMode};
+use std::ffi::CStr;
 use std::io::Cursor;
 
-/// Connects to the first available reader and selects the Rescue Applet
+/// Picks the CCID reader of the key the user works with. pcsc-lite puts the USB serial number in
+/// the reader name, which ties it to the HID device; without a match only a lone reader is used.
+fn pick_reader<'a>(readers: &[&'a CStr]) -> Result<&'a CStr, PFError> {
+    match readers {
+        [] => {
+            log::info!("No Smart Card Reader found");
+            Err(PFError::NoDevice)
+        }
+        [reader] => Ok(reader),
+        _ => {
+            if let Some(serial) = hid::target_serial() {
+                let matching: Vec<&CStr> = readers
+                    .iter()
+                    .copied()
+                    .filter(|r| r.to_string_lossy().contains(&serial))
+                    .collect();
+                if let [reader] = matching.as_slice() {
+                    log::debug!("Using reader {:?} for key {}", reader, serial);
+                    return Ok(reader);
+                }
+            }
+            log::warn!(
+                "{} readers attached, none matches the selected key",
+                readers.len()
+            );
+            Err(PFError::Device(format!(
+                "{} smart card readers are attached and none can be matched to the selected key. \
+                 Unplug the other readers or keys to use rescue operations.",
+                readers.len()
+            )))
+        }
+    }
+}
+
+/// Connects to the reader of the selected key and selects the Rescue Applet
 fn connect_and_select() -> Result<(pcsc::Card, Vec<u8>), PFError> {
     let ctx = Context::establish(Scope::User).map_err(|e| {
         log::error!("Failed to establish PCSC context: {}", e);
@@ -17,13 +59,8 @@ fn connect_and_select() -> Result<(pcsc::Card, Vec<u8>), PFError> {
     })?;
 
     let mut readers_buf = [0; 2048];
-    let mut readers = ctx.list_readers(&mut readers_buf)?;
-
-    // Use the first reader found
-    let reader = readers.next().ok_or_else(|| {
-        log::info!("No Smart Card Reader found");
-        PFError::NoDevice
-    })?;
+    let readers: Vec<&CStr> = ctx.list_readers(&mut readers_buf)?.collect();
+    let reader = pick_reader(&readers)?;
 
     let card = ctx.connect(reader, ShareMode::Shared, Protocols::ANY)?;
 
@@ -102,31 +139,19 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
     }
 
     let mut rdr = Cursor::new(&rx_flash[..rx_flash.len() - 2]);
-    let _free = rdr.read_u32::<BigEndian>().unwrap_or(0);
+    let free = rdr.read_u32::<BigEndian>().unwrap_or(0);
     let used = rdr.read_u32::<BigEndian>().unwrap_or(0);
     let total = rdr.read_u32::<BigEndian>().unwrap_or(0);
-
-    // NOTE: captured but currently unused variables
-    let _nfiles = rdr.read_u32::<BigEndian>().unwrap_or(0);
-    let _chip_size = rdr.read_u32::<BigEndian>().unwrap_or(0);
+    let nfiles = rdr.read_u32::<BigEndian>().unwrap_or(0);
+    let chip_size = rdr.read_u32::<BigEndian>().unwrap_or(0);
 
     // --- Read Secure Boot Status ---
-    let rx_secure = card.transmit(
-        &[
-            APDU_CLA_PROPRIETARY,
-            RescueInstruction::Read as u8,
-            ReadParam::SecureBootStatus as u8,
-            P2_UNUSED,
-            0x00,
-        ],
-        &mut rx_buf,
-    )?;
+    let secure = read_secure_boot_state(&card);
+    if let Err(e) = &secure {
+        log::warn!("{}", e);
+    }
 
-    let (sb_enabled, sb_locked) = if rx_secure.ends_with(&[0x90, 0x00]) && rx_secure.len() >= 4 {
-        (rx_secure[0] != 0, rx_secure[1] != 0)
-    } else {
-        (false, false)
-    }; // --- Read PHY Config ---
+    // --- Read PHY Config ---
     let rx_phy = card.transmit(
         &[
             APDU_CLA_PROPRIETARY,
@@ -229,12 +254,29 @@ pub fn read_device_details() -> Result<FullDeviceStatus, PFError> {
             serial: serial_str,
             flash_used: used / 1024,
             flash_total: total / 1024,
+            flash_free: free / 1024,
+            flash_chip_size: chip_size / 1024,
+            file_count: nfiles,
+            unknown_fields: Vec::new(),
             firmware_version: format!("{}.{}", version_major, version_minor),
         },
         config,
-        secure_boot: sb_enabled,
-        secure_lock: sb_locked,
+        secure_boot: secure.as_ref().is_ok_and(|s| s.enabled),
+        secure_lock: secure.as_ref().is_ok_and(|s| s.locked),
         method: DeviceMethod::Rescue,
+        fido: None,
+        provenance: StatusProvenance {
+            serial: FieldSource::Rescue,
+            firmware_version: FieldSource::Rescue,
+            flash: FieldSource::Rescue,
+            config: FieldSource::Rescue,
+            secure_boot: if secure.is_ok() {
+                FieldSource::Rescue
+            } else {
+                FieldSource::Unverified
+            },
+            fido: FieldSource::Unverified,
+        },
     })
 }
 
@@ -393,28 +435,196 @@ pub fn reboot_device(to_bootsel: bool) -> Result<String, PFError> {
     }
 }
 
-/// UNSTABLE! (WIP)
-pub fn enable_secure_boot(lock: bool) -> Result<String, PFError> {
-    let (card, _) = connect_and_select()?;
+/// Reads `ReadParam::SecureBootStatus`: enabled, locked and, on newer firmware, the boot key slot.
+/// An error or short answer is an error, never "disabled".
+fn read_secure_boot_state(card: &pcsc::Card) -> Result<SecureBootState, PFError> {
+    let mut rx_buf = [0; 256];
+    let rx = card.transmit(
+        &[
+            APDU_CLA_PROPRIETARY,
+            RescueInstruction::Read as u8,
+            ReadParam::SecureBootStatus as u8,
+            P2_UNUSED,
+            0x00,
+        ],
+        &mut rx_buf,
+    )?;
+
+    if !rx.ends_with(&SW_SUCCESS) {
+        return Err(PFError::Device(format!(
+            "Reading the secure boot state failed: {:02X?}",
+            rx
+        )));
+    }
+    if rx.len() < 4 {
+        return Err(PFError::Device(format!(
+            "Secure boot state response too short: {:02X?}",
+            rx
+        )));
+    }
+    let data = &rx[..rx.len() - 2];
+    Ok(SecureBootState {
+        enabled: data[0] != 0,
+        locked: data[1] != 0,
+        boot_key: data.get(2).copied().filter(|&k| k < RP2350_BOOT_KEY_SLOTS),
+    })
+}
 
-    // APDU: 80 1D [KeyIndex] [LockBool] 00
-    // KeyIndex = 0 (Default), LockBool = 1 if true
-    let lock_byte = if lock { 0x01 } else { 0x00 };
+fn chip_family(id: u8) -> ChipFamily {
+    match id {
+        MCU_RP2040 => ChipFamily::Rp2040,
+        MCU_RP2350 => ChipFamily::Rp2350,
+        MCU_ESP32 => ChipFamily::Esp32,
+        other => ChipFamily::Unknown(other),
+    }
+}
 
+fn preflight(card: &pcsc::Card, select_resp: &[u8]) -> Result<SecureBootPreflight, PFError> {
+    if select_resp.len() < 6 {
+        return Err(PFError::Device("Invalid select response".into()));
+    }
+    let chip = chip_family(select_resp[0]);
+    let version = FirmwareVersion {
+        major: select_resp[2],
+        minor: select_resp[3],
+    };
+    let serial = (select_resp.len() >= 14).then(|| hex::encode_upper(&select_resp[4..12]));
+    let state = read_secure_boot_state(card);
+
+    let mut blockers = Vec::new();
+    if let Err(e) = &state {
+        blockers.push(format!("The current secure boot state is unknown: {}", e));
+    }
+    let state = state.unwrap_or_default();
+    if chip != ChipFamily::Rp2350 {
+        blockers.push(format!(
+            "Secure boot is only supported on the RP2350, this device is an {}",
+            chip
+        ));
+    }
+    // Other features are tried on unverified releases; burning OTP is not something to try.
+    if let Err(reason) = capabilities::firmware_verified(version, Feature::RescueSecureBoot) {
+        blockers.push(reason);
+    }
+    if serial.is_none() {
+        blockers.push("The device did not report a serial number to confirm against".into());
+    }
+    if state.locked {
+        blockers.push("Secure lock is already set, the boot configuration is final".into());
+    }
+
+    Ok(SecureBootPreflight {
+        serial: serial.unwrap_or_default(),
+        firmware_version: version.to_string(),
+        chip,
+        state,
+        blockers,
+    })
+}
+
+/// Checks whether secure boot can be enabled on the connected device.
+pub fn secure_boot_preflight() -> Result<SecureBootPreflight, PFError> {
+    let (card, select_resp) = connect_and_select()?;
+    preflight(&card, &select_resp)
+}
+
+/// Enables secure boot with the given boot key slot and optionally sets secure lock.
+///
+/// The pre-flight checks are repeated on the same connection and `confirm_serial` must match the
+/// connected device, so a key swapped after the user confirmed is never written. The OTP state is
+/// read back afterwards and the call fails unless it reflects the request.
+///
+/// The rescue applet cannot read the BOOTKEY slots, so `confirmed_key_hash` is the OTP hash the
+/// user confirmed they burned into the slot and verified with picotool. It is only checked for
+/// form and recorded in the log.
+pub fn enable_secure_boot(
+    boot_key: u8,
+    lock: bool,
+    confirm_serial: &str,
+    confirmed_key_hash: &str,
+) -> Result<SecureBootState, PFError> {
+    if boot_key >= RP2350_BOOT_KEY_SLOTS {
+        return Err(PFError::Io(format!(
+            "Boot key index must be below {}",
+            RP2350_BOOT_KEY_SLOTS
+        )));
+    }
+    if confirmed_key_hash.len() != 64 || !confirmed_key_hash.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(PFError::Io(
+            "Confirm the SHA-256 hash burned into the boot key slot first".into(),
+        ));
+    }
+
+    let (card, select_resp) = connect_and_select()?;
+    let checks = preflight(&card, &select_resp)?;
+    if let Some(blocker) = checks.blockers.first() {
+        return Err(PFError::Device(blocker.clone()));
+    }
+    if !checks.serial.eq_ignore_ascii_case(confirm_serial.trim()) {
+        return Err(PFError::Io(
+            "The confirmed serial does not match the connected device".into(),
+        ));
+    }
+    if checks.state.enabled {
+        if checks.state.boot_key.is_some_and(|k| k != boot_key) {
+            return Err(PFError::Device(format!(
+                "Secure boot is already enabled with boot key {}",
+                checks.state.boot_key.unwrap_or_default()
+            )));
+        }
+        if !lock {
+            return Err(PFError::Device("Secure boot is already enabled".into()));
+        }
+    }
+
+    log::warn!(
+        "Enabling secure boot on {} with boot key {} confirmed to hold {} (lock: {})",
+        checks.serial,
+        boot_key,
+        confirmed_key_hash,
+        lock
+    );
+
+    let lock_param = if lock {
+        SecureLockParam::Lock
+    } else {
+        SecureLockParam::Unlock
+    };
     let apdu = [
         APDU_CLA_PROPRIETARY,
         RescueInstruction::Secure as u8,
-        0x00, // Boot Key Index (0 = Default)
-        lock_byte as u8,
+        boot_key,
+        lock_param as u8,
         0x00,
     ];
 
     let mut rx_buf = [0; 256];
     let rx = card.transmit(&apdu, &mut rx_buf)?;
+    if !rx.ends_with(&SW_SUCCESS) {
+        return Err(PFError::Device(format!("Secure Boot failed: {:02X?}", rx)));
+    }
 
-    if rx.ends_with(&[0x90, 0x00]) {
-        Ok("Secure Boot Enabled".into())
-    } else {
-        Err(PFError::Device(format!("Secure Boot failed: {:02X?}", rx)))
+    let state = read_secure_boot_state(&card).map_err(|e| {
+        PFError::Device(format!(
+            "The device accepted the command but its state could not be read back ({}). \
+             Reconnect and check the secure boot state before trying again.",
+            e
+        ))
+    })?;
+    log::info!("Secure boot state after write: {:?}", state);
+    if !state.enabled || (lock && !state.locked) {
+        return Err(PFError::Device(
+            "The device accepted the command but does not report secure boot as enabled".into(),
+        ));
     }
+    if state.boot_key.is_some_and(|k| k != boot_key) {
+        return Err(PFError::Device(format!(
+            "The device reports boot key {} instead of {}",
+            state.boot_key.unwrap_or_default(),
+            boot_key
+        )));
+    }
+
+    Ok(state)
 }
@@ -0,0 +1,80 @@
+//! Merges what the rescue and FIDO interfaces report into one device status.
+//!
+//! Rescue knows the serial, the secure boot state and the full physical config; FIDO knows the
+//! AAGUID, options and PIN state. When both answer, each field is taken from the interface that
+//! can actually read it and `StatusProvenance` records which one that was.
+//!
+//! The rescue reader and the FIDO device are found independently, so before merging the two
+//! readings have to agree on the USB identity and firmware. If they do not, they may come from
+//! different keys and only the FIDO reading is used, with the rescue-only fields unverified.
+
+use crate::device::{
+    capabilities::FirmwareVersion,
+    error::PFError,
+    types::{ConfigField, FieldSource, FullDeviceStatus},
+};
+
+/// Why `rescue` and `fido` cannot be the same device, if they disagree on something both report.
+fn mismatch(rescue: &FullDeviceStatus, fido: &FullDeviceStatus) -> Option<String> {
+    let (r, f) = (&rescue.config, &fido.config);
+    if r.is_known(ConfigField::VidPid)
+        && (!r.vid.eq_ignore_ascii_case(&f.vid) || !r.pid.eq_ignore_ascii_case(&f.pid))
+    {
+        return Some(format!(
+            "rescue reports {}:{} but FIDO reports {}:{}",
+            r.vid, r.pid, f.vid, f.pid
+        ));
+    }
+    if !r.product_name.is_empty() && !f.product_name.is_empty() && r.product_name != f.product_name
+    {
+        return Some(format!(
+            "rescue reports product \"{}\" but FIDO reports \"{}\"",
+            r.product_name, f.product_name
+        ));
+    }
+    let (r, f) = (&rescue.info.firmware_version, &fido.info.firmware_version);
+    let same_firmware = match (FirmwareVersion::parse(r), FirmwareVersion::parse(f)) {
+        (Some(a), Some(b)) => a == b,
+        _ => r == f,
+    };
+    if !same_firmware {
+        return Some(format!(
+            "rescue reports firmware {} but FIDO reports {}",
+            r, f
+        ));
+    }
+    None
+}
+
+pub(crate) fn merge(
+    rescue: Result<FullDeviceStatus, PFError>,
+    fido: Result<FullDeviceStatus, PFError>,
+) -> Result<FullDeviceStatus, PFError> {
+    match (rescue, fido) {
+        (Ok(mut status), Ok(fido)) => {
+            if let Some(reason) = mismatch(&status, &fido) {
+                // The FIDO reading already marks serial and secure boot as unverified.
+                log::warn!(
+                    "Rescue and FIDO readings do not match ({}), using FIDO only",
+                    reason
+                );
+                return Ok(fido);
+            }
+            status.fido = fido.fido;
+            status.provenance.fido = FieldSource::Fido;
+            Ok(status)
+        }
+        (Ok(status), Err(e)) => {
+            log::info!("FIDO interface unavailable ({}), using rescue only", e);
+            Ok(status)
+        }
+        (Err(e), Ok(status)) => {
+            log::warn!("Rescue interface unavailable ({}), using FIDO only", e);
+            Ok(status)
+        }
+        (Err(rescue_err), Err(fido_err)) => {
+            log::warn!("Rescue method failed: {}", rescue_err);
+            Err(fido_err)
+        }
+    }
+}
@@ -10,9 +10,34 @@ struct PForgeState {
 #[serde(rename_all = "camelCase")]
 pub struct DeviceInfo {
     pub serial: String,
+    /// Flash figures are in KB.
     pub flash_used: u32,
     pub flash_total: u32,
+    pub flash_free: u32,
+    /// Size of the flash chip, of which `flash_total` is reserved for the file system.
+    pub flash_chip_size: u32,
+    pub file_count: u32,
     pub firmware_version: String,
+    /// Storage figures the device did not report. Their values above are zero, not device state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_fields: Vec<FlashField>,
+}
+
+impl DeviceInfo {
+    pub fn is_known(&self, field: FlashField) -> bool {
+        !self.unknown_fields.contains(&field)
+    }
+}
+
+/// Storage figures of [`DeviceInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlashField {
+    Used,
+    Total,
+    Free,
+    ChipSize,
+    FileCount,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
@@ -30,6 +55,48 @@ pub struct AppConfig {
     pub power_cycle_on_reset: bool,
     pub led_steady: bool,
     pub enable_secp256k1: bool,
+    /// Fields the device did not report. Their values above are defaults, not device state.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_fields: Vec<ConfigField>,
+}
+
+impl AppConfig {
+    pub fn is_known(&self, field: ConfigField) -> bool {
+        !self.unknown_fields.contains(&field)
+    }
+}
+
+/// Physical configuration fields, as reported by the read paths and written by `write_config`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigField {
+    VidPid,
+    ProductName,
+    LedGpio,
+    LedBrightness,
+    TouchTimeout,
+    LedDriver,
+    LedDimmable,
+    PowerCycleOnReset,
+    LedSteady,
+    EnableSecp256k1,
+}
+
+impl ConfigField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::VidPid => "VID/PID",
+            Self::ProductName => "Product name",
+            Self::LedGpio => "LED GPIO",
+            Self::LedBrightness => "LED brightness",
+            Self::TouchTimeout => "Touch timeout",
+            Self::LedDriver => "LED driver",
+            Self::LedDimmable => "LED dimmable",
+            Self::PowerCycleOnReset => "Power cycle on reset",
+            Self::LedSteady => "LED steady",
+            Self::EnableSecp256k1 => "secp256k1",
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -48,6 +115,72 @@ pub struct AppConfigInput {
     pub enable_secp256k1: Option<bool>,
 }
 
+impl AppConfigInput {
+    /// The fields this input changes.
+    pub fn fields(&self) -> Vec<ConfigField> {
+        [
+            (
+                ConfigField::VidPid,
+                self.vid.is_some() && self.pid.is_some(),
+            ),
+            (ConfigField::ProductName, self.product_name.is_some()),
+            (ConfigField::LedGpio, self.led_gpio.is_some()),
+            (ConfigField::LedBrightness, self.led_brightness.is_some()),
+            (ConfigField::TouchTimeout, self.touch_timeout.is_some()),
+            (ConfigField::LedDriver, self.led_driver.is_some()),
+            (ConfigField::LedDimmable, self.led_dimmable.is_some()),
+            (
+                ConfigField::PowerCycleOnReset,
+                self.power_cycle_on_reset.is_some(),
+            ),
+            (ConfigField::LedSteady, self.led_steady.is_some()),
+            (
+                ConfigField::EnableSecp256k1,
+                self.enable_secp256k1.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(field, set)| set.then_some(field))
+        .collect()
+    }
+}
+
+/// Outcome of writing one configuration field.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFieldResult {
+    pub field: ConfigField,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigWriteReport {
+    pub results: Vec<ConfigFieldResult>,
+}
+
+impl ConfigWriteReport {
+    pub fn succeeded(fields: Vec<ConfigField>) -> Self {
+        Self {
+            results: fields
+                .into_iter()
+                .map(|field| ConfigFieldResult { field, error: None })
+                .collect(),
+        }
+    }
+
+    pub fn record(&mut self, field: ConfigField, result: &Result<(), String>) {
+        self.results.push(ConfigFieldResult {
+            field,
+            error: result.as_ref().err().cloned(),
+        });
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|r| r.error.is_some()).count()
+    }
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FullDeviceStatus {
@@ -56,6 +189,32 @@ pub struct FullDeviceStatus {
     pub secure_boot: bool,
     pub secure_lock: bool,
     pub method: DeviceMethod,
+    /// GetInfo of the FIDO interface, if it answered.
+    pub fido: Option<FidoDeviceInfo>,
+    pub provenance: StatusProvenance,
+}
+
+/// Interface a status value was read from.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldSource {
+    Rescue,
+    Fido,
+    /// Not reported by any available interface; the value is a placeholder.
+    #[default]
+    Unverified,
+}
+
+/// Where each part of a `FullDeviceStatus` came from.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusProvenance {
+    pub serial: FieldSource,
+    pub firmware_version: FieldSource,
+    pub flash: FieldSource,
+    pub config: FieldSource,
+    pub secure_boot: FieldSource,
+    /// AAGUID, options and PIN state.
+    pub fido: FieldSource,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -67,6 +226,15 @@ pub enum DeviceMethod {
 
 // Fido stuff:
 
+/// The key picked by touch when several are attached.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectedAuthenticator {
+    pub product_name: String,
+    pub vid: String,
+    pub pid: String,
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FidoDeviceInfo {
@@ -76,9 +244,45 @@ pub struct FidoDeviceInfo {
     pub options: std::collections::HashMap<String, bool>,
     pub max_msg_size: i32,
     pub pin_protocols: Vec<u32>,
-    // pub remaining_disc_creds: u32,
+    pub remaining_disc_creds: Option<u32>,
     pub min_pin_length: u32,
     pub firmware_version: String,
+    pub pin_complexity_policy: PinComplexityPolicy,
+    /// COSE algorithm identifiers, in the authenticator's order of preference.
+    pub algorithms: Vec<i32>,
+    pub max_credential_count_in_list: Option<u32>,
+    pub max_credential_id_length: Option<u32>,
+    pub transports: Vec<String>,
+    pub max_serialized_large_blob_array: Option<u32>,
+    pub force_pin_change: bool,
+    pub max_cred_blob_length: Option<u32>,
+    pub max_rp_ids_for_set_min_pin_length: Option<u32>,
+    pub preferred_platform_uv_attempts: Option<u32>,
+    pub uv_modality: Option<u32>,
+    /// Certification program name to level, e.g. "FIDO" to 1.
+    pub certifications: std::collections::BTreeMap<String, i64>,
+    pub vendor_prototype_config_commands: Vec<u64>,
+    pub attestation_formats: Vec<String>,
+    pub uv_count_since_last_pin_entry: Option<u32>,
+    pub long_touch_for_reset: bool,
+    pub transports_for_reset: Vec<String>,
+    pub max_pin_length: Option<u32>,
+}
+
+/// PIN complexity rules enforced by the firmware on top of `min_pin_length`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PinComplexityPolicy {
+    /// `pinComplexityPolicy` from GetInfo: the key enforces a policy whose rules it does not
+    /// report.
+    pub enforced: bool,
+    /// Rules to request with the vendor command. They are never read back, so a policy decoded
+    /// from GetInfo leaves them unset.
+    pub no_sequences: bool,
+    pub no_repeats: bool,
+    pub mixed_classes: bool,
+    /// `pinComplexityPolicyURL` from GetInfo, if the firmware publishes one.
+    pub url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -90,4 +294,511 @@ pub struct StoredCredential {
     pub user_display_name: String,
     pub user_id: String,
     pub credential_id: String,
+    pub public_key: Option<CredentialPublicKey>,
+    /// credProtect level (1 = UV optional, 2 = UV optional with credential id list, 3 = UV required).
+    pub cred_protect: Option<u8>,
+    pub has_large_blob_key: bool,
+}
+
+/// COSE public key of a resident credential, decoded for display.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialPublicKey {
+    pub algorithm_id: i32,
+    pub algorithm: String,
+    pub key_type: String,
+    pub curve: Option<String>,
+    /// Hex of the CBOR-encoded COSE key as returned by the device.
+    pub cose_key: String,
+    /// SHA-256 over the CBOR-encoded COSE key, colon separated.
+    pub fingerprint: String,
+    /// SubjectPublicKeyInfo in PEM form, if the key type is known.
+    pub pem: Option<String>,
+}
+
+/// Resident credential storage usage from getCredsMetadata.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialsMetadata {
+    pub existing_resident_credentials_count: u32,
+    pub max_possible_remaining_resident_credentials_count: u32,
+    /// Firmware limit on resident credentials (`MAX_RESIDENT_CREDENTIALS`).
+    pub capacity: u32,
+}
+
+/// File format of an exported passkey inventory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InventoryFormat {
+    Json,
+    Csv,
+}
+
+impl InventoryFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// OpenSSH security key types backed by a FIDO credential.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshKeyType {
+    /// `sk-ecdsa-sha2-nistp256@openssh.com`
+    EcdsaSk,
+    /// `sk-ssh-ed25519@openssh.com`
+    Ed25519Sk,
+}
+
+impl SshKeyType {
+    /// File name ssh-keygen uses for a resident key of this type.
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            Self::EcdsaSk => "id_ecdsa_sk_rk",
+            Self::Ed25519Sk => "id_ed25519_sk_rk",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SshKeyRequest {
+    pub key_type: SshKeyType,
+    /// FIDO application, always starting with `ssh:`.
+    pub application: String,
+    /// Stored as the credential's user; `None` uses OpenSSH's default.
+    pub user: Option<String>,
+    /// Require the PIN on every use (`verify-required`).
+    pub verify_required: bool,
+    /// Replace a resident key with the same application and user, which the device overwrites.
+    pub overwrite: bool,
+}
+
+/// Result of writing the resident OpenSSH keys to disk.
+#[derive(Debug, Clone)]
+pub struct SshExport {
+    pub written: Vec<std::path::PathBuf>,
+    /// Credentials that cannot be used with OpenSSH, with the reason.
+    pub skipped: Vec<String>,
+}
+
+/// How a device key compares with the one seen before for the same serial.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum DeviceKeyPin {
+    /// No key was recorded for this serial; this one is now.
+    FirstSeen,
+    Matches,
+    /// The serial answered with a different key than before.
+    Changed {
+        previous_fingerprint: String,
+    },
+    /// The device did not report its serial, so the key was neither compared nor recorded.
+    NoSerial,
+}
+
+/// Result of a device key challenge.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAuthenticity {
+    /// `None` when the rescue applet's SELECT response was too short to carry it.
+    pub serial: Option<String>,
+    /// Uncompressed P-256 point, hex.
+    pub public_key: String,
+    /// SHA-256 over the public key, colon separated.
+    pub fingerprint: String,
+    pub signature_valid: bool,
+    pub pin: DeviceKeyPin,
+}
+
+impl DeviceAuthenticity {
+    /// A valid signature from a key that is, or now is, pinned to the device's serial.
+    pub fn authentic(&self) -> bool {
+        self.signature_valid && matches!(self.pin, DeviceKeyPin::FirstSeen | DeviceKeyPin::Matches)
+    }
+}
+
+/// Device operations whose availability depends on the firmware.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    RescueConfig,
+    RescueSecureBoot,
+    RescueDeviceKey,
+    FidoPhysicalConfig,
+    FidoBackup,
+    FidoAuthEncryption,
+    FidoPinPolicy,
+    CredentialManagement,
+    LargeBlobs,
+    MinPinLength,
+    HmacSecret,
+    /// makeCredential / getAssertion round trips run by the diagnostics.
+    Registration,
+}
+
+impl Feature {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RescueConfig => "Configuration over rescue",
+            Self::RescueSecureBoot => "Secure boot",
+            Self::RescueDeviceKey => "Device key operations",
+            Self::FidoPhysicalConfig => "Configuration over FIDO",
+            Self::FidoBackup => "Seed backup",
+            Self::FidoAuthEncryption => "Storage encryption",
+            Self::FidoPinPolicy => "PIN complexity policy",
+            Self::CredentialManagement => "Passkey management",
+            Self::LargeBlobs => "Large blobs",
+            Self::MinPinLength => "Minimum PIN length",
+            Self::HmacSecret => "hmac-secret",
+            Self::Registration => "Test registration",
+        }
+    }
+}
+
+/// Microcontroller reported by the rescue applet.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipFamily {
+    Rp2040,
+    Rp2350,
+    Esp32,
+    Unknown(u8),
+}
+
+impl std::fmt::Display for ChipFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rp2040 => write!(f, "RP2040"),
+            Self::Rp2350 => write!(f, "RP2350"),
+            Self::Esp32 => write!(f, "ESP32"),
+            Self::Unknown(id) => write!(f, "Unknown chip ({:#04X})", id),
+        }
+    }
+}
+
+/// Secure boot OTP state as read through the rescue applet.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureBootState {
+    pub enabled: bool,
+    pub locked: bool,
+    /// Boot key slot in use, when the firmware reports it.
+    pub boot_key: Option<u8>,
+}
+
+/// Everything checked before secure boot may be enabled.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureBootPreflight {
+    pub serial: String,
+    pub firmware_version: String,
+    pub chip: ChipFamily,
+    pub state: SecureBootState,
+    /// Reasons the device cannot be locked; empty when it can.
+    pub blockers: Vec<String>,
+}
+
+impl SecureBootPreflight {
+    pub fn ready(&self) -> bool {
+        self.blockers.is_empty()
+    }
+}
+
+/// A secp256k1 secure boot signing key.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BootKeyInfo {
+    pub path: String,
+    /// x || y, hex.
+    pub public_key: String,
+    /// SHA-256 of the public key, the value a BOOTKEY OTP slot must hold.
+    pub otp_hash: String,
+}
+
+/// Result of signing a UF2 image.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedImage {
+    pub output: String,
+    pub image_size: u32,
+    /// SHA-256 the signature was made over.
+    pub image_hash: String,
+    pub boot_key: BootKeyInfo,
+}
+
+/// One row of an exported passkey inventory.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyInventoryEntry {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub user_name: String,
+    pub user_display_name: String,
+    pub user_id: String,
+    pub credential_id: String,
+    pub algorithm: String,
+    pub cred_protect: Option<u8>,
+    /// Hex of the CBOR-encoded COSE key.
+    pub public_key_cose: String,
+    pub public_key_pem: String,
+    pub public_key_fingerprint: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyInventory {
+    pub exported_at: String,
+    pub aaguid: Option<String>,
+    pub credentials: Vec<PasskeyInventoryEntry>,
+}
+
+/// Outcome of deleting one credential as part of a bulk operation.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialDeleteResult {
+    pub credential_id: String,
+    pub rp_id: String,
+    pub user_name: String,
+    /// `None` if the credential was deleted.
+    pub error: Option<String>,
+}
+
+// Diagnostics stuff:
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// One step of a diagnostic run.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+}
+
+/// Result of the hmac-secret extension test.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacSecretReport {
+    /// How the salt was obtained from the input ("Raw salt" or "PRF input").
+    pub salt_source: String,
+    pub user_verified: bool,
+    pub checks: Vec<DiagnosticCheck>,
+    /// Hex encoded output of the first assertion, empty if it failed.
+    pub output: String,
+}
+
+impl HmacSecretReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+}
+
+/// Outcome of trying to register a credential with one signature algorithm.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmSupport {
+    pub algorithm_id: i32,
+    pub algorithm: String,
+    /// Listed in GetInfo `algorithms`.
+    pub advertised: bool,
+    /// `Passed` if a credential was created, `Failed` if rejected, `Skipped` if undetermined.
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// One certificate of an attestation chain, leaf first.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub not_before: String,
+    pub not_after: String,
+    pub key_algorithm: String,
+    pub currently_valid: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub enum AttestationTrust {
+    /// The chain ends in an MDS vendor root or a user supplied trust anchor (its subject).
+    Trusted(String),
+    /// The chain ends in a certificate that signed itself.
+    SelfSigned,
+    /// The statement has no certificate (self attestation or none).
+    NoCertificate,
+    Untrusted(String),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationReport {
+    pub format: String,
+    /// AAGUID from the attested credential data.
+    pub aaguid: String,
+    pub signature_valid: bool,
+    pub signature_detail: String,
+    pub certificates: Vec<AttestationCertificate>,
+    pub trust: AttestationTrust,
+}
+
+// Metadata service stuff:
+
+/// Icon embedded in a metadata statement as a `data:` URL.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataIcon {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// What the FIDO Metadata Service declares about one authenticator model.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorMetadata {
+    pub aaguid: String,
+    pub description: String,
+    pub authenticator_version: Option<u64>,
+    /// Most recent certification status, e.g. "FIDO Certified L1" or "Not FIDO Certified".
+    pub certification: String,
+    /// Most recent status report of any kind, which may flag a revocation or compromise.
+    pub latest_status: String,
+    pub status_date: Option<String>,
+    pub icon: Option<MetadataIcon>,
+    pub attestation_roots: Vec<AttestationCertificate>,
+}
+
+/// A verified MDS3 BLOB as stored in the data directory.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataBlobInfo {
+    /// The BLOB serial number (`no`).
+    pub number: u64,
+    pub next_update: String,
+    pub entries: usize,
+    /// Subject of the certificate that signed the BLOB.
+    pub signer: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataLookup {
+    /// The AAGUID that was looked up.
+    pub aaguid: String,
+    pub blob: MetadataBlobInfo,
+    /// `None` when the BLOB has no entry for the AAGUID.
+    pub entry: Option<AuthenticatorMetadata>,
+}
+
+// Backup stuff:
+
+/// On-disk representation of an encrypted pico-fido seed backup.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FidoBackup {
+    pub format_version: u32,
+    pub serial: String,
+    pub firmware_version: String,
+    pub aaguid: String,
+    pub created_at: String,
+    /// Hex encoded blob returned by the firmware, empty if only a mnemonic was exposed.
+    pub encrypted_backup: String,
+    /// Credential created from the seed when the backup was taken, used to check a restore.
+    #[serde(default)]
+    pub seed_check: Option<SeedCheck>,
+}
+
+/// A non-discoverable credential. Its ID is wrapped with a key derived from the seed and its key
+/// pair is re-derived from the seed on every use, so only a key holding the same seed can sign
+/// with it again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedCheck {
+    pub rp_id: String,
+    /// Hex encoded credential ID.
+    pub credential_id: String,
+    /// Hex encoded CBOR COSE public key returned at registration.
+    pub public_key: String,
+}
+
+/// Result of reading a backup out of the device. The mnemonic is never written to disk.
+#[derive(Debug, Clone)]
+pub struct FidoBackupExport {
+    pub backup: FidoBackup,
+    pub mnemonic: Vec<String>,
+}
+
+/// Whether the restored key signs the backup's seed check credential with the recorded key.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVerification {
+    pub seed_restored: bool,
+    pub detail: String,
+}
+
+// Storage encryption stuff:
+
+/// Storage encryption state as answered by the device.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageEncryption {
+    /// The firmware has no read-only query for it; known once the device answered an enable,
+    /// disable or unlock request.
+    Unknown,
+    Encrypted,
+    Plain,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthEncryptionStatus {
+    pub state: StorageEncryption,
+    /// Whether this computer holds the unlock secret for the device with this serial.
+    pub has_secret: bool,
+    pub secret_path: String,
+}
+
+// Large blob stuff:
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeBlobEntry {
+    /// Position in the serialized large-blob array.
+    pub index: usize,
+    /// Hex SHA-256 of the ciphertext and nonce, used to delete exactly this entry.
+    pub fingerprint: String,
+    pub stored_size: usize,
+    pub orig_size: u64,
+    /// Owner of the entry, if one of the resident credentials' largeBlobKey decrypted it.
+    pub rp_id: Option<String>,
+    pub user_name: Option<String>,
+    pub credential_id: Option<String>,
+    /// Decrypted and decompressed content.
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeBlobStorage {
+    pub entries: Vec<LargeBlobEntry>,
+    pub used_bytes: usize,
+    pub max_bytes: usize,
+    /// False when the SHA-256 trailer did not match; the array is then treated as empty.
+    pub integrity_ok: bool,
 }
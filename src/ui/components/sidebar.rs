@@ -19,6 +19,7 @@ pub struct AppSidebar<V: 'static> {
     state: GlobalDeviceState,
     on_select: Option<Rc<dyn Fn(&mut V, ActiveView, &mut Window, &mut Context<V>)>>,
     on_refresh: Option<Rc<dyn Fn(&mut V, &mut Window, &mut Context<V>)>>,
+    on_select_key: Option<Rc<dyn Fn(&mut V, &mut Window, &mut Context<V>)>>,
 }
 
 impl<V: 'static> AppSidebar<V> {
@@ -35,6 +36,7 @@ impl<V: 'static> AppSidebar<V> {
             state,
             on_select: None,
             on_refresh: None,
+            on_select_key: None,
         }
     }
 
@@ -54,6 +56,14 @@ impl<V: 'static> AppSidebar<V> {
         self
     }
 
+    pub fn on_select_key(
+        mut self,
+        handler: impl Fn(&mut V, &mut Window, &mut Context<V>) + 'static,
+    ) -> Self {
+        self.on_select_key = Some(Rc::new(handler));
+        self
+    }
+
     pub fn render(self, cx: &mut Context<V>) -> impl IntoElement {
         let width = self.width;
         let collapsed = self.collapsed;
@@ -64,6 +74,7 @@ impl<V: 'static> AppSidebar<V> {
 
         let on_refresh = self.on_refresh.clone();
         let on_refresh_collapsed = self.on_refresh.clone();
+        let on_select_key = self.on_select_key.clone();
 
         v_flex()
             .h_full()
@@ -156,6 +167,12 @@ impl<V: 'static> AppSidebar<V> {
                                     "icons/shield-check.svg",
                                     ActiveView::Security,
                                 ))
+                                .child(self.menu_item(
+                                    cx,
+                                    "Diagnostics",
+                                    "icons/inspector.svg",
+                                    ActiveView::Diagnostics,
+                                ))
                                 .child(self.menu_item(
                                     cx,
                                     "Logs",
@@ -260,6 +277,7 @@ impl<V: 'static> AppSidebar<V> {
                                     Icon::default().path("icons/refresh-cw.svg"),
                                     "Refresh",
                                 )
+                                .disabled(state.selecting_authenticator)
                                 .on_click(cx.listener(
                                     move |this, _, window, cx| {
                                         if let Some(f) = &on_refresh {
@@ -268,6 +286,38 @@ impl<V: 'static> AppSidebar<V> {
                                     },
                                 )),
                             )
+                            .children((state.authenticator_count > 1).then(|| {
+                                v_flex()
+                                    .gap_2()
+                                    .child(
+                                        div()
+                                            .text_size(px(12.))
+                                            .text_color(muted_foreground)
+                                            .child(if state.selecting_authenticator {
+                                                "Touch the key you want to use".to_string()
+                                            } else {
+                                                format!(
+                                                    "{} keys attached",
+                                                    state.authenticator_count
+                                                )
+                                            }),
+                                    )
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/key.svg"),
+                                            "Switch Key",
+                                        )
+                                        .disabled(state.selecting_authenticator)
+                                        .loading(state.selecting_authenticator)
+                                        .on_click(
+                                            cx.listener(move |this, _, window, cx| {
+                                                if let Some(f) = &on_select_key {
+                                                    f(this, window, cx);
+                                                }
+                                            }),
+                                        ),
+                                    )
+                            }))
                     }),
             )
     }
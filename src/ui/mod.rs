@@ -7,6 +7,7 @@
 pub mod assets;
 pub mod colors;
 pub mod components;
+pub mod paths;
 pub mod rootview;
 pub mod ui_types;
 pub mod views;
@@ -0,0 +1,15 @@
+//! Helpers for the native file prompts used by the import/export actions.
+
+use directories::UserDirs;
+use std::path::PathBuf;
+
+/// Directory the save/open prompts start in: Documents, then home, then the working directory.
+pub fn default_export_dir() -> PathBuf {
+    UserDirs::new()
+        .map(|dirs| {
+            dirs.document_dir()
+                .unwrap_or_else(|| dirs.home_dir())
+                .to_path_buf()
+        })
+        .unwrap_or_else(|| PathBuf::from("."))
+}
@@ -4,8 +4,9 @@ use crate::ui::ui_types::{ActiveView, GlobalDeviceState};
 use crate::ui::{
     colors,
     views::{
-        about::AboutView, config::ConfigView, home::HomeView, logs::LogsView,
-        passkeys::PasskeysEvent, passkeys::PasskeysView, security::SecurityView,
+        about::AboutView, config::ConfigView, diagnostics::DiagnosticsEvent,
+        diagnostics::DiagnosticsView, home::HomeView, logs::LogsView, passkeys::PasskeysEvent,
+        passkeys::PasskeysView, security::SecurityEvent, security::SecurityView,
     },
 };
 
@@ -28,6 +29,8 @@ pub struct ApplicationRoot {
     sidebar_width: Pixels,
     config_view: Option<Entity<ConfigView>>,
     passkeys_view: Option<Entity<PasskeysView>>,
+    security_view: Option<Entity<SecurityView>>,
+    diagnostics_view: Option<Entity<DiagnosticsView>>,
     logs_view: Option<Entity<LogsView>>,
 }
 
@@ -41,6 +44,8 @@ impl ApplicationRoot {
             sidebar_width: px(255.),
             config_view: None,
             passkeys_view: None,
+            security_view: None,
+            diagnostics_view: None,
             logs_view: None,
         };
         this.refresh_device_status(None, cx);
@@ -52,6 +57,13 @@ impl ApplicationRoot {
             return;
         }
 
+        self.state.authenticator_count = io::count_authenticators();
+        if self.state.authenticator_count > 1 && !io::has_selected_authenticator() {
+            // Same as a browser: ask which key to use before talking to any of them.
+            self.select_authenticator(cx);
+            return;
+        }
+
         self.device_loading = true;
         self.state.error = None;
         cx.notify();
@@ -61,15 +73,18 @@ impl ApplicationRoot {
                 self.state.device_status = Some(status.clone());
                 self.state.error = None;
 
-                match io::get_fido_info() {
-                    Ok(fido) => {
-                        self.state.fido_info = Some(fido);
-                    }
-                    Err(e) => {
-                        eprintln!("FIDO Info fetch failed: {}", e);
-                        self.state.fido_info = None;
+                // The merged status already carries GetInfo when the FIDO interface answered.
+                if let Some(fido) = &status.fido {
+                    let aaguid_changed = self
+                        .state
+                        .metadata
+                        .as_ref()
+                        .is_none_or(|m| m.aaguid != fido.aaguid);
+                    if aaguid_changed {
+                        self.lookup_metadata(fido.aaguid.clone(), cx);
                     }
                 }
+                self.state.fido_info = status.fido.clone();
 
                 if let Some(config_view) = &self.config_view {
                     if let Some(window) = window {
@@ -85,16 +100,178 @@ impl ApplicationRoot {
                         view.update_device_status(Some(status.clone()), fido, cx);
                     });
                 }
+
+                if let Some(security_view) = &self.security_view {
+                    security_view.update(cx, |view, cx| {
+                        view.update_device_status(Some(status.clone()), cx);
+                    });
+                }
+
+                if let Some(diagnostics_view) = &self.diagnostics_view {
+                    diagnostics_view.update(cx, |view, cx| {
+                        view.update_device_status(Some(status.clone()), cx);
+                    });
+                }
             }
             Err(e) => {
                 self.state.device_status = None;
                 self.state.error = Some(format!("{}", e));
                 self.state.fido_info = None;
+                self.state.attestation = None;
+                self.state.metadata = None;
             }
         }
         self.device_loading = false;
         cx.notify();
     }
+
+    fn inspect_attestation(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.state.attestation_loading {
+            return;
+        }
+        self.state.attestation_loading = true;
+        window.push_notification("Touch your key when it blinks", cx);
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::inspect_attestation() })
+                .await;
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.state.attestation_loading = false;
+                match result {
+                    Ok(report) => this.state.attestation = Some(report),
+                    Err(e) => window
+                        .push_notification(format!("Attestation inspection failed: {}", e), cx),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Makes every attached key blink and switches to the one that is touched.
+    fn select_authenticator(&mut self, cx: &mut Context<Self>) {
+        if self.state.selecting_authenticator {
+            return;
+        }
+        self.state.selecting_authenticator = true;
+        cx.notify();
+
+        cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::select_authenticator() })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.state.selecting_authenticator = false;
+                match result {
+                    Ok(selected) => {
+                        log::info!(
+                            "Active key is now {} ({}:{})",
+                            selected.product_name,
+                            selected.vid,
+                            selected.pid
+                        );
+                        // A different key means different attestation and metadata.
+                        this.state.attestation = None;
+                        this.state.metadata = None;
+                        this.refresh_device_status(None, cx);
+                    }
+                    Err(e) => {
+                        log::warn!("Authenticator selection failed: {}", e);
+                        this.state.error = Some(format!("No key was selected: {}", e));
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Looks the AAGUID up in the stored MDS BLOB, if one was imported before.
+    fn lookup_metadata(&mut self, aaguid: String, cx: &mut Context<Self>) {
+        if self.state.metadata_loading {
+            return;
+        }
+        self.state.metadata_loading = true;
+
+        cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::lookup_metadata(aaguid) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.state.metadata_loading = false;
+                match result {
+                    Ok(lookup) => this.state.metadata = lookup,
+                    Err(e) => {
+                        log::warn!("Metadata lookup failed: {}", e);
+                        this.state.metadata = None;
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn import_metadata_blob(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.state.metadata_loading {
+            return;
+        }
+        let Some(aaguid) = self.state.fido_info.as_ref().map(|f| f.aaguid.clone()) else {
+            return;
+        };
+        let path_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select MDS3 BLOB".into()),
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let path = match path_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+
+            let _ = this.update(cx, |this, cx| {
+                this.state.metadata_loading = true;
+                cx.notify();
+            });
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::import_metadata_blob(path, aaguid) })
+                .await;
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.state.metadata_loading = false;
+                match result {
+                    Ok(lookup) => {
+                        window.push_notification(
+                            if lookup.entry.is_some() {
+                                "Metadata BLOB verified and imported"
+                            } else {
+                                "Metadata BLOB imported, but it has no entry for this key"
+                            },
+                            cx,
+                        );
+                        this.state.metadata = Some(lookup);
+                    }
+                    Err(e) => window
+                        .push_notification(format!("Could not import metadata BLOB: {}", e), cx),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
 }
 
 impl Render for ApplicationRoot {
@@ -117,6 +294,16 @@ impl Render for ApplicationRoot {
         }
 
         let dialog_layer = Root::render_dialog_layer(window, cx);
+        let inspect_attestation_listener = cx.listener(|this, _, window, cx| {
+            this.inspect_attestation(window, cx);
+        });
+        let import_metadata_listener = cx.listener(|this, _, window, cx| {
+            this.import_metadata_blob(window, cx);
+        });
+        let toggle_advanced_listener = cx.listener(|this, _, _, cx| {
+            this.state.show_advanced_info = !this.state.show_advanced_info;
+            cx.notify();
+        });
 
         div().size_full().overflow_hidden().child(
             h_flex()
@@ -134,6 +321,10 @@ impl Render for ApplicationRoot {
                     .on_refresh(|this, window, cx| {
                         this.refresh_device_status(Some(window), cx);
                     })
+                    .on_select_key(|this, window, cx| {
+                        window.push_notification("Touch the key you want to use", cx);
+                        this.select_authenticator(cx);
+                    })
                     .render(cx),
                 )
                 .child(
@@ -170,6 +361,9 @@ impl Render for ApplicationRoot {
                                         &self.state,
                                         cx.theme(),
                                         window.bounds().size.width,
+                                        inspect_attestation_listener,
+                                        import_metadata_listener,
+                                        toggle_advanced_listener,
                                     )
                                     .into_any_element(),
                                     ActiveView::Passkeys => {
@@ -217,7 +411,63 @@ impl Render for ApplicationRoot {
                                         view.clone().into_any_element()
                                     }
                                     ActiveView::Security => {
-                                        SecurityView::build(cx).into_any_element()
+                                        let view = self.security_view.get_or_insert_with(|| {
+                                            let view = cx.new(|cx| {
+                                                SecurityView::new(
+                                                    window,
+                                                    cx,
+                                                    self.state.device_status.clone(),
+                                                )
+                                            });
+                                            cx.subscribe_in(
+                                                &view,
+                                                window,
+                                                |_, _, event: &SecurityEvent, window, cx| {
+                                                    match event {
+                                                        SecurityEvent::Notification(msg) => {
+                                                            window.push_notification(
+                                                                msg.to_string(),
+                                                                cx,
+                                                            );
+                                                        }
+                                                        SecurityEvent::CloseDialog => {
+                                                            window.close_dialog(cx);
+                                                        }
+                                                    }
+                                                },
+                                            )
+                                            .detach();
+                                            view
+                                        });
+                                        view.clone().into_any_element()
+                                    }
+                                    ActiveView::Diagnostics => {
+                                        let view = self.diagnostics_view.get_or_insert_with(|| {
+                                            let view = cx.new(|cx| {
+                                                DiagnosticsView::new(
+                                                    window,
+                                                    cx,
+                                                    self.state.device_status.clone(),
+                                                )
+                                            });
+                                            cx.subscribe_in(
+                                                &view,
+                                                window,
+                                                |_, _, event: &DiagnosticsEvent, window, cx| {
+                                                    match event {
+                                                        DiagnosticsEvent::Notification(msg) => {
+                                                            window.push_notification(
+                                                                msg.to_string(),
+                                                                cx,
+                                                            );
+                                                        }
+                                                    }
+                                                },
+                                            )
+                                            .detach();
+                                            view
+                                        });
+                                        view.clone().into_any_element()
                                     }
                                     ActiveView::Logs => {
                                         let view = self.logs_view.get_or_insert_with(|| {
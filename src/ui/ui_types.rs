@@ -1,6 +1,6 @@
 use gpui::SharedString;
 
-use crate::device::types::{FidoDeviceInfo, FullDeviceStatus};
+use crate::device::types::{AttestationReport, FidoDeviceInfo, FullDeviceStatus, MetadataLookup};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ActiveView {
@@ -8,6 +8,7 @@ pub enum ActiveView {
     Passkeys,
     Configuration,
     Security,
+    Diagnostics,
     Logs,
     About,
 }
@@ -17,6 +18,17 @@ pub struct GlobalDeviceState {
     pub device_status: Option<FullDeviceStatus>,
     pub fido_info: Option<FidoDeviceInfo>,
     pub error: Option<String>,
+    /// Result of the last attestation inspection, cleared when the device goes away.
+    pub attestation: Option<AttestationReport>,
+    pub attestation_loading: bool,
+    /// Metadata service entry for the connected AAGUID, from the imported MDS3 BLOB.
+    pub metadata: Option<MetadataLookup>,
+    pub metadata_loading: bool,
+    /// Whether the full GetInfo panel on Home is expanded.
+    pub show_advanced_info: bool,
+    /// Number of FIDO keys attached; above one the user picks a key by touch.
+    pub authenticator_count: usize,
+    pub selecting_authenticator: bool,
 }
 
 impl GlobalDeviceState {
@@ -25,6 +37,13 @@ impl GlobalDeviceState {
             device_status: None,
             fido_info: None,
             error: None,
+            attestation: None,
+            attestation_loading: false,
+            metadata: None,
+            metadata_loading: false,
+            show_advanced_info: false,
+            authenticator_count: 0,
+            selecting_authenticator: false,
         }
     }
 }
@@ -1,18 +1,31 @@
 use crate::device::io;
-use crate::device::types::{AppConfigInput, FullDeviceStatus};
+use crate::device::types::{
+    AppConfig, AppConfigInput, ConfigField, ConfigWriteReport, FullDeviceStatus,
+};
 use crate::ui::components::{card::Card, page_view::PageView};
 use crate::ui::ui_types::{LedDriverType, UsbIdentityPreset};
 use gpui::*;
 use gpui_component::button::{ButtonCustomVariant, ButtonVariants};
 use gpui_component::{
-    ActiveTheme, Disableable, Icon, Theme, WindowExt,
+    ActiveTheme, Disableable, Icon, Sizable, StyledExt, Theme, WindowExt,
     button::Button,
+    h_flex,
     input::{Input, InputState},
     select::{Select, SelectItem, SelectState},
-    slider::{Slider, SliderState},
+    slider::{Slider, SliderEvent, SliderState},
     switch::Switch,
     v_flex,
 };
+use std::collections::HashSet;
+
+/// The value to show for `field`, or nothing if the device did not report it.
+fn reported(config: &AppConfig, field: ConfigField, value: impl ToString) -> SharedString {
+    if config.is_known(field) {
+        value.to_string().into()
+    } else {
+        SharedString::default()
+    }
+}
 
 #[derive(Clone, PartialEq)]
 struct VendorSelectOption {
@@ -66,6 +79,10 @@ pub struct ConfigView {
     loading: bool,
     device_status: Option<FullDeviceStatus>,
     is_custom_vendor: bool,
+    /// Unreported fields the user has set, so they are written even if equal to the default.
+    touched: HashSet<ConfigField>,
+    write_report: Option<ConfigWriteReport>,
+    write_error: Option<String>,
     _task: Option<Task<()>>,
 }
 
@@ -106,10 +123,10 @@ impl ConfigView {
             .map(|c| c.product_name.clone().into())
             .unwrap_or_else(|| "My Key".into());
         let current_led_gpio: SharedString = config
-            .map(|c| c.led_gpio.to_string().into())
+            .map(|c| reported(c, ConfigField::LedGpio, c.led_gpio))
             .unwrap_or_else(|| "25".into());
         let current_touch_timeout: SharedString = config
-            .map(|c| c.touch_timeout.to_string().into())
+            .map(|c| reported(c, ConfigField::TouchTimeout, c.touch_timeout))
             .unwrap_or_else(|| "10".into());
         let current_brightness = config.map(|c| c.led_brightness as f32).unwrap_or(8.0);
 
@@ -135,19 +152,23 @@ impl ConfigView {
         let product_name_input =
             cx.new(|cx| InputState::new(window, cx).default_value(current_product_name.clone()));
 
-        let led_gpio_input =
-            cx.new(|cx| InputState::new(window, cx).default_value(current_led_gpio.clone()));
+        let led_gpio_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Not reported")
+                .default_value(current_led_gpio.clone())
+        });
 
         let current_driver_val = config.and_then(|c| c.led_driver).unwrap_or(0);
         let initial_driver_idx = LedDriverType::all()
             .iter()
             .position(|d| d.value() == current_driver_val)
             .unwrap_or(0);
+        let driver_known = config.is_none_or(|c| c.is_known(ConfigField::LedDriver));
 
         let led_driver_select = cx.new(|cx| {
             SelectState::new(
                 drivers,
-                Some(gpui_component::IndexPath::default().row(initial_driver_idx)),
+                driver_known.then(|| gpui_component::IndexPath::default().row(initial_driver_idx)),
                 window,
                 cx,
             )
@@ -183,8 +204,19 @@ impl ConfigView {
                 .default_value(current_brightness)
         });
 
-        let touch_timeout_input =
-            cx.new(|cx| InputState::new(window, cx).default_value(current_touch_timeout.clone()));
+        cx.subscribe(
+            &led_brightness_slider,
+            |this: &mut Self, _, _: &SliderEvent, _| {
+                this.touched.insert(ConfigField::LedBrightness);
+            },
+        )
+        .detach();
+
+        let touch_timeout_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Not reported")
+                .default_value(current_touch_timeout.clone())
+        });
 
         Self {
             vendor_select,
@@ -202,6 +234,9 @@ impl ConfigView {
             loading: false,
             device_status: device_status.clone(),
             is_custom_vendor,
+            touched: HashSet::new(),
+            write_report: None,
+            write_error: None,
             _task: None,
         }
     }
@@ -214,6 +249,8 @@ impl ConfigView {
         cx: &mut Context<Self>,
     ) {
         self.loading = true;
+        self.write_report = None;
+        self.write_error = None;
         cx.notify();
 
         let entity = cx.entity().downgrade();
@@ -238,8 +275,13 @@ impl ConfigView {
                 this.loading = false;
 
                 match result {
-                    Ok(msg) => {
-                        log::info!("Success: {}", msg);
+                    Ok(report) => {
+                        log::info!(
+                            "Config written, {} of {} fields failed",
+                            report.failed(),
+                            report.results.len()
+                        );
+                        this.write_report = Some(report);
 
                         if let Some(Ok(new_status)) = new_status_result {
                             log::info!(
@@ -254,12 +296,14 @@ impl ConfigView {
                             this.power_cycle = config.power_cycle_on_reset;
                             this.enable_secp256k1 = config.enable_secp256k1;
 
+                            this.touched.clear();
                             this.device_status = Some(new_status);
                             cx.notify();
                         }
                     }
                     Err(e) => {
                         log::error!("Error saving config: {}", e);
+                        this.write_error = Some(e.to_string());
                     }
                 }
 
@@ -364,9 +408,19 @@ impl ConfigView {
             changes.product_name = Some(product_name);
         }
 
+        // Fields the device did not report are only sent once the user has set them.
+        let changed = |field: ConfigField, differs: bool| {
+            if current_config.is_known(field) {
+                differs
+            } else {
+                self.touched.contains(&field)
+            }
+        };
+
+        // Unreported text fields start empty, so any parsed value was entered by the user.
         let led_gpio_str = self.led_gpio_input.read(cx).text().to_string();
         if let Ok(val) = led_gpio_str.parse::<u8>() {
-            if val != current_config.led_gpio {
+            if !current_config.is_known(ConfigField::LedGpio) || val != current_config.led_gpio {
                 changes.led_gpio = Some(val);
             }
         }
@@ -375,37 +429,67 @@ impl ConfigView {
         if let Some(idx) = driver_idx {
             if let Some(driver) = LedDriverType::all().get(idx.row) {
                 let val = driver.value();
-                if Some(val) != current_config.led_driver {
+                if !current_config.is_known(ConfigField::LedDriver)
+                    || Some(val) != current_config.led_driver
+                {
                     changes.led_driver = Some(val);
                 }
             }
         }
 
         let brightness = self.led_brightness_slider.read(cx).value().start() as u8;
-        if brightness != current_config.led_brightness {
+        if changed(
+            ConfigField::LedBrightness,
+            brightness != current_config.led_brightness,
+        ) {
             changes.led_brightness = Some(brightness);
         }
 
         let touch_timeout_str = self.touch_timeout_input.read(cx).text().to_string();
         if let Ok(val) = touch_timeout_str.parse::<u8>() {
-            if val != current_config.touch_timeout {
+            if !current_config.is_known(ConfigField::TouchTimeout)
+                || val != current_config.touch_timeout
+            {
                 changes.touch_timeout = Some(val);
             }
         }
 
-        if (self.led_dimmable != current_config.led_dimmable)
-            || (self.led_steady != current_config.led_steady)
-            || (self.power_cycle != current_config.power_cycle_on_reset)
-        {
+        if changed(
+            ConfigField::LedDimmable,
+            self.led_dimmable != current_config.led_dimmable,
+        ) || changed(
+            ConfigField::LedSteady,
+            self.led_steady != current_config.led_steady,
+        ) || changed(
+            ConfigField::PowerCycleOnReset,
+            self.power_cycle != current_config.power_cycle_on_reset,
+        ) {
             changes.led_dimmable = Some(self.led_dimmable);
             changes.led_steady = Some(self.led_steady);
             changes.power_cycle_on_reset = Some(self.power_cycle);
         }
 
-        if self.enable_secp256k1 != current_config.enable_secp256k1 {
+        if changed(
+            ConfigField::EnableSecp256k1,
+            self.enable_secp256k1 != current_config.enable_secp256k1,
+        ) {
             changes.enable_secp256k1 = Some(self.enable_secp256k1);
         }
 
+        // Never send what the connection cannot write; the inputs are disabled for these.
+        if self.read_only(ConfigField::ProductName) {
+            changes.product_name = None;
+        }
+        if self.read_only(ConfigField::TouchTimeout) {
+            changes.touch_timeout = None;
+        }
+        if self.read_only(ConfigField::LedDriver) {
+            changes.led_driver = None;
+        }
+        if self.read_only(ConfigField::EnableSecp256k1) {
+            changes.enable_secp256k1 = None;
+        }
+
         let has_changes = changes.vid.is_some()
             || changes.pid.is_some()
             || changes.product_name.is_some()
@@ -442,6 +526,7 @@ impl ConfigView {
             return;
         }
         self.device_status = status.clone();
+        self.touched.clear();
         let config = status.as_ref().map(|s| &s.config);
 
         let vid = config
@@ -463,13 +548,13 @@ impl ConfigView {
             .update(cx, |input, cx| input.set_value(product, window, cx));
 
         let gpio = config
-            .map(|c| c.led_gpio.to_string())
+            .map(|c| reported(c, ConfigField::LedGpio, c.led_gpio))
             .unwrap_or_else(|| "25".into());
         self.led_gpio_input
             .update(cx, |input, cx| input.set_value(gpio, window, cx));
 
         let timeout = config
-            .map(|c| c.touch_timeout.to_string())
+            .map(|c| reported(c, ConfigField::TouchTimeout, c.touch_timeout))
             .unwrap_or_else(|| "10".into());
         self.touch_timeout_input
             .update(cx, |input, cx| input.set_value(timeout, window, cx));
@@ -486,6 +571,121 @@ impl ConfigView {
         cx.notify();
     }
 
+    fn is_known(&self, field: ConfigField) -> bool {
+        self.device_status
+            .as_ref()
+            .is_none_or(|s| s.config.is_known(field))
+    }
+
+    /// Whether `field` cannot be written with the current connection. The FIDO interface has no
+    /// command for some fields.
+    fn read_only(&self, field: ConfigField) -> bool {
+        self.device_status.as_ref().is_some_and(|s| {
+            s.method == crate::device::types::DeviceMethod::Fido && !io::fido_config_writable(field)
+        })
+    }
+
+    /// A field label, marked when the device did not report the field's value or when it cannot
+    /// be changed over the current connection.
+    fn field_label(
+        &self,
+        label: &'static str,
+        field: ConfigField,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .items_center()
+            .child(label)
+            .children((!self.is_known(field)).then(|| {
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child("Unknown, not reported by the device")
+            }))
+            .children(self.read_only(field).then(|| {
+                div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child("Read-only over FIDO, change it in rescue mode")
+            }))
+    }
+
+    fn render_write_results(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if self.write_report.is_none() && self.write_error.is_none() {
+            return None;
+        }
+        let dismiss_listener = cx.listener(|this, _, _, cx| {
+            this.write_report = None;
+            this.write_error = None;
+            cx.notify();
+        });
+
+        let theme = cx.theme();
+        let results = self
+            .write_report
+            .as_ref()
+            .map(|r| r.results.as_slice())
+            .unwrap_or_default();
+        let title = match (&self.write_error, &self.write_report) {
+            (Some(e), _) => format!("Failed to apply changes: {}", e),
+            (None, Some(report)) => format!(
+                "{} of {} settings applied",
+                results.len() - report.failed(),
+                results.len()
+            ),
+            (None, None) => String::new(),
+        };
+        let replug = results
+            .iter()
+            .any(|r| r.field == ConfigField::VidPid && r.error.is_none());
+
+        Some(
+            v_flex()
+                .gap_2()
+                .p_3()
+                .rounded_lg()
+                .border_1()
+                .border_color(theme.border)
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .items_center()
+                        .child(div().text_sm().font_semibold().child(title))
+                        .child(
+                            Button::new("dismiss-write-results")
+                                .ghost()
+                                .small()
+                                .label("Dismiss")
+                                .on_click(dismiss_listener),
+                        ),
+                )
+                .children(results.iter().map(|r| {
+                    let (icon, color, status) = match &r.error {
+                        None => (
+                            "icons/circle-check.svg",
+                            gpui::green(),
+                            "Applied".to_string(),
+                        ),
+                        Some(e) => ("icons/circle-x.svg", gpui::red(), e.clone()),
+                    };
+                    h_flex()
+                        .gap_2()
+                        .items_center()
+                        .text_sm()
+                        .child(Icon::default().path(icon).size_4().text_color(color))
+                        .child(r.field.label())
+                        .child(div().text_color(theme.muted_foreground).child(status))
+                }))
+                .children(replug.then(|| {
+                    div()
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child("Unplug and re-plug the device to apply VID/PID changes.")
+                })),
+        )
+    }
+
     fn render_identity_card(&self, theme: &Theme) -> impl IntoElement {
         let content = v_flex()
             .gap_4()
@@ -521,8 +721,12 @@ impl ConfigView {
             .child(
                 v_flex()
                     .gap_2()
-                    .child("Product Name")
-                    .child(Input::new(&self.product_name_input).bg(rgb(0x222225))),
+                    .child(self.field_label("Product Name", ConfigField::ProductName, theme))
+                    .child(
+                        Input::new(&self.product_name_input)
+                            .bg(rgb(0x222225))
+                            .disabled(self.read_only(ConfigField::ProductName)),
+                    ),
             );
 
         Card::new()
@@ -535,11 +739,13 @@ impl ConfigView {
     fn render_led_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
         let dim_listener = cx.listener(|this, checked, _, cx| {
             this.led_dimmable = *checked;
+            this.touched.insert(ConfigField::LedDimmable);
             cx.notify();
         });
 
         let steady_listener = cx.listener(|this, checked, _, cx| {
             this.led_steady = *checked;
+            this.touched.insert(ConfigField::LedSteady);
             cx.notify();
         });
 
@@ -552,42 +758,56 @@ impl ConfigView {
             .child(
                 v_flex()
                     .gap_2()
-                    .child("LED GPIO Pin")
+                    .child(self.field_label("LED GPIO Pin", ConfigField::LedGpio, theme))
                     .child(Input::new(&self.led_gpio_input).bg(rgb(0x222225))),
             )
             .child(
-                v_flex().gap_2().child("LED Driver").child(
-                    Select::new(&self.led_driver_select)
-                        .w_full()
-                        .bg(rgb(0x222225)),
-                ),
+                v_flex()
+                    .gap_2()
+                    .child(self.field_label("LED Driver", ConfigField::LedDriver, theme))
+                    .child(
+                        Select::new(&self.led_driver_select)
+                            .w_full()
+                            .bg(rgb(0x222225))
+                            .disabled(self.read_only(ConfigField::LedDriver)),
+                    ),
             )
             .child(div().h_px().bg(theme.border))
             .child(
-                v_flex().gap_2().child("Brightness (0-15)").child(
-                    gpui_component::h_flex()
-                        .items_center()
-                        .gap_4()
-                        .child(Slider::new(&self.led_brightness_slider).flex_1())
-                        .child(
-                            div()
-                                .text_xs()
-                                .text_color(theme.muted_foreground)
-                                .child(format!("Level {}", brightness)),
-                        ),
-                ),
+                v_flex()
+                    .gap_2()
+                    .child(self.field_label("Brightness (0-15)", ConfigField::LedBrightness, theme))
+                    .child(
+                        gpui_component::h_flex()
+                            .items_center()
+                            .gap_4()
+                            .child(Slider::new(&self.led_brightness_slider).flex_1())
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(format!("Level {}", brightness)),
+                            ),
+                    ),
             )
             .child(
                 gpui_component::h_flex()
                     .items_center()
                     .justify_between()
                     .child(
-                        v_flex().gap_0p5().child("LED Dimmable").child(
-                            div()
-                                .text_sm()
-                                .text_color(theme.muted_foreground)
-                                .child("Allow brightness adjustment"),
-                        ),
+                        v_flex()
+                            .gap_0p5()
+                            .child(self.field_label(
+                                "LED Dimmable",
+                                ConfigField::LedDimmable,
+                                theme,
+                            ))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .child("Allow brightness adjustment"),
+                            ),
                     )
                     .child(
                         Switch::new("led-dimmable")
@@ -600,12 +820,19 @@ impl ConfigView {
                     .items_center()
                     .justify_between()
                     .child(
-                        v_flex().gap_0p5().child("LED Steady Mode").child(
-                            div()
-                                .text_sm()
-                                .text_color(theme.muted_foreground)
-                                .child("Keep LED on constantly"),
-                        ),
+                        v_flex()
+                            .gap_0p5()
+                            .child(self.field_label(
+                                "LED Steady Mode",
+                                ConfigField::LedSteady,
+                                theme,
+                            ))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .child("Keep LED on constantly"),
+                            ),
                     )
                     .child(
                         Switch::new("led-steady")
@@ -621,12 +848,20 @@ impl ConfigView {
             .child(content)
     }
 
-    fn render_touch_card(&self, _theme: &Theme) -> impl IntoElement {
+    fn render_touch_card(&self, theme: &Theme) -> impl IntoElement {
         let content = v_flex().gap_4().child(
             v_flex()
                 .gap_2()
-                .child("Touch Timeout (seconds)")
-                .child(Input::new(&self.touch_timeout_input).bg(rgb(0x222225))),
+                .child(self.field_label(
+                    "Touch Timeout (seconds)",
+                    ConfigField::TouchTimeout,
+                    theme,
+                ))
+                .child(
+                    Input::new(&self.touch_timeout_input)
+                        .bg(rgb(0x222225))
+                        .disabled(self.read_only(ConfigField::TouchTimeout)),
+                ),
         );
 
         Card::new()
@@ -639,11 +874,13 @@ impl ConfigView {
     fn render_options_card(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
         let power_cycle_listener = cx.listener(|this, checked, _, cx| {
             this.power_cycle = *checked;
+            this.touched.insert(ConfigField::PowerCycleOnReset);
             cx.notify();
         });
 
         let secp_listener = cx.listener(|this, checked, _, cx| {
             this.enable_secp256k1 = *checked;
+            this.touched.insert(ConfigField::EnableSecp256k1);
             cx.notify();
         });
 
@@ -656,12 +893,19 @@ impl ConfigView {
                     .items_center()
                     .justify_between()
                     .child(
-                        v_flex().gap_0p5().child("Power Cycle on Reset").child(
-                            div()
-                                .text_sm()
-                                .text_color(theme.muted_foreground)
-                                .child("Restart device on reset"),
-                        ),
+                        v_flex()
+                            .gap_0p5()
+                            .child(self.field_label(
+                                "Power Cycle on Reset",
+                                ConfigField::PowerCycleOnReset,
+                                theme,
+                            ))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .child("Restart device on reset"),
+                            ),
                     )
                     .child(
                         Switch::new("power-cycle")
@@ -674,16 +918,24 @@ impl ConfigView {
                     .items_center()
                     .justify_between()
                     .child(
-                        v_flex().gap_0p5().child("Enable Secp256k1").child(
-                            div()
-                                .text_sm()
-                                .text_color(theme.muted_foreground)
-                                .child("Does not work on Android!"),
-                        ),
+                        v_flex()
+                            .gap_0p5()
+                            .child(self.field_label(
+                                "Enable Secp256k1",
+                                ConfigField::EnableSecp256k1,
+                                theme,
+                            ))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .child("Does not work on Android!"),
+                            ),
                     )
                     .child(
                         Switch::new("enable-secp")
                             .checked(self.enable_secp256k1)
+                            .disabled(self.read_only(ConfigField::EnableSecp256k1))
                             .on_click(secp_listener),
                     ),
             );
@@ -718,8 +970,21 @@ impl Render for ConfigView {
         }
 
         let led_card = self.render_led_card(cx).into_any_element();
+        let write_results = self
+            .render_write_results(cx)
+            .map(IntoElement::into_any_element);
         let options_card = self.render_options_card(cx).into_any_element();
 
+        let unsupported = self.device_status.as_ref().and_then(|status| {
+            let feature = if status.method == crate::device::types::DeviceMethod::Fido {
+                crate::device::types::Feature::FidoPhysicalConfig
+            } else {
+                crate::device::types::Feature::RescueConfig
+            };
+            io::feature_support(Some(status), feature).err()
+        });
+        let apply_blocked = unsupported.is_some();
+
         let theme = cx.theme();
 
         let identity_card = self.render_identity_card(theme).into_any_element();
@@ -743,23 +1008,34 @@ impl Render for ConfigView {
                         .child(touch_card)
                         .child(options_card),
                 )
+                .children(write_results)
                 .child(
-                    gpui_component::h_flex().justify_end().pt_4().child(
-                        Button::new("apply-changes")
-                            .icon(Icon::default().path("icons/save.svg"))
-                            .child("Apply Changes")
-                            .disabled(self.loading)
-                            .custom(
-                                ButtonCustomVariant::new(cx)
-                                    .color(rgb(0xe3e3e6).into())
-                                    .hover(rgb(0xcfcfd1).into())
-                                    .active(rgb(0xe3e3e6).into())
-                                    .foreground(rgb(0x4b4b4e).into()),
-                            )
-                            .on_click(cx.listener(|this, _, window, cx| {
-                                this.apply_changes(window, cx);
-                            })),
-                    ),
+                    gpui_component::h_flex()
+                        .justify_end()
+                        .items_center()
+                        .gap_4()
+                        .pt_4()
+                        .children(
+                            unsupported.map(|reason| {
+                                div().text_sm().text_color(rgb(0xfe9a00)).child(reason)
+                            }),
+                        )
+                        .child(
+                            Button::new("apply-changes")
+                                .icon(Icon::default().path("icons/save.svg"))
+                                .child("Apply Changes")
+                                .disabled(self.loading || apply_blocked)
+                                .custom(
+                                    ButtonCustomVariant::new(cx)
+                                        .color(rgb(0xe3e3e6).into())
+                                        .hover(rgb(0xcfcfd1).into())
+                                        .active(rgb(0xe3e3e6).into())
+                                        .foreground(rgb(0x4b4b4e).into()),
+                                )
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.apply_changes(window, cx);
+                                })),
+                        ),
                 ),
             &theme,
         )
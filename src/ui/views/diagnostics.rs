@@ -0,0 +1,590 @@
+use crate::device::fido::constants::CoseAlgorithm;
+use crate::device::io;
+use crate::device::types::{
+    AlgorithmSupport, CheckStatus, DiagnosticCheck, Feature, FullDeviceStatus, HmacSecretReport,
+    SelfTestReport,
+};
+use crate::ui::components::{button::PFIconButton, card::Card, page_view::PageView};
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, StyledExt, Theme, WindowExt,
+    badge::Badge,
+    button::{Button, ButtonVariants},
+    h_flex,
+    input::{Input, InputState},
+    v_flex,
+};
+
+pub struct DiagnosticsView {
+    device_status: Option<FullDeviceStatus>,
+    loading: bool,
+    self_test: Option<SelfTestReport>,
+    algorithms: Option<Vec<AlgorithmSupport>>,
+    hmac_secret: Option<HmacSecretReport>,
+    _task: Option<Task<()>>,
+}
+
+pub enum DiagnosticsEvent {
+    Notification(String),
+}
+
+impl EventEmitter<DiagnosticsEvent> for DiagnosticsView {}
+
+impl DiagnosticsView {
+    pub fn new(
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+        device_status: Option<FullDeviceStatus>,
+    ) -> Self {
+        Self {
+            device_status,
+            loading: false,
+            self_test: None,
+            algorithms: None,
+            hmac_secret: None,
+            _task: None,
+        }
+    }
+
+    pub fn update_device_status(
+        &mut self,
+        status: Option<FullDeviceStatus>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.device_status == status {
+            return;
+        }
+        self.device_status = status;
+        cx.notify();
+    }
+
+    fn open_self_test_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let pin_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("FIDO PIN (optional)")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let input = pin_input.clone();
+
+            dialog
+                .title("Run Self-Test")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(
+                            "A test passkey is registered for a dummy site and used to sign in \
+                             once. Touch your key each time it blinks.",
+                        )
+                        .child(
+                            "With a PIN the test passkey is stored on the key and deleted \
+                             afterwards. Without one nothing is stored.",
+                        )
+                        .child(Input::new(&pin_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let input = input.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("run").primary().label("Start").on_click(
+                            move |_, window, cx| {
+                                let pin = input.read(cx).text().to_string();
+                                let pin = if pin.is_empty() { None } else { Some(pin) };
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    this.run_self_test(pin, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn run_self_test(&mut self, pin: Option<String>, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        self.self_test = None;
+        cx.emit(DiagnosticsEvent::Notification(
+            "Touch your key when it blinks".to_string(),
+        ));
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::run_self_test(pin) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(report) => {
+                        cx.emit(DiagnosticsEvent::Notification(if report.passed() {
+                            "Self-test passed".to_string()
+                        } else {
+                            "Self-test failed".to_string()
+                        }));
+                        this.self_test = Some(report);
+                    }
+                    Err(e) => {
+                        cx.emit(DiagnosticsEvent::Notification(format!(
+                            "Self-test could not run: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn run_algorithm_probe(&mut self, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.emit(DiagnosticsEvent::Notification(
+            "Touch your key each time it blinks".to_string(),
+        ));
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::probe_algorithms() })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(algorithms) => {
+                        let supported = algorithms
+                            .iter()
+                            .filter(|a| a.status == CheckStatus::Passed)
+                            .count();
+                        cx.emit(DiagnosticsEvent::Notification(format!(
+                            "{} of {} algorithms supported",
+                            supported,
+                            algorithms.len()
+                        )));
+                        this.algorithms = Some(algorithms);
+                    }
+                    Err(e) => {
+                        cx.emit(DiagnosticsEvent::Notification(format!(
+                            "Algorithm probe failed: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn open_hmac_secret_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let salt_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("Salt (64 hex characters) or PRF input")
+        });
+        let pin_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("FIDO PIN (optional)")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let salt = salt_input.clone();
+            let pin = pin_input.clone();
+
+            dialog
+                .title("Test hmac-secret")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(
+                            "A test credential with hmac-secret is registered, then the secret \
+                             for your salt is requested twice. Touch your key each time it \
+                             blinks.",
+                        )
+                        .child(
+                            "Use the PIN if the key will be enrolled with user verification: \
+                             the secret differs with and without it.",
+                        )
+                        .child(Input::new(&salt_input))
+                        .child(Input::new(&pin_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let salt = salt.clone();
+                    let pin = pin.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("run").primary().label("Start").on_click(
+                            move |_, window, cx| {
+                                let salt = salt.read(cx).text().to_string();
+                                let pin = pin.read(cx).text().to_string();
+                                let pin = if pin.is_empty() { None } else { Some(pin) };
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    this.run_hmac_secret_test(salt, pin, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn run_hmac_secret_test(&mut self, salt: String, pin: Option<String>, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        self.hmac_secret = None;
+        cx.emit(DiagnosticsEvent::Notification(
+            "Touch your key each time it blinks".to_string(),
+        ));
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::test_hmac_secret(salt, pin) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(report) => {
+                        cx.emit(DiagnosticsEvent::Notification(if report.passed() {
+                            "hmac-secret test passed".to_string()
+                        } else {
+                            "hmac-secret test failed".to_string()
+                        }));
+                        this.hmac_secret = Some(report);
+                    }
+                    Err(e) => {
+                        cx.emit(DiagnosticsEvent::Notification(format!(
+                            "hmac-secret test could not run: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn render_checks(checks: &[DiagnosticCheck], theme: &Theme) -> impl IntoElement {
+        v_flex().gap_2().children(checks.iter().map(|check| {
+            let (icon, color) = match check.status {
+                CheckStatus::Passed => ("icons/circle-check.svg", gpui::green()),
+                CheckStatus::Failed => ("icons/circle-x.svg", gpui::red()),
+                CheckStatus::Skipped => ("icons/minus.svg", theme.muted_foreground),
+            };
+            h_flex()
+                .gap_3()
+                .items_start()
+                .p_3()
+                .rounded_md()
+                .bg(theme.secondary)
+                .child(Icon::default().path(icon).size_4().text_color(color))
+                .child(
+                    v_flex()
+                        .gap_0p5()
+                        .child(div().text_sm().font_semibold().child(check.name.clone()))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(theme.muted_foreground)
+                                .child(check.detail.clone()),
+                        ),
+                )
+        }))
+    }
+
+    /// Why `feature` cannot be used on the connected key, if it cannot.
+    fn unsupported(&self, feature: Feature) -> Option<String> {
+        io::feature_support(self.device_status.as_ref(), feature).err()
+    }
+
+    fn unsupported_note(reason: String) -> impl IntoElement {
+        div().text_sm().text_color(rgb(0xfe9a00)).child(reason)
+    }
+
+    fn render_self_test_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let run_listener = cx.listener(|this, _, window, cx| {
+            this.open_self_test_dialog(window, cx);
+        });
+
+        let unsupported = self.unsupported(Feature::Registration);
+        let blocked = unsupported.is_some();
+        let theme = cx.theme();
+        let connected = self.device_status.is_some();
+
+        Card::new()
+            .title("Authenticator Self-Test")
+            .icon(Icon::default().path("icons/circle-check.svg"))
+            .description("Register and sign in with a test passkey, then verify every signature")
+            .header_right(match &self.self_test {
+                Some(report) if report.passed() => {
+                    Badge::new().child("Passed").color(gpui::green())
+                }
+                Some(_) => Badge::new().child("Failed").color(gpui::red()),
+                None => Badge::new().child("Not run").color(theme.secondary),
+            })
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(div().text_sm().text_color(theme.muted_foreground).child(
+                        "Checks the attestation and assertion signatures, the RP ID \
+                         hash, the authenticator flags and the signature counter.",
+                    ))
+                    .children(unsupported.map(Self::unsupported_note))
+                    .children(
+                        self.self_test
+                            .as_ref()
+                            .map(|report| Self::render_checks(&report.checks, theme)),
+                    )
+                    .child(
+                        h_flex().child(
+                            PFIconButton::new(
+                                Icon::default().path("icons/play.svg"),
+                                "Run Self-Test",
+                            )
+                            .disabled(!connected || blocked || self.loading)
+                            .loading(self.loading)
+                            .on_click(run_listener),
+                        ),
+                    ),
+            )
+    }
+
+    fn render_algorithm_table(algorithms: &[AlgorithmSupport], theme: &Theme) -> impl IntoElement {
+        let header = h_flex()
+            .px_3()
+            .py_2()
+            .text_xs()
+            .font_semibold()
+            .text_color(theme.muted_foreground)
+            .child(div().w(px(140.)).child("Algorithm"))
+            .child(div().w(px(60.)).child("COSE ID"))
+            .child(div().w(px(100.)).child("Advertised"))
+            .child(div().flex_1().child("Result"));
+
+        v_flex()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_md()
+            .child(header)
+            .children(algorithms.iter().map(|alg| {
+                let (icon, color) = match alg.status {
+                    CheckStatus::Passed => ("icons/circle-check.svg", gpui::green()),
+                    CheckStatus::Failed => ("icons/circle-x.svg", gpui::red()),
+                    CheckStatus::Skipped => ("icons/minus.svg", theme.muted_foreground),
+                };
+                h_flex()
+                    .px_3()
+                    .py_2()
+                    .border_t_1()
+                    .border_color(theme.border)
+                    .items_center()
+                    .text_sm()
+                    .child(
+                        div()
+                            .w(px(140.))
+                            .font_semibold()
+                            .child(alg.algorithm.clone()),
+                    )
+                    .child(
+                        div()
+                            .w(px(60.))
+                            .font_family("Mono")
+                            .child(alg.algorithm_id.to_string()),
+                    )
+                    .child(
+                        div()
+                            .w(px(100.))
+                            .text_color(theme.muted_foreground)
+                            .child(if alg.advertised { "Yes" } else { "No" }),
+                    )
+                    .child(
+                        h_flex()
+                            .flex_1()
+                            .gap_2()
+                            .items_center()
+                            .child(Icon::default().path(icon).size_4().text_color(color))
+                            .child(alg.detail.clone()),
+                    )
+            }))
+    }
+
+    fn render_algorithms_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let probe_listener = cx.listener(|this, _, _, cx| {
+            this.run_algorithm_probe(cx);
+        });
+
+        let unsupported = self.unsupported(Feature::Registration);
+        let blocked = unsupported.is_some();
+        let theme = cx.theme();
+        let connected = self.device_status.is_some();
+
+        let secp256k1_rejected = self.algorithms.as_ref().is_some_and(|algs| {
+            algs.iter().any(|a| {
+                a.algorithm_id == CoseAlgorithm::ES256K as i32 && a.status == CheckStatus::Failed
+            })
+        });
+
+        Card::new()
+            .title("Algorithm Support")
+            .icon(Icon::default().path("icons/key.svg"))
+            .description("Register a throwaway credential with each signature algorithm")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(div().text_sm().text_color(theme.muted_foreground).child(
+                        "Nothing is stored on the key. Rejected algorithms fail without a \
+                         touch; accepted ones need one each.",
+                    ))
+                    .children(unsupported.map(Self::unsupported_note))
+                    .children(
+                        self.algorithms
+                            .as_ref()
+                            .map(|algs| Self::render_algorithm_table(algs, theme)),
+                    )
+                    .children(secp256k1_rejected.then(|| {
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(
+                                Icon::default()
+                                    .path("icons/info.svg")
+                                    .size_4()
+                                    .text_color(theme.muted_foreground),
+                            )
+                            .child(
+                                "ES256K is rejected. Enable secp256k1 in Configuration and \
+                                 replug the key if you need it.",
+                            )
+                    }))
+                    .child(
+                        h_flex().child(
+                            PFIconButton::new(Icon::default().path("icons/play.svg"), "Run Probe")
+                                .disabled(!connected || blocked || self.loading)
+                                .loading(self.loading)
+                                .on_click(probe_listener),
+                        ),
+                    ),
+            )
+    }
+
+    fn render_hmac_secret_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let run_listener = cx.listener(|this, _, window, cx| {
+            this.open_hmac_secret_dialog(window, cx);
+        });
+
+        let unsupported = self.unsupported(Feature::HmacSecret);
+        let blocked = unsupported.is_some();
+        let theme = cx.theme();
+        let connected = self.device_status.is_some();
+
+        Card::new()
+            .title("hmac-secret / PRF")
+            .icon(Icon::default().path("icons/key-round.svg"))
+            .description("Check that the key derives the same secret for the same salt")
+            .header_right(match &self.hmac_secret {
+                Some(report) if report.passed() => {
+                    Badge::new().child("Passed").color(gpui::green())
+                }
+                Some(_) => Badge::new().child("Failed").color(gpui::red()),
+                None => Badge::new().child("Not run").color(theme.secondary),
+            })
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(div().text_sm().text_color(theme.muted_foreground).child(
+                        "Disk unlock tools such as systemd-cryptenroll and age-plugin-fido2 \
+                         depend on this. Nothing is stored on the key.",
+                    ))
+                    .children(unsupported.map(Self::unsupported_note))
+                    .children(self.hmac_secret.as_ref().map(|report| {
+                        v_flex()
+                            .gap_4()
+                            .child(Self::render_checks(&report.checks, theme))
+                            .children((!report.output.is_empty()).then(|| {
+                                v_flex()
+                                    .gap_1()
+                                    .child(
+                                        div().text_xs().text_color(theme.muted_foreground).child(
+                                            format!(
+                                                "Output ({}, {})",
+                                                report.salt_source,
+                                                if report.user_verified {
+                                                    "user verified"
+                                                } else {
+                                                    "without user verification"
+                                                }
+                                            ),
+                                        ),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .font_family("Mono")
+                                            .child(report.output.clone()),
+                                    )
+                            }))
+                    }))
+                    .child(
+                        h_flex().child(
+                            PFIconButton::new(Icon::default().path("icons/play.svg"), "Run Test")
+                                .disabled(!connected || blocked || self.loading)
+                                .loading(self.loading)
+                                .on_click(run_listener),
+                        ),
+                    ),
+            )
+    }
+}
+
+impl Render for DiagnosticsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = v_flex()
+            .gap_6()
+            .w_full()
+            .child(self.render_self_test_card(cx))
+            .child(self.render_algorithms_card(cx))
+            .child(self.render_hmac_secret_card(cx));
+
+        PageView::build(
+            "Diagnostics",
+            "Check that the key signs correctly and see what it supports.",
+            content,
+            cx.theme(),
+        )
+    }
+}
@@ -1,10 +1,17 @@
-use crate::device::types::DeviceMethod;
-use crate::ui::components::{card::Card, page_view::PageView};
+use crate::device::fido::constants::CoseAlgorithm;
+use crate::device::types::{
+    AttestationReport, AttestationTrust, AuthenticatorMetadata, DeviceMethod, FidoDeviceInfo,
+    FieldSource, FlashField, MetadataIcon, StatusProvenance,
+};
+use crate::ui::components::{button::PFIconButton, card::Card, page_view::PageView};
 use crate::ui::ui_types::GlobalDeviceState;
 use gpui::*;
 use gpui_component::StyledExt;
 use gpui_component::{Icon, IconName, Theme, badge::Badge, h_flex, progress::Progress, v_flex};
 
+/// Storage use at which the Home view warns that the key is running out of space.
+const FLASH_WARNING_PERCENT: f32 = 85.0;
+
 pub struct HomeView;
 
 impl HomeView {
@@ -12,6 +19,9 @@ impl HomeView {
         state: &GlobalDeviceState,
         theme: &Theme,
         window_width: Pixels,
+        on_inspect_attestation: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+        on_import_metadata: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+        on_toggle_advanced: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
     ) -> impl IntoElement {
         let connected = state.device_status.is_some();
         let is_wide = window_width > px(1100.0);
@@ -38,7 +48,7 @@ impl HomeView {
                     .into_any_element()
             } else {
                 // Card Grid
-                div()
+                let grid = div()
                     .grid()
                     .grid_cols(columns)
                     .gap_6()
@@ -46,6 +56,24 @@ impl HomeView {
                     .child(Self::render_fido_info(state, theme))
                     .child(Self::render_led_config(state, theme))
                     .child(Self::render_security_status(state, theme))
+                    .child(Self::render_attestation(
+                        state,
+                        theme,
+                        on_inspect_attestation,
+                    ))
+                    .child(Self::render_metadata(state, theme, on_import_metadata));
+
+                v_flex()
+                    .gap_6()
+                    .child(grid)
+                    .children(state.fido_info.as_ref().map(|fido| {
+                        Self::render_advanced_info(
+                            fido,
+                            state.show_advanced_info,
+                            theme,
+                            on_toggle_advanced,
+                        )
+                    }))
                     .into_any_element()
             },
             theme,
@@ -81,16 +109,61 @@ impl HomeView {
             )
     }
 
+    /// A value that could not be read from the device, with a badge saying so.
+    fn unverified(value: impl Into<SharedString>) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .items_center()
+            .child(value.into())
+            .child(Badge::new().child("Unverified").color(rgb(0xfe9a00)))
+    }
+
+    fn sources_label(provenance: &StatusProvenance) -> &'static str {
+        let rescue = provenance.serial == FieldSource::Rescue;
+        let fido = provenance.fido == FieldSource::Fido;
+        match (rescue, fido) {
+            (true, true) => "Rescue + FIDO",
+            (true, false) => "Rescue only",
+            (false, true) => "FIDO only",
+            (false, false) => "Unknown",
+        }
+    }
+
     fn render_device_info(state: &GlobalDeviceState, theme: &Theme) -> impl IntoElement {
         let status = state.device_status.as_ref().unwrap();
         let info = &status.info;
         let config = &status.config;
 
-        let flash_percent = (info.flash_used as f32 / info.flash_total as f32) * 100.0;
+        let usage_known = info.is_known(FlashField::Used) && info.is_known(FlashField::Total);
+        let flash_percent = if usage_known && info.flash_total > 0 {
+            (info.flash_used as f32 / info.flash_total as f32) * 100.0
+        } else {
+            0.0
+        };
+        // Without both figures there is nothing to warn about, only an unknown.
+        let flash_low = usage_known && flash_percent >= FLASH_WARNING_PERCENT;
+        let figure = |field: FlashField, text: String| {
+            if info.is_known(field) {
+                text
+            } else {
+                "Not reported".to_string()
+            }
+        };
+
+        let serial = if status.provenance.serial == FieldSource::Unverified {
+            Self::unverified("Unavailable over FIDO").into_any_element()
+        } else {
+            info.serial.clone().into_any_element()
+        };
 
         Card::new()
             .title("Device Information")
             .icon(Icon::default().path("icons/cpu.svg"))
+            .header_right(
+                Badge::new()
+                    .child(Self::sources_label(&status.provenance))
+                    .color(theme.secondary),
+            )
             .child(
                 v_flex()
                     .gap_6()
@@ -99,12 +172,7 @@ impl HomeView {
                             .grid()
                             .grid_cols(2)
                             .gap_4()
-                            .child(Self::render_kv(
-                                "Serial Number",
-                                info.serial.clone(),
-                                theme,
-                                true,
-                            ))
+                            .child(Self::render_kv("Serial Number", serial, theme, true))
                             .child(Self::render_kv(
                                 "Firmware Version",
                                 format!("v{}", info.firmware_version),
@@ -137,12 +205,64 @@ impl HomeView {
                                             .text_color(theme.muted_foreground)
                                             .child("Flash Memory"),
                                     )
-                                    .child(div().text_color(theme.foreground).child(format!(
-                                        "{:.0} / {:.0} KB",
-                                        info.flash_used, info.flash_total
-                                    ))),
+                                    .child(div().text_color(theme.foreground).child(
+                                        if usage_known {
+                                            format!(
+                                                "{:.0} / {:.0} KB",
+                                                info.flash_used, info.flash_total
+                                            )
+                                        } else {
+                                            "Not reported".to_string()
+                                        },
+                                    )),
                             )
-                            .child(Progress::new().value(flash_percent)),
+                            .child(Progress::new().value(flash_percent))
+                            .children(flash_low.then(|| {
+                                h_flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .text_xs()
+                                    .text_color(rgb(0xfe9a00))
+                                    .child(
+                                        Icon::default().path("icons/triangle-alert.svg").size_3p5(),
+                                    )
+                                    .child(format!(
+                                        "Storage is {:.0}% full. Delete unused passkeys or large \
+                                         blobs before the key runs out of space.",
+                                        flash_percent
+                                    ))
+                            })),
+                    )
+                    .child(
+                        div()
+                            .grid()
+                            .grid_cols(3)
+                            .gap_4()
+                            .child(Self::render_kv(
+                                "Free Space",
+                                figure(FlashField::Free, format!("{} KB", info.flash_free)),
+                                theme,
+                                true,
+                            ))
+                            .child(Self::render_kv(
+                                "Files",
+                                figure(FlashField::FileCount, info.file_count.to_string()),
+                                theme,
+                                true,
+                            ))
+                            .child(Self::render_kv(
+                                "Flash Chip",
+                                figure(
+                                    FlashField::ChipSize,
+                                    if info.flash_chip_size >= 1024 {
+                                        format!("{} MB", info.flash_chip_size / 1024)
+                                    } else {
+                                        format!("{} KB", info.flash_chip_size)
+                                    },
+                                ),
+                                theme,
+                                true,
+                            )),
                     ),
             )
     }
@@ -304,6 +424,28 @@ impl HomeView {
 
     fn render_security_status(state: &GlobalDeviceState, theme: &Theme) -> impl IntoElement {
         let status = state.device_status.as_ref().unwrap();
+        if status.provenance.secure_boot == FieldSource::Unverified {
+            return Card::new()
+                .title("Security Status")
+                .icon(Icon::default().path("icons/shield-check.svg"))
+                .child(
+                    v_flex()
+                        .gap_3()
+                        .text_sm()
+                        .child(
+                            h_flex()
+                                .justify_between()
+                                .items_center()
+                                .child(div().text_color(theme.muted_foreground).child("Boot Mode"))
+                                .child(Self::unverified("Unknown")),
+                        )
+                        .child(div().text_xs().text_color(theme.muted_foreground).child(
+                            "Secure boot and debug lock state can only be read through the \
+                                 rescue interface, which did not respond.",
+                        )),
+                );
+        }
+
         Card::new()
             .title("Security Status")
             .icon(Icon::default().path("icons/shield-check.svg"))
@@ -388,4 +530,588 @@ impl HomeView {
                     ),
             )
     }
+
+    fn render_attestation_report(report: &AttestationReport, theme: &Theme) -> impl IntoElement {
+        let (label, color, detail) = match &report.trust {
+            AttestationTrust::Trusted(anchor) => {
+                ("Trusted", gpui::green(), format!("Chains to {}", anchor))
+            }
+            AttestationTrust::SelfSigned => (
+                "Self-signed",
+                rgb(0xfe9a00).into(),
+                "The attestation certificate is signed by the key itself".to_string(),
+            ),
+            AttestationTrust::NoCertificate => (
+                "No certificate",
+                theme.secondary,
+                format!(
+                    "Attestation format '{}' carries no certificate",
+                    report.format
+                ),
+            ),
+            AttestationTrust::Untrusted(reason) => ("Untrusted", gpui::red(), reason.clone()),
+        };
+
+        v_flex()
+            .gap_4()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(Badge::new().child(label).color(color))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(if report.signature_valid {
+                                theme.muted_foreground
+                            } else {
+                                gpui::red()
+                            })
+                            .child(if report.signature_valid {
+                                "Signature verified".to_string()
+                            } else {
+                                format!("Signature invalid: {}", report.signature_detail)
+                            }),
+                    ),
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(detail),
+            )
+            .children(report.certificates.iter().enumerate().map(|(i, cert)| {
+                v_flex()
+                    .gap_3()
+                    .pt_4()
+                    .border_t_1()
+                    .border_color(theme.border)
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_semibold()
+                            .text_color(theme.muted_foreground)
+                            .child(if i == 0 {
+                                "Leaf Certificate".to_string()
+                            } else {
+                                format!("Intermediate {}", i)
+                            }),
+                    )
+                    .child(Self::render_kv(
+                        "Subject",
+                        cert.subject.clone(),
+                        theme,
+                        false,
+                    ))
+                    .child(Self::render_kv("Issuer", cert.issuer.clone(), theme, false))
+                    .child(
+                        div()
+                            .grid()
+                            .grid_cols(2)
+                            .gap_4()
+                            .child(Self::render_kv(
+                                "Valid From",
+                                cert.not_before.clone(),
+                                theme,
+                                false,
+                            ))
+                            .child(Self::render_kv(
+                                "Valid Until",
+                                if cert.currently_valid {
+                                    cert.not_after.clone()
+                                } else {
+                                    format!("{} (not valid now)", cert.not_after)
+                                },
+                                theme,
+                                false,
+                            ))
+                            .child(Self::render_kv(
+                                "Key Algorithm",
+                                cert.key_algorithm.clone(),
+                                theme,
+                                false,
+                            ))
+                            .child(Self::render_kv("Serial", cert.serial.clone(), theme, true)),
+                    )
+            }))
+    }
+
+    fn render_attestation(
+        state: &GlobalDeviceState,
+        theme: &Theme,
+        on_inspect: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> impl IntoElement {
+        Card::new()
+            .title("Attestation")
+            .icon(Icon::default().path("icons/shield-check.svg"))
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(match &state.attestation {
+                        Some(report) => {
+                            Self::render_attestation_report(report, theme).into_any_element()
+                        }
+                        None => div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(
+                                "Register a throwaway credential to read and verify the \
+                                 attestation certificate. Nothing is stored on the key.",
+                            )
+                            .into_any_element(),
+                    })
+                    .child(
+                        h_flex().child(
+                            PFIconButton::new(
+                                Icon::default().path("icons/search.svg"),
+                                if state.attestation.is_some() {
+                                    "Inspect Again"
+                                } else {
+                                    "Inspect Attestation"
+                                },
+                            )
+                            .disabled(state.attestation_loading)
+                            .loading(state.attestation_loading)
+                            .on_click(on_inspect),
+                        ),
+                    ),
+            )
+    }
+
+    fn render_metadata_icon(icon: &MetadataIcon) -> Option<impl IntoElement> {
+        let format = match icon.mime_type.as_str() {
+            "image/png" => ImageFormat::Png,
+            "image/jpeg" => ImageFormat::Jpeg,
+            "image/gif" => ImageFormat::Gif,
+            "image/webp" => ImageFormat::Webp,
+            "image/bmp" => ImageFormat::Bmp,
+            "image/svg+xml" => ImageFormat::Svg,
+            _ => return None,
+        };
+        Some(
+            img(std::sync::Arc::new(Image::from_bytes(
+                format,
+                icon.data.clone(),
+            )))
+            .size_10(),
+        )
+    }
+
+    fn render_metadata_entry(entry: &AuthenticatorMetadata, theme: &Theme) -> impl IntoElement {
+        let certified = entry.certification.starts_with("FIDO Certified");
+        let status_is_certification = entry.latest_status == entry.certification;
+
+        v_flex()
+            .gap_4()
+            .child(
+                h_flex()
+                    .gap_3()
+                    .items_center()
+                    .children(entry.icon.as_ref().and_then(Self::render_metadata_icon))
+                    .child(
+                        v_flex()
+                            .flex_1()
+                            .gap_1()
+                            .child(
+                                div()
+                                    .font_semibold()
+                                    .child(if entry.description.is_empty() {
+                                        "Unnamed authenticator".to_string()
+                                    } else {
+                                        entry.description.clone()
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .font_family("Mono")
+                                    .text_color(theme.muted_foreground)
+                                    .child(entry.aaguid.clone()),
+                            ),
+                    )
+                    .child(
+                        Badge::new()
+                            .child(entry.certification.clone())
+                            .color(if certified {
+                                gpui::green()
+                            } else {
+                                theme.secondary
+                            }),
+                    ),
+            )
+            .child(
+                div()
+                    .grid()
+                    .grid_cols(2)
+                    .gap_4()
+                    .child(Self::render_kv(
+                        "Latest Status",
+                        if status_is_certification {
+                            entry.latest_status.clone()
+                        } else {
+                            format!("{} (check before trusting)", entry.latest_status)
+                        },
+                        theme,
+                        false,
+                    ))
+                    .child(Self::render_kv(
+                        "Status Date",
+                        entry.status_date.clone().unwrap_or("N/A".into()),
+                        theme,
+                        false,
+                    ))
+                    .child(Self::render_kv(
+                        "Authenticator Version",
+                        entry
+                            .authenticator_version
+                            .map(|v| v.to_string())
+                            .unwrap_or("N/A".into()),
+                        theme,
+                        false,
+                    ))
+                    .child(Self::render_kv(
+                        "Attestation Roots",
+                        entry.attestation_roots.len().to_string(),
+                        theme,
+                        false,
+                    )),
+            )
+            .children(entry.attestation_roots.iter().map(|root| {
+                v_flex()
+                    .gap_3()
+                    .pt_4()
+                    .border_t_1()
+                    .border_color(theme.border)
+                    .child(Self::render_kv("Root", root.subject.clone(), theme, false))
+                    .child(
+                        div()
+                            .grid()
+                            .grid_cols(2)
+                            .gap_4()
+                            .child(Self::render_kv(
+                                "Valid Until",
+                                root.not_after.clone(),
+                                theme,
+                                false,
+                            ))
+                            .child(Self::render_kv(
+                                "Key Algorithm",
+                                root.key_algorithm.clone(),
+                                theme,
+                                false,
+                            )),
+                    )
+            }))
+    }
+
+    fn render_metadata(
+        state: &GlobalDeviceState,
+        theme: &Theme,
+        on_import: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> impl IntoElement {
+        let body = match &state.metadata {
+            Some(lookup) => v_flex()
+                .gap_4()
+                .child(match &lookup.entry {
+                    Some(entry) => Self::render_metadata_entry(entry, theme).into_any_element(),
+                    None => div()
+                        .text_sm()
+                        .text_color(theme.muted_foreground)
+                        .child(format!(
+                            "The metadata BLOB has no entry for AAGUID {}.",
+                            lookup.aaguid
+                        ))
+                        .into_any_element(),
+                })
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child(format!(
+                            "BLOB #{} with {} entries, next update {}. Signed by {}.",
+                            lookup.blob.number,
+                            lookup.blob.entries,
+                            lookup.blob.next_update,
+                            lookup.blob.signer
+                        )),
+                )
+                .into_any_element(),
+            None => div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child(
+                    "Import a metadata BLOB downloaded from the FIDO Alliance Metadata \
+                     Service to see what it declares about this authenticator.",
+                )
+                .into_any_element(),
+        };
+
+        Card::new()
+            .title("Authenticator Metadata")
+            .icon(Icon::default().path("icons/book-open.svg"))
+            .child(
+                v_flex().gap_4().child(body).child(
+                    h_flex().child(
+                        PFIconButton::new(
+                            Icon::default().path("icons/folder-open.svg"),
+                            if state.metadata.is_some() {
+                                "Import Newer BLOB"
+                            } else {
+                                "Import MDS BLOB"
+                            },
+                        )
+                        .disabled(state.metadata_loading || state.fido_info.is_none())
+                        .loading(state.metadata_loading)
+                        .on_click(on_import),
+                    ),
+                ),
+            )
+    }
+
+    fn or_na(value: Option<u32>) -> String {
+        value.map(|v| v.to_string()).unwrap_or("N/A".into())
+    }
+
+    fn list_or_none(items: Vec<String>) -> String {
+        if items.is_empty() {
+            "None".into()
+        } else {
+            items.join(", ")
+        }
+    }
+
+    fn render_advanced_info(
+        fido: &FidoDeviceInfo,
+        open: bool,
+        theme: &Theme,
+        on_toggle: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+    ) -> impl IntoElement {
+        let toggle = PFIconButton::new(
+            Icon::default().path(if open {
+                "icons/chevron-up.svg"
+            } else {
+                "icons/chevron-down.svg"
+            }),
+            if open { "Hide" } else { "Show" },
+        )
+        .small()
+        .on_click(on_toggle);
+
+        let card = Card::new()
+            .title("Authenticator Details")
+            .icon(Icon::default().path("icons/info.svg"))
+            .description("Everything the key reports in GetInfo")
+            .header_right(toggle);
+
+        if !open {
+            return card;
+        }
+
+        let mut options: Vec<(&String, &bool)> = fido.options.iter().collect();
+        options.sort();
+
+        let algorithms = fido
+            .algorithms
+            .iter()
+            .map(|alg| match CoseAlgorithm::from_i32(*alg) {
+                Some(name) => format!("{} ({})", name, alg),
+                None => alg.to_string(),
+            })
+            .collect();
+        let certifications = fido
+            .certifications
+            .iter()
+            .map(|(program, level)| format!("{} level {}", program, level))
+            .collect();
+        let vendor_commands = fido
+            .vendor_prototype_config_commands
+            .iter()
+            .map(|c| format!("0x{:X}", c))
+            .collect();
+
+        card.child(
+            v_flex()
+                .gap_6()
+                .child(
+                    div()
+                        .grid()
+                        .grid_cols(2)
+                        .gap_4()
+                        .child(Self::render_kv(
+                            "Versions",
+                            Self::list_or_none(fido.versions.clone()),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Extensions",
+                            Self::list_or_none(fido.extensions.clone()),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Algorithms",
+                            Self::list_or_none(algorithms),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Transports",
+                            Self::list_or_none(fido.transports.clone()),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "PIN/UV Auth Protocols",
+                            Self::list_or_none(
+                                fido.pin_protocols.iter().map(|p| p.to_string()).collect(),
+                            ),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Attestation Formats",
+                            Self::list_or_none(fido.attestation_formats.clone()),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Certifications",
+                            Self::list_or_none(certifications),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Vendor Config Commands",
+                            Self::list_or_none(vendor_commands),
+                            theme,
+                            true,
+                        )),
+                )
+                .child(div().h_px().bg(theme.border))
+                .child(
+                    div()
+                        .grid()
+                        .grid_cols(3)
+                        .gap_4()
+                        .child(Self::render_kv(
+                            "Remaining Discoverable Credentials",
+                            Self::or_na(fido.remaining_disc_creds),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max Message Size",
+                            fido.max_msg_size.to_string(),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max Credentials in List",
+                            Self::or_na(fido.max_credential_count_in_list),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max Credential ID Length",
+                            Self::or_na(fido.max_credential_id_length),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max Large Blob Array",
+                            Self::or_na(fido.max_serialized_large_blob_array),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max credBlob Length",
+                            Self::or_na(fido.max_cred_blob_length),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Max RP IDs for Min PIN Length",
+                            Self::or_na(fido.max_rp_ids_for_set_min_pin_length),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "PIN Length",
+                            format!(
+                                "{} to {}",
+                                fido.min_pin_length,
+                                Self::or_na(fido.max_pin_length)
+                            ),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Force PIN Change",
+                            if fido.force_pin_change { "Yes" } else { "No" },
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Preferred UV Attempts",
+                            Self::or_na(fido.preferred_platform_uv_attempts),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "UV Modality",
+                            fido.uv_modality
+                                .map(|m| format!("0x{:X}", m))
+                                .unwrap_or("N/A".into()),
+                            theme,
+                            true,
+                        ))
+                        .child(Self::render_kv(
+                            "UV Count Since PIN",
+                            Self::or_na(fido.uv_count_since_last_pin_entry),
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Long Touch for Reset",
+                            if fido.long_touch_for_reset {
+                                "Yes"
+                            } else {
+                                "No"
+                            },
+                            theme,
+                            false,
+                        ))
+                        .child(Self::render_kv(
+                            "Reset Transports",
+                            Self::list_or_none(fido.transports_for_reset.clone()),
+                            theme,
+                            false,
+                        )),
+                )
+                .child(div().h_px().bg(theme.border))
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(theme.muted_foreground)
+                                .child("Options"),
+                        )
+                        .child(
+                            h_flex()
+                                .flex_wrap()
+                                .gap_2()
+                                .children(options.into_iter().map(|(name, enabled)| {
+                                    Badge::new().child(name.clone()).color(if *enabled {
+                                        gpui::green()
+                                    } else {
+                                        theme.secondary
+                                    })
+                                })),
+                        ),
+                ),
+        )
+    }
 }
@@ -1,20 +1,29 @@
 use crate::device::io;
-use crate::device::types::{FidoDeviceInfo, FullDeviceStatus, StoredCredential};
+use crate::device::types::{
+    CredentialDeleteResult, CredentialsMetadata, Feature, FidoDeviceInfo, FullDeviceStatus,
+    InventoryFormat, LargeBlobStorage, PinComplexityPolicy, SshKeyRequest, SshKeyType,
+    StoredCredential,
+};
 use crate::ui::components::{
     button::{PFButton, PFIconButton},
     card::Card,
     page_view::PageView,
 };
+use crate::ui::paths;
 use gpui::*;
 use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
 use gpui_component::{
-    ActiveTheme, Icon, Sizable, StyledExt, Theme, WindowExt,
+    ActiveTheme, Disableable, Icon, Sizable, StyledExt, Theme, WindowExt,
     badge::Badge,
+    checkbox::Checkbox,
     h_flex,
     input::{Input, InputState},
+    progress::Progress,
     slider::{Slider, SliderState},
+    switch::Switch,
     v_flex,
 };
+use std::collections::{BTreeMap, HashSet};
 
 struct SliderLabel {
     slider: Entity<SliderState>,
@@ -27,12 +36,136 @@ impl Render for SliderLabel {
     }
 }
 
+struct PinPolicyForm {
+    policy: PinComplexityPolicy,
+}
+
+impl PinPolicyForm {
+    fn rule_row(
+        id: &'static str,
+        title: &'static str,
+        description: &'static str,
+        checked: bool,
+        listener: impl Fn(&bool, &mut Window, &mut App) + 'static,
+        theme: &Theme,
+    ) -> impl IntoElement {
+        h_flex()
+            .justify_between()
+            .items_center()
+            .child(
+                v_flex()
+                    .gap_1()
+                    .child(div().text_sm().font_medium().child(title))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(description),
+                    ),
+            )
+            .child(Switch::new(id).checked(checked).on_click(listener))
+    }
+}
+
+impl Render for PinPolicyForm {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let sequences_listener = cx.listener(|this, checked, _, cx| {
+            this.policy.no_sequences = *checked;
+            cx.notify();
+        });
+        let repeats_listener = cx.listener(|this, checked, _, cx| {
+            this.policy.no_repeats = *checked;
+            cx.notify();
+        });
+        let classes_listener = cx.listener(|this, checked, _, cx| {
+            this.policy.mixed_classes = *checked;
+            cx.notify();
+        });
+        let theme = cx.theme();
+
+        v_flex()
+            .gap_4()
+            .child(Self::rule_row(
+                "policy-no-sequences",
+                "Reject sequences",
+                "PINs such as 123456 or fedcba",
+                self.policy.no_sequences,
+                sequences_listener,
+                theme,
+            ))
+            .child(Self::rule_row(
+                "policy-no-repeats",
+                "Reject repeats",
+                "PINs made of one repeated character, such as 111111",
+                self.policy.no_repeats,
+                repeats_listener,
+                theme,
+            ))
+            .child(Self::rule_row(
+                "policy-mixed-classes",
+                "Require mixed characters",
+                "At least two of digits, lowercase, uppercase and symbols",
+                self.policy.mixed_classes,
+                classes_listener,
+                theme,
+            ))
+    }
+}
+
+struct SshKeyForm {
+    ed25519: bool,
+    ed25519_supported: bool,
+    verify_required: bool,
+}
+
+impl Render for SshKeyForm {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ed25519_listener = cx.listener(|this, checked, _, cx| {
+            this.ed25519 = *checked;
+            cx.notify();
+        });
+        let verify_listener = cx.listener(|this, checked, _, cx| {
+            this.verify_required = *checked;
+            cx.notify();
+        });
+        let theme = cx.theme();
+
+        v_flex()
+            .gap_4()
+            .child(PinPolicyForm::rule_row(
+                "ssh-ed25519",
+                "Ed25519",
+                if self.ed25519_supported {
+                    "sk-ssh-ed25519 instead of sk-ecdsa-sha2-nistp256"
+                } else {
+                    "Not supported by this key"
+                },
+                self.ed25519,
+                ed25519_listener,
+                theme,
+            ))
+            .child(PinPolicyForm::rule_row(
+                "ssh-verify-required",
+                "Require PIN",
+                "Ask for the PIN on every login (verify-required)",
+                self.verify_required,
+                verify_listener,
+                theme,
+            ))
+    }
+}
+
 pub struct PasskeysView {
     device_status: Option<FullDeviceStatus>,
     fido_info: Option<FidoDeviceInfo>,
     credentials: Vec<StoredCredential>,
+    credentials_metadata: Option<CredentialsMetadata>,
+    expanded_credential: Option<String>,
+    selected_credentials: HashSet<String>,
+    bulk_results: Option<Vec<CredentialDeleteResult>>,
     unlocked: bool,
     cached_pin: Option<String>,
+    large_blobs: Option<LargeBlobStorage>,
     loading: bool,
 
     _task: Option<Task<()>>,
@@ -56,8 +189,13 @@ impl PasskeysView {
             device_status,
             fido_info,
             credentials: Vec::new(),
+            credentials_metadata: None,
+            expanded_credential: None,
+            selected_credentials: HashSet::new(),
+            bulk_results: None,
             unlocked: false,
             cached_pin: None,
+            large_blobs: None,
             loading: false,
             _task: None,
         }
@@ -77,6 +215,25 @@ impl PasskeysView {
         cx.notify();
     }
 
+    /// Why `feature` cannot be used on the connected key, if it cannot.
+    fn unsupported(&self, feature: Feature) -> Option<String> {
+        io::feature_support(self.device_status.as_ref(), feature).err()
+    }
+
+    fn unsupported_note(reason: String) -> impl IntoElement {
+        div().text_sm().text_color(rgb(0xfe9a00)).child(reason)
+    }
+
+    /// Validates a new PIN locally, against `min_len` or the device minimum when not given.
+    fn validate_new_pin(&self, pin: &str, min_len: Option<u32>) -> Result<(), String> {
+        let (device_min, policy) = self
+            .fido_info
+            .as_ref()
+            .map(|f| (f.min_pin_length, f.pin_complexity_policy.clone()))
+            .unwrap_or((4, PinComplexityPolicy::default()));
+        io::validate_new_pin(pin, min_len.unwrap_or(device_min), &policy)
+    }
+
     fn unlock_storage(&mut self, pin: String, cx: &mut Context<Self>) {
         if self.loading {
             return;
@@ -90,16 +247,17 @@ impl PasskeysView {
             let pin_for_bg = pin.clone();
             let result = cx
                 .background_executor()
-                .spawn(async move { io::get_credentials(pin_for_bg) })
+                .spawn(async move { io::get_credentials_with_metadata(pin_for_bg) })
                 .await;
 
             let _ = entity.update(cx, |this, cx| {
                 this.loading = false;
                 match result {
-                    Ok(creds) => {
+                    Ok((creds, metadata)) => {
                         this.unlocked = true;
                         this.cached_pin = Some(pin);
                         this.credentials = creds;
+                        this.credentials_metadata = Some(metadata);
                         cx.emit(PasskeysEvent::CloseDialog);
                     }
                     Err(e) => {
@@ -116,6 +274,11 @@ impl PasskeysView {
         self.unlocked = false;
         self.cached_pin = None;
         self.credentials.clear();
+        self.credentials_metadata = None;
+        self.large_blobs = None;
+        self.expanded_credential = None;
+        self.selected_credentials.clear();
+        self.bulk_results = None;
         cx.notify();
     }
 
@@ -153,18 +316,79 @@ impl PasskeysView {
         }));
     }
 
+    fn execute_bulk_delete(
+        &mut self,
+        credentials: Vec<StoredCredential>,
+        pin: String,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let pin_for_bg = pin.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::delete_credentials(pin_for_bg, credentials) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| match result {
+                Ok(results) => {
+                    let deleted = results.iter().filter(|r| r.error.is_none()).count();
+                    let failed = results.len() - deleted;
+                    for r in results.iter().filter(|r| r.error.is_none()) {
+                        this.selected_credentials.remove(&r.credential_id);
+                    }
+
+                    let msg = if failed == 0 {
+                        format!("Deleted {} passkeys", deleted)
+                    } else {
+                        format!("Deleted {} passkeys, {} failed", deleted, failed)
+                    };
+                    this.bulk_results = Some(results);
+                    this.refresh_credentials(pin, cx);
+                    cx.emit(PasskeysEvent::CloseDialog);
+                    cx.emit(PasskeysEvent::Notification(msg));
+                }
+                Err(e) => {
+                    this.loading = false;
+                    let msg = format!("Error deleting: {}", e);
+                    cx.emit(PasskeysEvent::Notification(msg));
+                    cx.notify();
+                }
+            });
+        }));
+    }
+
     fn refresh_credentials(&mut self, pin: String, cx: &mut Context<Self>) {
         let entity = cx.entity().downgrade();
         self._task = Some(cx.spawn(async move |_, cx| {
             let result = cx
                 .background_executor()
-                .spawn(async move { io::get_credentials(pin) })
+                .spawn(async move { io::get_credentials_with_metadata(pin) })
                 .await;
 
             let _ = entity.update(cx, |this, cx| {
                 this.loading = false;
-                if let Ok(creds) = result {
-                    this.credentials = creds;
+                match result {
+                    Ok((creds, metadata)) => {
+                        this.selected_credentials
+                            .retain(|id| creds.iter().any(|c| &c.credential_id == id));
+                        this.credentials = creds;
+                        this.credentials_metadata = Some(metadata);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to refresh credentials: {}", e);
+                        cx.emit(PasskeysEvent::Notification(format!(
+                            "Failed to refresh passkeys: {}",
+                            e
+                        )));
+                    }
                 }
                 cx.notify();
             });
@@ -256,6 +480,50 @@ impl PasskeysView {
         });
     }
 
+    fn open_bulk_delete_dialog(
+        &mut self,
+        credentials: Vec<StoredCredential>,
+        description: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if credentials.is_empty() {
+            return;
+        }
+        let Some(pin) = self.cached_pin.clone() else {
+            window.push_notification("Session expired, please unlock again.", cx);
+            self.lock_storage(cx);
+            return;
+        };
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view_handle = view_handle.clone();
+            let credentials = credentials.clone();
+            let pin = pin.clone();
+
+            dialog
+                .confirm()
+                .title("Delete Passkeys")
+                .child(div().pb_4().child(format!(
+                    "Are you sure you want to delete {}? This cannot be undone.",
+                    description
+                )))
+                .on_ok(move |_, _, cx| {
+                    let _ = view_handle.update(cx, |this, cx| {
+                        this.execute_bulk_delete(credentials.clone(), pin.clone(), cx);
+                    });
+                    false
+                })
+                .on_cancel(|_, _, _| true)
+                .button_props(
+                    gpui_component::dialog::DialogButtonProps::default()
+                        .ok_text("Delete")
+                        .ok_variant(ButtonVariant::Danger),
+                )
+        });
+    }
+
     fn open_change_pin_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let current_pin = cx.new(|cx| {
             InputState::new(window, cx)
@@ -326,11 +594,13 @@ impl PasskeysView {
                                     return;
                                 }
 
-                                if new_val.len() < 4 {
+                                let validation = view
+                                    .upgrade()
+                                    .map(|v| v.read(cx).validate_new_pin(&new_val, None))
+                                    .unwrap_or(Ok(()));
+                                if let Err(msg) = validation {
                                     let _ = view.update(cx, |_, cx| {
-                                        cx.emit(PasskeysEvent::Notification(
-                                            "PIN too short".to_string(),
-                                        ));
+                                        cx.emit(PasskeysEvent::Notification(msg));
                                     });
                                     return;
                                 }
@@ -447,9 +717,13 @@ impl PasskeysView {
                                         });
                                         return;
                                     }
-                                    if new_val.len() < min_len as usize {
+                                    let validation = view
+                                        .upgrade()
+                                        .map(|v| v.read(cx).validate_new_pin(&new_val, Some(min_len as u32)))
+                                        .unwrap_or(Ok(()));
+                                    if let Err(msg) = validation {
                                         let _ = view.update(cx, |_, cx| {
-                                            cx.emit(PasskeysEvent::Notification(format!("PIN must be at least {} characters", min_len)));
+                                            cx.emit(PasskeysEvent::Notification(msg));
                                         });
                                         return;
                                     }
@@ -463,6 +737,103 @@ impl PasskeysView {
         });
     }
 
+    fn open_pin_policy_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let policy = self
+            .fido_info
+            .as_ref()
+            .map(|f| f.pin_complexity_policy.clone())
+            .unwrap_or_default();
+        let form = cx.new(|_| PinPolicyForm { policy });
+        let current_pin = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Enter current PIN")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let form = form.clone();
+            let current = current_pin.clone();
+
+            dialog
+                .title("PIN Complexity Policy")
+                .child(
+                    "Rules the key enforces for every new PIN, on top of the minimum length. \
+                     Existing PINs are not affected until they are changed.",
+                )
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .py_4()
+                        .child(form.clone())
+                        .child("Current PIN")
+                        .child(Input::new(&current)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let form = form.clone();
+                    let current = current.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("save-policy").primary().label("Save").on_click(
+                            move |_, _, cx| {
+                                let current_val = current.read(cx).text().to_string();
+                                if current_val.is_empty() {
+                                    return;
+                                }
+                                let policy = form.read(cx).policy.clone();
+                                let _ = view.update(cx, |this, cx| {
+                                    this.set_pin_policy(current_val, policy, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn set_pin_policy(
+        &mut self,
+        current: String,
+        policy: PinComplexityPolicy,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::set_pin_complexity_policy(current, policy) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(msg) => {
+                        cx.emit(PasskeysEvent::CloseDialog);
+                        cx.emit(PasskeysEvent::Notification(msg));
+                        if let Ok(info) = io::get_fido_info() {
+                            this.fido_info = Some(info);
+                        }
+                    }
+                    Err(e) => {
+                        cx.emit(PasskeysEvent::Notification(format!("Error: {}", e)));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
     fn change_pin(&mut self, current: String, new: String, cx: &mut Context<Self>) {
         if self.loading {
             return;
@@ -574,49 +945,491 @@ impl PasskeysView {
         }));
     }
 
-    fn render_no_device(&self, theme: &Theme) -> impl IntoElement {
-        div()
-            .flex()
-            .items_center()
-            .justify_center()
-            .h_64()
-            .border_1()
-            .border_color(theme.border)
-            .rounded_xl()
-            .child(
-                div()
-                    .text_color(theme.muted_foreground)
-                    .child("Connect your pico-key to manage passkeys."),
-            )
-            .into_any_element()
-    }
+    fn load_large_blobs(&mut self, cx: &mut Context<Self>) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
 
-    fn render_not_supported(&self, theme: &Theme) -> impl IntoElement {
-        div()
-            .flex()
-            .items_center()
-            .justify_center()
-            .h_64()
-            .border_1()
-            .border_color(theme.border)
-            .rounded_xl()
-            .child(
-                div()
-                    .text_color(theme.muted_foreground)
-                    .child("FIDO Passkeys are not supported on this device."),
-            )
-            .into_any_element()
-    }
+        let entity = cx.entity().downgrade();
 
-    fn render_pin_management(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        let status_row = self.render_pin_status_row(cx).into_any_element();
-        let min_len_row = self.render_min_pin_length_row(cx).into_any_element();
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::read_large_blobs(pin) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(storage) => {
+                        if !storage.integrity_ok {
+                            cx.emit(PasskeysEvent::Notification(
+                                "Large blob array failed its integrity check".to_string(),
+                            ));
+                        }
+                        this.large_blobs = Some(storage);
+                    }
+                    Err(e) => {
+                        cx.emit(PasskeysEvent::Notification(format!(
+                            "Failed to read large blobs: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Deletes the entry with the given fingerprint, or clears the whole array when `None`.
+    fn write_large_blobs(&mut self, fingerprint: Option<String>, cx: &mut Context<Self>) {
+        let Some(pin) = self.cached_pin.clone() else {
+            return;
+        };
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    match fingerprint {
+                        Some(fingerprint) => io::delete_large_blob(pin, fingerprint),
+                        None => io::clear_large_blobs(pin),
+                    }
+                })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(msg) => {
+                        cx.emit(PasskeysEvent::CloseDialog);
+                        cx.emit(PasskeysEvent::Notification(msg));
+                        this.load_large_blobs(cx);
+                    }
+                    Err(e) => {
+                        cx.emit(PasskeysEvent::Notification(format!("Error: {}", e)));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn open_large_blob_write_dialog(
+        &mut self,
+        target: Option<(usize, String)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let view_handle = cx.entity().downgrade();
+        let (title, message, ok_text) = match &target {
+            Some((i, _)) => (
+                "Delete Large Blob",
+                format!(
+                    "Delete large blob #{}? Sites that stored data in it will lose that data.",
+                    i + 1
+                ),
+                "Delete",
+            ),
+            None => (
+                "Clear Large Blobs",
+                "Remove every large blob stored on this key? Sites that stored data in them \
+                 will lose that data."
+                    .to_string(),
+                "Clear All",
+            ),
+        };
+
+        let fingerprint = target.map(|(_, fingerprint)| fingerprint);
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view_handle = view_handle.clone();
+            let fingerprint = fingerprint.clone();
+
+            dialog
+                .confirm()
+                .title(title)
+                .child(div().pb_4().child(message.clone()))
+                .on_ok(move |_, _, cx| {
+                    let fingerprint = fingerprint.clone();
+                    let _ = view_handle.update(cx, |this, cx| {
+                        this.write_large_blobs(fingerprint, cx);
+                    });
+                    false
+                })
+                .on_cancel(|_, _, _| true)
+                .button_props(
+                    gpui_component::dialog::DialogButtonProps::default()
+                        .ok_text(ok_text)
+                        .ok_variant(ButtonVariant::Danger),
+                )
+        });
+    }
+
+    fn export_large_blob(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(data) = self
+            .large_blobs
+            .as_ref()
+            .and_then(|s| s.entries.get(index))
+            .and_then(|e| e.data.clone())
+        else {
+            return;
+        };
+        let suggested_name = format!("large-blob-{}.bin", index + 1);
+        let path_rx = cx.prompt_for_new_path(&paths::default_export_dir(), Some(&suggested_name));
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_rx.await else {
+                return;
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::export_large_blob(path, data) })
+                .await;
+
+            let _ = this.update(cx, |_, cx| {
+                cx.emit(PasskeysEvent::Notification(match result {
+                    Ok(()) => "Large blob exported".to_string(),
+                    Err(e) => format!("Export failed: {}", e),
+                }));
+            });
+        }));
+    }
+
+    fn export_inventory(
+        &mut self,
+        format: InventoryFormat,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.credentials.is_empty() {
+            return;
+        }
+        let credentials = self.credentials.clone();
+        let aaguid = self.fido_info.as_ref().map(|f| f.aaguid.clone());
+        let suggested_name = format!(
+            "picoforge-passkeys-{}.{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            format.extension()
+        );
+        let path_rx = cx.prompt_for_new_path(&paths::default_export_dir(), Some(&suggested_name));
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_rx.await else {
+                return;
+            };
+
+            let count = credentials.len();
+            let result = cx
+                .background_executor()
+                .spawn(
+                    async move { io::export_passkey_inventory(path, credentials, aaguid, format) },
+                )
+                .await;
+
+            let _ = this.update(cx, |_, cx| {
+                cx.emit(PasskeysEvent::Notification(match result {
+                    Ok(()) => format!("Exported {} passkeys", count),
+                    Err(e) => format!("Export failed: {}", e),
+                }));
+            });
+        }));
+    }
+
+    fn open_ssh_key_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let ed25519_supported = self
+            .fido_info
+            .as_ref()
+            .is_some_and(|f| f.algorithms.contains(&-8));
+        let form = cx.new(|_| SshKeyForm {
+            ed25519: false,
+            ed25519_supported,
+            verify_required: false,
+        });
+        let application_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("ssh:")
+                .default_value("ssh:")
+        });
+        let user_input = cx.new(|cx| InputState::new(window, cx).placeholder("openssh"));
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let form = form.clone();
+            let application = application_input.clone();
+            let user = user_input.clone();
+
+            dialog
+                .title("New SSH Key")
+                .child(
+                    "Creates a resident OpenSSH security key, like ssh-keygen -O resident. \
+                     Only the key handle is written to disk; the private key stays on the device.",
+                )
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .py_4()
+                        .child("Application")
+                        .child(Input::new(&application))
+                        .child("User (optional)")
+                        .child(Input::new(&user))
+                        .child(form.clone()),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let form = form.clone();
+                    let application = application.clone();
+                    let user = user.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("create-ssh-key")
+                            .primary()
+                            .label("Create")
+                            .on_click(move |_, window, cx| {
+                                let application_val =
+                                    application.read(cx).value().trim().to_string();
+                                let user_val = user.read(cx).value().trim().to_string();
+                                if !application_val.starts_with("ssh:") {
+                                    let _ = view.update(cx, |_, cx| {
+                                        cx.emit(PasskeysEvent::Notification(
+                                            "The application must start with \"ssh:\"".to_string(),
+                                        ));
+                                    });
+                                    return;
+                                }
+                                let form = form.read(cx);
+                                let request = SshKeyRequest {
+                                    key_type: if form.ed25519 && form.ed25519_supported {
+                                        SshKeyType::Ed25519Sk
+                                    } else {
+                                        SshKeyType::EcdsaSk
+                                    },
+                                    application: application_val,
+                                    user: (!user_val.is_empty()).then_some(user_val),
+                                    verify_required: form.verify_required,
+                                    overwrite: false,
+                                };
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    if this.credentials.iter().any(|c| request.replaces(c)) {
+                                        this.open_ssh_overwrite_dialog(request, window, cx);
+                                    } else {
+                                        this.generate_ssh_key(request, window, cx);
+                                    }
+                                });
+                            }),
+                    ]
+                })
+        });
+    }
+
+    fn open_ssh_overwrite_dialog(
+        &mut self,
+        request: SshKeyRequest,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let user = request
+            .user
+            .clone()
+            .unwrap_or_else(|| "openssh".to_string());
+        let message = format!(
+            "The device already holds an SSH key for {} and user {}. Creating a new one \
+             overwrites it, and key files made from the old one stop working.",
+            request.application, user
+        );
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view_handle = view_handle.clone();
+            let request = request.clone();
+
+            dialog
+                .confirm()
+                .title("Replace SSH Key")
+                .child(div().pb_4().child(message.clone()))
+                .on_ok(move |_, window, cx| {
+                    let request = SshKeyRequest {
+                        overwrite: true,
+                        ..request.clone()
+                    };
+                    let _ = view_handle.update(cx, |this, cx| {
+                        this.generate_ssh_key(request, window, cx);
+                    });
+                    true
+                })
+                .on_cancel(|_, _, _| true)
+                .button_props(
+                    gpui_component::dialog::DialogButtonProps::default()
+                        .ok_text("Replace")
+                        .ok_variant(ButtonVariant::Danger),
+                )
+        });
+    }
+
+    fn generate_ssh_key(
+        &mut self,
+        request: SshKeyRequest,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pin) = self.cached_pin.clone() else {
+            window.push_notification("Session expired, please unlock again.", cx);
+            self.lock_storage(cx);
+            return;
+        };
+        if self.loading {
+            return;
+        }
+        let path_rx = cx.prompt_for_new_path(
+            &paths::default_export_dir(),
+            Some(request.key_type.file_stem()),
+        );
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_rx.await else {
+                return;
+            };
+            let _ = this.update(cx, |this, cx| {
+                this.loading = true;
+                cx.emit(PasskeysEvent::Notification(
+                    "Touch your key to create the SSH key".to_string(),
+                ));
+                cx.notify();
+            });
+
+            let pin_for_bg = pin.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::generate_ssh_key(pin_for_bg, request, path) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(_) => {
+                        cx.emit(PasskeysEvent::Notification("SSH key created".to_string()));
+                        this.refresh_credentials(pin, cx);
+                    }
+                    Err(e) => {
+                        cx.emit(PasskeysEvent::Notification(format!(
+                            "Failed to create SSH key: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn export_ssh_keys(
+        &mut self,
+        credentials: Vec<StoredCredential>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let path_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some("Save SSH keys here".into()),
+        });
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let dir = match path_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::export_ssh_keys(credentials, dir) })
+                .await;
+
+            let _ = this.update(cx, |_, cx| {
+                cx.emit(PasskeysEvent::Notification(match result {
+                    Ok(export) if export.skipped.is_empty() => {
+                        format!("Saved {} SSH keys", export.written.len())
+                    }
+                    Ok(export) => format!(
+                        "Saved {} SSH keys, skipped {}",
+                        export.written.len(),
+                        export.skipped.join("; ")
+                    ),
+                    Err(e) => format!("Export failed: {}", e),
+                }));
+            });
+        }));
+    }
+
+    fn render_no_device(&self, theme: &Theme) -> impl IntoElement {
+        div()
+            .flex()
+            .items_center()
+            .justify_center()
+            .h_64()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_xl()
+            .child(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .child("Connect your pico-key to manage passkeys."),
+            )
+            .into_any_element()
+    }
+
+    fn render_not_supported(&self, theme: &Theme) -> impl IntoElement {
+        div()
+            .flex()
+            .items_center()
+            .justify_center()
+            .h_64()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_xl()
+            .child(
+                div()
+                    .text_color(theme.muted_foreground)
+                    .child("FIDO Passkeys are not supported on this device."),
+            )
+            .into_any_element()
+    }
+
+    fn render_pin_management(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let status_row = self.render_pin_status_row(cx).into_any_element();
+        let min_len_row = self.render_min_pin_length_row(cx).into_any_element();
+        let policy_row = self.render_pin_policy_row(cx).into_any_element();
 
         Card::new()
             .title("PIN Management")
             .icon(Icon::default().path("icons/key.svg"))
             .description("Configure FIDO2 PIN security")
-            .child(v_flex().gap_4().child(status_row).child(min_len_row))
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(status_row)
+                    .child(min_len_row)
+                    .child(policy_row),
+            )
     }
 
     fn render_pin_status_row(&self, cx: &mut Context<Self>) -> impl IntoElement {
@@ -672,6 +1485,8 @@ impl PasskeysView {
             .as_ref()
             .and_then(|f| f.options.get("clientPin").copied())
             .unwrap_or(false);
+        let unsupported = self.unsupported(Feature::MinPinLength);
+        let available = pin_set && unsupported.is_none();
 
         let theme = cx.theme();
 
@@ -691,18 +1506,83 @@ impl PasskeysView {
                             .text_sm()
                             .text_color(theme.muted_foreground)
                             .child(format!("Current: {} characters", min_len)),
-                    ),
+                    )
+                    .children(unsupported.map(Self::unsupported_note)),
             )
             .child(
                 PFButton::new("Update Minimum Length")
                     .id("update-min-len-btn")
-                    .disabled(!pin_set)
+                    .disabled(!available)
                     .on_click(cx.listener(|this, _, window, cx| {
                         this.open_min_pin_length_dialog(window, cx);
                     })),
             )
     }
 
+    fn render_pin_policy_row(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let policy = self
+            .fido_info
+            .as_ref()
+            .map(|f| f.pin_complexity_policy.clone())
+            .unwrap_or_default();
+        let pin_set = self
+            .fido_info
+            .as_ref()
+            .and_then(|f| f.options.get("clientPin").copied())
+            .unwrap_or(false);
+
+        let mut rules = Vec::new();
+        if policy.no_sequences {
+            rules.push("no sequences");
+        }
+        if policy.no_repeats {
+            rules.push("no repeats");
+        }
+        if policy.mixed_classes {
+            rules.push("mixed characters");
+        }
+        let summary = if !rules.is_empty() {
+            format!("Enforced: {}", rules.join(", "))
+        } else if policy.enforced {
+            "A policy is in force; the key checks new PINs against it".to_string()
+        } else {
+            "No complexity rules".to_string()
+        };
+
+        let unsupported = self.unsupported(Feature::FidoPinPolicy);
+        let available = pin_set && unsupported.is_none();
+
+        let theme = cx.theme();
+
+        div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .p_4()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(
+                v_flex()
+                    .child(div().font_medium().child("PIN Complexity"))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(summary),
+                    )
+                    .children(unsupported.map(Self::unsupported_note)),
+            )
+            .child(
+                PFButton::new("Configure Policy")
+                    .id("pin-policy-btn")
+                    .disabled(!available)
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.open_pin_policy_dialog(window, cx);
+                    })),
+            )
+    }
+
     fn render_stored_passkeys(&self, cx: &mut Context<Self>) -> impl IntoElement {
         if !self.unlocked {
             self.render_locked_state(cx).into_any_element()
@@ -715,6 +1595,8 @@ impl PasskeysView {
         let listener = cx.listener(|this, _, window, cx| {
             this.open_unlock_dialog(window, cx);
         });
+        let unsupported = self.unsupported(Feature::CredentialManagement);
+        let blocked = unsupported.is_some();
         let theme = cx.theme();
 
         Card::new()
@@ -747,90 +1629,535 @@ impl PasskeysView {
                             .text_sm()
                             .child("Unlock your device to view and manage passkeys."),
                     )
+                    .children(unsupported.map(Self::unsupported_note))
                     .child(
                         PFIconButton::new(
                             Icon::default().path("icons/lock-open.svg"),
                             "Unlock Storage",
                         )
-                        .on_click(listener)
-                        .with_colors(rgb(0xe4e4e7), rgb(0xd0d0d3), rgb(0xe4e4e7))
-                        .with_text_color(rgb(0x18181b)),
+                        .disabled(blocked)
+                        .on_click(listener)
+                        .with_colors(rgb(0xe4e4e7), rgb(0xd0d0d3), rgb(0xe4e4e7))
+                        .with_text_color(rgb(0x18181b)),
+                    ),
+            )
+    }
+
+    fn render_unlocked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let creds_len = self.credentials.len();
+        let lock_listener = cx.listener(|this, _, _, cx| {
+            this.lock_storage(cx);
+        });
+        let export_json_listener = cx.listener(|this, _, window, cx| {
+            this.export_inventory(InventoryFormat::Json, window, cx);
+        });
+        let export_csv_listener = cx.listener(|this, _, window, cx| {
+            this.export_inventory(InventoryFormat::Csv, window, cx);
+        });
+        let ssh_key_listener = cx.listener(|this, _, window, cx| {
+            this.open_ssh_key_dialog(window, cx);
+        });
+
+        let mut groups: BTreeMap<&str, Vec<&StoredCredential>> = BTreeMap::new();
+        for cred in &self.credentials {
+            groups.entry(cred.rp_id.as_str()).or_default().push(cred);
+        }
+        let groups: Vec<AnyElement> = groups
+            .into_values()
+            .map(|creds| self.render_rp_group(creds, cx).into_any_element())
+            .collect();
+
+        let selection = self
+            .render_selection_bar(cx)
+            .map(IntoElement::into_any_element);
+        let bulk_results = self
+            .bulk_results
+            .as_ref()
+            .map(|results| self.render_bulk_results(results, cx).into_any_element());
+
+        let theme = cx.theme();
+
+        Card::new()
+            .title("Stored Passkeys")
+            .icon(Icon::default().path("icons/key-round.svg"))
+            .description("View and manage your resident credentials")
+            .child(
+                v_flex()
+                    .gap_6()
+                    .child(
+                        h_flex()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                h_flex()
+                                    .gap_4()
+                                    .items_center()
+                                    .child(
+                                        Badge::new()
+                                            .child(
+                                                h_flex()
+                                                    .gap_1()
+                                                    .items_center()
+                                                    .child(
+                                                        Icon::default()
+                                                            .path("icons/lock-open.svg")
+                                                            .size_3p5(),
+                                                    )
+                                                    .child("Unlocked"),
+                                            )
+                                            .color(gpui::green()),
+                                    )
+                                    .child(div().w_px().h_4().bg(theme.border))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(theme.muted_foreground)
+                                            .child(format!("{} credentials stored", creds_len)),
+                                    ),
+                            )
+                            .child(
+                                h_flex()
+                                    .gap_2()
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/key.svg").size_3p5(),
+                                            "New SSH Key",
+                                        )
+                                        .small()
+                                        .disabled(self.loading)
+                                        .on_click(ssh_key_listener),
+                                    )
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/save.svg").size_3p5(),
+                                            "Export JSON",
+                                        )
+                                        .small()
+                                        .disabled(self.credentials.is_empty())
+                                        .on_click(export_json_listener),
+                                    )
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/save.svg").size_3p5(),
+                                            "Export CSV",
+                                        )
+                                        .small()
+                                        .disabled(self.credentials.is_empty())
+                                        .on_click(export_csv_listener),
+                                    )
+                                    .child(
+                                        PFIconButton::new(
+                                            Icon::default().path("icons/lock.svg").size_3p5(),
+                                            "Lock Storage",
+                                        )
+                                        .small()
+                                        .on_click(lock_listener),
+                                    ),
+                            ),
+                    )
+                    .children(
+                        self.credentials_metadata
+                            .map(|m| Self::render_capacity(m, theme)),
+                    )
+                    .children(selection)
+                    .children(bulk_results)
+                    .child(if self.credentials.is_empty() {
+                        self.render_empty_credentials_with_theme(theme)
+                            .into_any_element()
+                    } else {
+                        v_flex().gap_6().children(groups).into_any_element()
+                    }),
+            )
+    }
+
+    fn render_rp_group(
+        &self,
+        creds: Vec<&StoredCredential>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let first = creds[0];
+        let title = if !first.rp_name.is_empty() {
+            first.rp_name.clone()
+        } else if !first.rp_id.is_empty() {
+            first.rp_id.clone()
+        } else {
+            "Unknown Service".to_string()
+        };
+
+        let owned: Vec<StoredCredential> = creds.iter().map(|c| (*c).clone()).collect();
+        let description = format!("all {} passkeys for {}", owned.len(), title);
+        let ssh_button = first.rp_id.starts_with("ssh:").then(|| {
+            let owned = owned.clone();
+            Button::new(SharedString::from(format!("save-ssh-{}", first.rp_id)))
+                .ghost()
+                .small()
+                .label("Save SSH Keys")
+                .disabled(self.loading)
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.export_ssh_keys(owned.clone(), window, cx);
+                }))
+        });
+        let delete_all_listener = cx.listener(move |this, _, window, cx| {
+            this.open_bulk_delete_dialog(owned.clone(), description.clone(), window, cx);
+        });
+
+        let cards: Vec<AnyElement> = creds
+            .iter()
+            .map(|cred| self.render_credential_card(cred, cx).into_any_element())
+            .collect();
+
+        let theme = cx.theme();
+
+        v_flex()
+            .gap_3()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(div().font_semibold().child(title))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .child(first.rp_id.clone()),
+                            )
+                            .child(
+                                Badge::new()
+                                    .child(format!("{}", creds.len()))
+                                    .color(theme.muted_foreground),
+                            ),
+                    )
+                    .child(
+                        h_flex().gap_2().children(ssh_button).child(
+                            Button::new(SharedString::from(format!("delete-rp-{}", first.rp_id)))
+                                .ghost()
+                                .small()
+                                .label("Delete All")
+                                .disabled(self.loading)
+                                .on_click(delete_all_listener),
+                        ),
+                    ),
+            )
+            .child(div().grid().grid_cols(3).gap_4().children(cards))
+    }
+
+    fn render_selection_bar(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if self.selected_credentials.is_empty() {
+            return None;
+        }
+
+        let selected: Vec<StoredCredential> = self
+            .credentials
+            .iter()
+            .filter(|c| self.selected_credentials.contains(&c.credential_id))
+            .cloned()
+            .collect();
+        let count = selected.len();
+
+        let delete_listener = cx.listener(move |this, _, window, cx| {
+            let description = format!("{} selected passkeys", selected.len());
+            this.open_bulk_delete_dialog(selected.clone(), description, window, cx);
+        });
+        let clear_listener = cx.listener(|this, _, _, cx| {
+            this.selected_credentials.clear();
+            cx.notify();
+        });
+
+        let theme = cx.theme();
+
+        Some(
+            h_flex()
+                .justify_between()
+                .items_center()
+                .p_3()
+                .rounded_lg()
+                .border_1()
+                .border_color(theme.border)
+                .bg(theme.secondary)
+                .child(
+                    div()
+                        .text_sm()
+                        .child(format!("{} passkeys selected", count)),
+                )
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(
+                            PFButton::new("Clear Selection")
+                                .small()
+                                .on_click(clear_listener),
+                        )
+                        .child(
+                            PFIconButton::new(
+                                Icon::default().path("icons/trash-2.svg").size_3p5(),
+                                "Delete Selected",
+                            )
+                            .small()
+                            .disabled(self.loading)
+                            .loading(self.loading)
+                            .on_click(delete_listener),
+                        ),
+                ),
+        )
+    }
+
+    fn render_bulk_results(
+        &self,
+        results: &[CredentialDeleteResult],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let dismiss_listener = cx.listener(|this, _, _, cx| {
+            this.bulk_results = None;
+            cx.notify();
+        });
+
+        let theme = cx.theme();
+        let deleted = results.iter().filter(|r| r.error.is_none()).count();
+
+        v_flex()
+            .gap_2()
+            .p_3()
+            .rounded_lg()
+            .border_1()
+            .border_color(theme.border)
+            .child(
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .child(div().text_sm().font_semibold().child(format!(
+                        "{} of {} passkeys deleted",
+                        deleted,
+                        results.len()
+                    )))
+                    .child(
+                        Button::new("dismiss-bulk-results")
+                            .ghost()
+                            .small()
+                            .label("Dismiss")
+                            .on_click(dismiss_listener),
                     ),
             )
+            .children(results.iter().map(|r| {
+                let (icon, color, status) = match &r.error {
+                    None => (
+                        "icons/circle-check.svg",
+                        gpui::green(),
+                        "Deleted".to_string(),
+                    ),
+                    Some(e) => ("icons/circle-x.svg", gpui::red(), e.clone()),
+                };
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .text_sm()
+                    .child(Icon::default().path(icon).size_4().text_color(color))
+                    .child(format!("{} ({})", r.rp_id, r.user_name))
+                    .child(div().text_color(theme.muted_foreground).child(status))
+            }))
     }
 
-    fn render_unlocked_state(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        let creds_len = self.credentials.len();
-        let lock_listener = cx.listener(|this, _, _, cx| {
-            this.lock_storage(cx);
+    fn render_large_blobs(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let load_listener = cx.listener(|this, _, _, cx| {
+            this.load_large_blobs(cx);
+        });
+        let clear_listener = cx.listener(|this, _, window, cx| {
+            this.open_large_blob_write_dialog(None, window, cx);
         });
 
-        let mut cards = Vec::new();
-        for cred in &self.credentials {
-            cards.push(self.render_credential_card(cred, cx).into_any_element());
-        }
+        let rows: Vec<AnyElement> = self
+            .large_blobs
+            .iter()
+            .flat_map(|s| s.entries.iter())
+            .map(|entry| {
+                let index = entry.index;
+                let fingerprint = entry.fingerprint.clone();
+                let export_listener = cx.listener(move |this, _, window, cx| {
+                    this.export_large_blob(index, window, cx);
+                });
+                let delete_listener = cx.listener(move |this, _, window, cx| {
+                    let target = (index, fingerprint.clone());
+                    this.open_large_blob_write_dialog(Some(target), window, cx);
+                });
+                let theme = cx.theme();
 
-        let theme = cx.theme();
+                let owner = match (&entry.rp_id, &entry.user_name) {
+                    (Some(rp), Some(user)) if !user.is_empty() => format!("{} ({})", rp, user),
+                    (Some(rp), _) => rp.clone(),
+                    _ => "Unknown owner (no stored passkey can decrypt it)".to_string(),
+                };
 
-        Card::new()
-            .title("Stored Passkeys")
-            .icon(Icon::default().path("icons/key-round.svg"))
-            .description("View and manage your resident credentials")
-            .child(
-                v_flex()
-                    .gap_6()
+                h_flex()
+                    .justify_between()
+                    .items_center()
+                    .p_3()
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .child(
+                        v_flex()
+                            .child(
+                                div()
+                                    .font_medium()
+                                    .child(format!("#{} {}", index + 1, owner)),
+                            )
+                            .child(div().text_xs().text_color(theme.muted_foreground).child(
+                                format!(
+                                    "{} bytes stored, {} bytes uncompressed",
+                                    entry.stored_size, entry.orig_size
+                                ),
+                            )),
+                    )
                     .child(
                         h_flex()
-                            .justify_between()
-                            .items_center()
+                            .gap_2()
                             .child(
-                                h_flex()
-                                    .gap_4()
-                                    .items_center()
-                                    .child(
-                                        Badge::new()
-                                            .child(
-                                                h_flex()
-                                                    .gap_1()
-                                                    .items_center()
-                                                    .child(
-                                                        Icon::default()
-                                                            .path("icons/lock-open.svg")
-                                                            .size_3p5(),
-                                                    )
-                                                    .child("Unlocked"),
-                                            )
-                                            .color(gpui::green()),
-                                    )
-                                    .child(div().w_px().h_4().bg(theme.border))
-                                    .child(
-                                        div()
-                                            .text_sm()
-                                            .text_color(theme.muted_foreground)
-                                            .child(format!("{} credentials stored", creds_len)),
-                                    ),
+                                PFIconButton::new(
+                                    Icon::default().path("icons/save.svg").size_3p5(),
+                                    "Export",
+                                )
+                                .small()
+                                .disabled(entry.data.is_none())
+                                .on_click(export_listener),
                             )
                             .child(
                                 PFIconButton::new(
-                                    Icon::default().path("icons/lock.svg").size_3p5(),
-                                    "Lock Storage",
+                                    Icon::default().path("icons/trash-2.svg").size_3p5(),
+                                    "Delete",
                                 )
                                 .small()
-                                .on_click(lock_listener),
+                                .disabled(self.loading)
+                                .on_click(delete_listener),
                             ),
                     )
-                    .child(if self.credentials.is_empty() {
-                        self.render_empty_credentials_with_theme(theme)
-                            .into_any_element()
-                    } else {
+                    .into_any_element()
+            })
+            .collect();
+
+        let unsupported = self.unsupported(Feature::LargeBlobs);
+        let blocked = unsupported.is_some();
+        let theme = cx.theme();
+
+        let body = match (&self.large_blobs, unsupported) {
+            (_, Some(reason)) => Self::unsupported_note(reason).into_any_element(),
+            (None, None) => div()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child("Load the large blob array to see what sites stored on this key.")
+                .into_any_element(),
+            (Some(storage), None) => {
+                let percent = if storage.max_bytes > 0 {
+                    (storage.used_bytes as f32 / storage.max_bytes as f32 * 100.0).min(100.0)
+                } else {
+                    0.0
+                };
+
+                v_flex()
+                    .gap_4()
+                    .child(
+                        v_flex()
+                            .gap_2()
+                            .child(
+                                h_flex()
+                                    .justify_between()
+                                    .text_sm()
+                                    .child(format!("{} entries", storage.entries.len()))
+                                    .child(div().text_color(theme.muted_foreground).child(
+                                        format!(
+                                            "{} / {} bytes",
+                                            storage.used_bytes, storage.max_bytes
+                                        ),
+                                    )),
+                            )
+                            .child(Progress::new().value(percent)),
+                    )
+                    .children((!storage.integrity_ok).then(|| {
+                        div().text_sm().text_color(rgb(0xef4444)).child(
+                            "The stored array failed its SHA-256 check and is treated as empty. \
+                             Clear it to make large blobs usable again.",
+                        )
+                    }))
+                    .children(rows)
+                    .into_any_element()
+            }
+        };
+
+        Card::new()
+            .title("Large Blobs")
+            .icon(Icon::default().path("icons/hard-drive.svg"))
+            .description("Data sites stored alongside their passkeys (CTAP 2.1 largeBlobs)")
+            .header_right(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        PFIconButton::new(
+                            Icon::default().path("icons/refresh-cw.svg").size_3p5(),
+                            if self.large_blobs.is_some() {
+                                "Refresh"
+                            } else {
+                                "Load"
+                            },
+                        )
+                        .small()
+                        .loading(self.loading)
+                        .disabled(self.loading || blocked)
+                        .on_click(load_listener),
+                    )
+                    .children(self.large_blobs.as_ref().map(|_| {
+                        PFIconButton::new(
+                            Icon::default().path("icons/delete.svg").size_3p5(),
+                            "Clear All",
+                        )
+                        .small()
+                        .disabled(self.loading)
+                        .on_click(clear_listener)
+                    })),
+            )
+            .child(body)
+    }
+
+    fn render_capacity(metadata: CredentialsMetadata, theme: &Theme) -> impl IntoElement {
+        let used = metadata.existing_resident_credentials_count;
+        let remaining = metadata.max_possible_remaining_resident_credentials_count;
+        let capacity = metadata.capacity.max(1);
+        let percent = (used as f32 / capacity as f32 * 100.0).min(100.0);
+        // Warn at 90% of the firmware limit, or when flash leaves room for fewer than 10 more.
+        let nearly_full = used * 10 >= capacity * 9 || remaining < 10;
+
+        v_flex()
+            .gap_2()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .text_sm()
+                    .child(format!("{} of {} passkey slots used", used, capacity))
+                    .child(
                         div()
-                            .grid()
-                            .grid_cols(3)
-                            .gap_4()
-                            .children(cards)
-                            .into_any_element()
-                    }),
+                            .text_color(theme.muted_foreground)
+                            .child(format!("Room for {} more", remaining)),
+                    ),
             )
+            .child(Progress::new().value(percent))
+            .children(nearly_full.then(|| {
+                h_flex()
+                    .gap_2()
+                    .items_center()
+                    .text_sm()
+                    .text_color(rgb(0xfe9a00))
+                    .child(
+                        Icon::default()
+                            .path("icons/triangle-alert.svg")
+                            .size_4()
+                            .text_color(rgb(0xfe9a00)),
+                    )
+                    .child(
+                        "Passkey storage is nearly full. Remove passkeys you no longer use \
+                         before registering new ones.",
+                    )
+            }))
     }
 
     fn render_empty_credentials_with_theme(&self, theme: &Theme) -> impl IntoElement {
@@ -875,6 +2202,30 @@ impl PasskeysView {
         let delete_listener = cx.listener(move |this, _, window, cx| {
             this.open_ask_delete_pin(cred_clone.clone(), window, cx);
         });
+        let cred_clone = cred.clone();
+        let edit_listener = cx.listener(move |this, _, window, cx| {
+            this.open_edit_user_dialog(cred_clone.clone(), window, cx);
+        });
+        let cred_id = cred.credential_id.clone();
+        let expand_listener = cx.listener(move |this, _, _, cx| {
+            this.expanded_credential = if this.expanded_credential.as_ref() == Some(&cred_id) {
+                None
+            } else {
+                Some(cred_id.clone())
+            };
+            cx.notify();
+        });
+        let expanded = self.expanded_credential.as_ref() == Some(&cred.credential_id);
+        let cred_id = cred.credential_id.clone();
+        let select_listener = cx.listener(move |this, checked: &bool, _, cx| {
+            if *checked {
+                this.selected_credentials.insert(cred_id.clone());
+            } else {
+                this.selected_credentials.remove(&cred_id);
+            }
+            cx.notify();
+        });
+        let selected = self.selected_credentials.contains(&cred.credential_id);
 
         let theme = cx.theme();
 
@@ -894,6 +2245,14 @@ impl PasskeysView {
                             .items_center()
                             .flex_1()
                             .min_w_0()
+                            .child(
+                                Checkbox::new(SharedString::from(format!(
+                                    "select-cred-{}",
+                                    cred.credential_id
+                                )))
+                                .checked(selected)
+                                .on_click(select_listener),
+                            )
                             .child(
                                 div()
                                     .size_10()
@@ -939,18 +2298,248 @@ impl PasskeysView {
                             ),
                     )
                     .child(
-                        Button::new("delete-cred-btn")
-                            .ghost()
-                            .small()
+                        h_flex()
                             .child(
-                                Icon::default()
-                                    .path("icons/trash-2.svg")
-                                    .size_4()
-                                    .text_color(theme.muted_foreground),
+                                Button::new("expand-cred-btn")
+                                    .ghost()
+                                    .small()
+                                    .child(
+                                        Icon::default()
+                                            .path(if expanded {
+                                                "icons/chevron-up.svg"
+                                            } else {
+                                                "icons/chevron-down.svg"
+                                            })
+                                            .size_4()
+                                            .text_color(theme.muted_foreground),
+                                    )
+                                    .tooltip("Details")
+                                    .on_click(expand_listener),
                             )
-                            .on_click(delete_listener),
+                            .child(
+                                Button::new("edit-cred-btn")
+                                    .ghost()
+                                    .small()
+                                    .child(
+                                        Icon::default()
+                                            .path("icons/user.svg")
+                                            .size_4()
+                                            .text_color(theme.muted_foreground),
+                                    )
+                                    .tooltip("Edit user")
+                                    .on_click(edit_listener),
+                            )
+                            .child(
+                                Button::new("delete-cred-btn")
+                                    .ghost()
+                                    .small()
+                                    .child(
+                                        Icon::default()
+                                            .path("icons/trash-2.svg")
+                                            .size_4()
+                                            .text_color(theme.muted_foreground),
+                                    )
+                                    .on_click(delete_listener),
+                            ),
                     ),
             )
+            .children(expanded.then(|| Self::render_credential_details(cred, theme)))
+    }
+
+    fn render_credential_details(cred: &StoredCredential, theme: &Theme) -> impl IntoElement {
+        let row = |label: &'static str, value: String, mono: bool| {
+            let value_el = div().text_sm().overflow_hidden().child(value);
+            v_flex()
+                .gap_0p5()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child(label),
+                )
+                .child(if mono {
+                    value_el.font_family("Mono")
+                } else {
+                    value_el
+                })
+        };
+
+        let cred_protect = match cred.cred_protect {
+            Some(1) => "UV optional".to_string(),
+            Some(2) => "UV optional with credential ID list".to_string(),
+            Some(3) => "UV required".to_string(),
+            Some(other) => format!("Unknown ({})", other),
+            None => "Not reported".to_string(),
+        };
+
+        let mut details = v_flex()
+            .gap_3()
+            .mt_4()
+            .pt_4()
+            .border_t_1()
+            .border_color(theme.border);
+
+        details = match &cred.public_key {
+            Some(key) => details
+                .child(row(
+                    "Algorithm",
+                    format!("{} ({})", key.algorithm, key.algorithm_id),
+                    false,
+                ))
+                .child(row(
+                    "Key Type",
+                    match &key.curve {
+                        Some(curve) => format!("{} / {}", key.key_type, curve),
+                        None => key.key_type.clone(),
+                    },
+                    false,
+                ))
+                .child(row("Fingerprint (SHA-256)", key.fingerprint.clone(), true)),
+            None => details.child(row("Public Key", "Not reported".to_string(), false)),
+        };
+
+        details
+            .child(row("Credential Protection", cred_protect, false))
+            .child(row(
+                "Large Blob Key",
+                if cred.has_large_blob_key { "Yes" } else { "No" }.to_string(),
+                false,
+            ))
+            .child(row("Display Name", cred.user_display_name.clone(), false))
+            .child(row("User ID", cred.user_id.clone(), true))
+            .child(row("Credential ID", cred.credential_id.clone(), true))
+    }
+
+    fn open_edit_user_dialog(
+        &mut self,
+        cred: StoredCredential,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(pin) = self.cached_pin.clone() else {
+            window.push_notification("Session expired, please unlock again.", cx);
+            self.lock_storage(cx);
+            return;
+        };
+
+        let name_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("User name")
+                .default_value(cred.user_name.clone())
+        });
+        let display_name_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Display name")
+                .default_value(cred.user_display_name.clone())
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let name = name_input.clone();
+            let display_name = display_name_input.clone();
+            let cred = cred.clone();
+            let pin = pin.clone();
+
+            dialog
+                .title("Edit Passkey User")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(format!(
+                            "Rename the account shown for this {} passkey. The user id stays \
+                             the same, so the site still recognizes it.",
+                            cred.rp_id
+                        ))
+                        .child("User Name")
+                        .child(Input::new(&name_input))
+                        .child("Display Name")
+                        .child(Input::new(&display_name_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let name = name.clone();
+                    let display_name = display_name.clone();
+                    let cred = cred.clone();
+                    let pin = pin.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("save-user").primary().label("Save").on_click(
+                            move |_, _, cx| {
+                                let name_val = name.read(cx).value().trim().to_string();
+                                let display_val = display_name.read(cx).value().trim().to_string();
+                                if name_val.is_empty() {
+                                    let _ = view.update(cx, |_, cx| {
+                                        cx.emit(PasskeysEvent::Notification(
+                                            "User name cannot be empty".to_string(),
+                                        ));
+                                    });
+                                    return;
+                                }
+                                let _ = view.update(cx, |this, cx| {
+                                    this.update_user(
+                                        cred.clone(),
+                                        pin.clone(),
+                                        name_val,
+                                        display_val,
+                                        cx,
+                                    );
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn update_user(
+        &mut self,
+        cred: StoredCredential,
+        pin: String,
+        name: String,
+        display_name: String,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let pin_for_bg = pin.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    io::update_credential_user(
+                        pin_for_bg,
+                        cred.credential_id,
+                        cred.user_id,
+                        name,
+                        display_name,
+                    )
+                })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| match result {
+                Ok(msg) => {
+                    this.refresh_credentials(pin, cx);
+                    cx.emit(PasskeysEvent::CloseDialog);
+                    cx.emit(PasskeysEvent::Notification(msg));
+                }
+                Err(e) => {
+                    this.loading = false;
+                    cx.emit(PasskeysEvent::Notification(format!("Error: {}", e)));
+                    cx.notify();
+                }
+            });
+        }));
     }
 
     fn open_ask_delete_pin(
@@ -1003,7 +2592,11 @@ impl Render for PasskeysView {
         let content = v_flex()
             .gap_6()
             .child(self.render_pin_management(cx))
-            .child(self.render_stored_passkeys(cx));
+            .child(self.render_stored_passkeys(cx))
+            .children(
+                self.unlocked
+                    .then(|| self.render_large_blobs(cx).into_any_element()),
+            );
 
         let theme = cx.theme();
 
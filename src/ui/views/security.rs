@@ -1,17 +1,1561 @@
-use crate::ui::components::page_view::PageView;
+use crate::device::io;
+use crate::device::types::{
+    AuthEncryptionStatus, BackupVerification, BootKeyInfo, DeviceAuthenticity, DeviceKeyPin,
+    Feature, FidoBackup, FieldSource, FullDeviceStatus, SecureBootPreflight, StorageEncryption,
+};
+use crate::ui::components::{button::PFIconButton, card::Card, page_view::PageView};
+use crate::ui::paths;
 use gpui::*;
 use gpui_component::{
-    ActiveTheme, Disableable, Icon, StyledExt,
+    ActiveTheme, Disableable, Icon, StyledExt, Theme, WindowExt,
+    badge::Badge,
     button::{Button, ButtonCustomVariant, ButtonVariants},
     h_flex,
+    input::{Input, InputState},
     switch::Switch,
     v_flex,
 };
 
-pub struct SecurityView;
+pub struct SecurityView {
+    device_status: Option<FullDeviceStatus>,
+    loading: bool,
+    restored_backup: Option<FidoBackup>,
+    verification: Option<BackupVerification>,
+    auth_status: AuthEncryptionStatus,
+    authenticity: Option<DeviceAuthenticity>,
+    secure_boot_preflight: Option<SecureBootPreflight>,
+    boot_key: u8,
+    secure_lock: bool,
+    risk_acknowledged: bool,
+    /// The user confirmed the loaded signing key's hash is burned into `boot_key`.
+    key_hash_confirmed: bool,
+    signing_key: Option<BootKeyInfo>,
+    _task: Option<Task<()>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum AuthEncryptionAction {
+    Enable,
+    Disable,
+}
+
+pub enum SecurityEvent {
+    Notification(String),
+    CloseDialog,
+}
+
+impl EventEmitter<SecurityEvent> for SecurityView {}
 
 impl SecurityView {
-    pub fn build<V: 'static>(cx: &mut Context<V>) -> impl IntoElement {
+    pub fn new(
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+        device_status: Option<FullDeviceStatus>,
+    ) -> Self {
+        let auth_status = io::get_auth_encryption_status(&Self::serial_of(device_status.as_ref()));
+        Self {
+            device_status,
+            loading: false,
+            restored_backup: None,
+            verification: None,
+            auth_status,
+            authenticity: None,
+            secure_boot_preflight: None,
+            boot_key: 0,
+            secure_lock: false,
+            risk_acknowledged: false,
+            key_hash_confirmed: false,
+            signing_key: None,
+            _task: None,
+        }
+    }
+
+    pub fn update_device_status(
+        &mut self,
+        status: Option<FullDeviceStatus>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.device_status == status {
+            return;
+        }
+        self.device_status = status;
+        self.auth_status = io::get_auth_encryption_status(&self.serial());
+        self.authenticity = None;
+        self.secure_boot_preflight = None;
+        self.risk_acknowledged = false;
+        self.key_hash_confirmed = false;
+        cx.notify();
+    }
+
+    fn serial_of(status: Option<&FullDeviceStatus>) -> String {
+        status.map(|s| s.info.serial.clone()).unwrap_or_default()
+    }
+
+    fn serial(&self) -> String {
+        Self::serial_of(self.device_status.as_ref())
+    }
+
+    fn open_backup_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let pin_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("FIDO PIN (optional)")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let input = pin_input.clone();
+
+            dialog
+                .title("Create Seed Backup")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(
+                            "The backup file contains the encrypted seed of this key. Anyone holding \
+                             the file and its recovery words can clone every credential of this key. \
+                             Store it offline.",
+                        )
+                        .child(
+                            "The key registers a check credential so a restore can be verified \
+                             later. Touch it when it blinks, and enter your PIN if it asks for one.",
+                        )
+                        .child(Input::new(&pin_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let input = input.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("backup").primary().label("Choose File").on_click(
+                            move |_, window, cx| {
+                                let pin = input.read(cx).text().to_string();
+                                let pin = if pin.is_empty() { None } else { Some(pin) };
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    this.start_backup(pin, window, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn start_backup(&mut self, pin: Option<String>, window: &mut Window, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let serial = self
+            .device_status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default();
+        let suggested_name = format!("picoforge-backup-{}.json", serial.replace('?', "unknown"));
+        let path_rx = cx.prompt_for_new_path(&paths::default_export_dir(), Some(&suggested_name));
+
+        self.loading = true;
+        cx.notify();
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let path = match path_rx.await {
+                Ok(Ok(Some(path))) => path,
+                _ => {
+                    let _ = this.update(cx, |this, cx| {
+                        this.loading = false;
+                        cx.notify();
+                    });
+                    return;
+                }
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let export = io::create_fido_backup(serial, pin)?;
+                    io::save_fido_backup(path, export.backup.clone())?;
+                    Ok::<_, crate::device::error::PFError>(export)
+                })
+                .await;
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.loading = false;
+                match result {
+                    Ok(export) => {
+                        cx.emit(SecurityEvent::Notification("Backup saved".to_string()));
+                        if !export.mnemonic.is_empty() {
+                            this.open_mnemonic_dialog(export.mnemonic, window, cx);
+                        }
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!("Backup failed: {}", e)));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn open_mnemonic_dialog(
+        &mut self,
+        words: Vec<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        window.open_dialog(cx, move |dialog, _, cx| {
+            let theme = cx.theme();
+            let word_cells = words.iter().enumerate().map(|(i, word)| {
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .w_6()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("{}.", i + 1)),
+                    )
+                    .child(div().font_family("Mono").child(word.clone()))
+            });
+
+            dialog
+                .title("Recovery Words")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(div().text_color(rgb(0xef4444)).child(
+                            "Write these words down now. They are not stored in the backup \
+                                 file and are required to restore it.",
+                        ))
+                        .child(div().grid().grid_cols(3).gap_2().children(word_cells)),
+                )
+                .footer(|_, _, _, _| {
+                    vec![
+                        Button::new("done")
+                            .primary()
+                            .label("I wrote them down")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                    ]
+                })
+        });
+    }
+
+    fn pick_restore_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let path_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select backup file".into()),
+        });
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let path = match path_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::load_fido_backup(path) })
+                .await;
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(backup) => this.open_restore_dialog(backup, window, cx),
+                Err(e) => cx.emit(SecurityEvent::Notification(format!(
+                    "Could not open backup: {}",
+                    e
+                ))),
+            });
+        }));
+    }
+
+    fn open_restore_dialog(
+        &mut self,
+        backup: FidoBackup,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mnemonic_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("Recovery words, separated by spaces")
+        });
+        let confirm_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Type RESTORE to confirm"));
+        let view_handle = cx.entity().downgrade();
+
+        let target_serial = self
+            .device_status
+            .as_ref()
+            .map(|s| s.info.serial.clone())
+            .unwrap_or_default();
+
+        window.open_dialog(cx, move |dialog, _, cx| {
+            let view = view_handle.clone();
+            let mnemonic = mnemonic_input.clone();
+            let confirm = confirm_input.clone();
+            let backup_for_footer = backup.clone();
+            let theme = cx.theme();
+
+            let same_device = !target_serial.is_empty() && target_serial == backup.serial;
+
+            dialog
+                .title("Restore Seed Backup")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(
+                            div()
+                                .grid()
+                                .grid_cols(2)
+                                .gap_2()
+                                .text_sm()
+                                .child(div().text_color(theme.muted_foreground).child("Serial"))
+                                .child(div().font_family("Mono").child(backup.serial.clone()))
+                                .child(div().text_color(theme.muted_foreground).child("Firmware"))
+                                .child(format!("v{}", backup.firmware_version))
+                                .child(div().text_color(theme.muted_foreground).child("AAGUID"))
+                                .child(div().font_family("Mono").child(backup.aaguid.clone()))
+                                .child(div().text_color(theme.muted_foreground).child("Created"))
+                                .child(backup.created_at.clone()),
+                        )
+                        .child(div().text_color(rgb(0xef4444)).child(
+                            "Restoring replaces the seed of the connected key. Every credential \
+                             created with its current seed stops working. This cannot be undone.",
+                        ))
+                        .children(same_device.then(|| {
+                            div()
+                                .text_color(rgb(0xfe9a00))
+                                .child("This backup was taken from the connected key itself.")
+                        }))
+                        .child(Input::new(&mnemonic_input))
+                        .child(Input::new(&confirm_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let mnemonic = mnemonic.clone();
+                    let confirm = confirm.clone();
+                    let backup = backup_for_footer.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("restore").danger().label("Restore").on_click(
+                            move |_, window, cx| {
+                                if confirm.read(cx).value().trim() != "RESTORE" {
+                                    let _ = view.update(cx, |_, cx| {
+                                        cx.emit(SecurityEvent::Notification(
+                                            "Type RESTORE to confirm".to_string(),
+                                        ));
+                                    });
+                                    return;
+                                }
+                                let words = mnemonic.read(cx).text().to_string();
+                                let words = if words.trim().is_empty() {
+                                    None
+                                } else {
+                                    Some(words)
+                                };
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    this.start_restore(backup.clone(), words, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn start_restore(
+        &mut self,
+        backup: FidoBackup,
+        mnemonic: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        self.verification = None;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let backup_for_bg = backup.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::restore_fido_backup(backup_for_bg, mnemonic) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(msg) => {
+                        this.restored_backup = Some(backup);
+                        cx.emit(SecurityEvent::Notification(msg));
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!(
+                            "Restore failed: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn open_verify_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let pin_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("FIDO PIN (optional)")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let input = pin_input.clone();
+
+            dialog
+                .title("Verify Restore")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(
+                            "Re-plug the key and touch it when it blinks. It has to sign with the \
+                             check credential recorded in the backup under the same public key.",
+                        )
+                        .child(Input::new(&pin_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let input = input.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("verify").primary().label("Verify").on_click(
+                            move |_, _, cx| {
+                                let pin = input.read(cx).text().to_string();
+                                let pin = if pin.is_empty() { None } else { Some(pin) };
+                                let _ = view.update(cx, |this, cx| {
+                                    this.verify_restore(pin, cx);
+                                });
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn verify_restore(&mut self, pin: Option<String>, cx: &mut Context<Self>) {
+        let Some(backup) = self.restored_backup.clone() else {
+            return;
+        };
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::verify_fido_restore(backup, pin) })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(verification) => {
+                        cx.emit(SecurityEvent::CloseDialog);
+                        cx.emit(SecurityEvent::Notification(
+                            if verification.seed_restored {
+                                "Seed restore verified"
+                            } else {
+                                "Seed restore could not be verified"
+                            }
+                            .to_string(),
+                        ));
+                        this.verification = Some(verification);
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!(
+                            "Verification failed: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn open_auth_encryption_dialog(
+        &mut self,
+        action: AuthEncryptionAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let pin_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Enter FIDO PIN")
+                .masked(true)
+        });
+        let view_handle = cx.entity().downgrade();
+
+        let (title, description, ok_label) = match action {
+            AuthEncryptionAction::Enable => (
+                "Enable Storage Encryption",
+                "The device key will be encrypted with a secret stored on this computer. After \
+                 every power-up the key refuses to work until it is unlocked from PicoForge on \
+                 this computer. If the secret is lost, the key must be reset.",
+                "Enable",
+            ),
+            AuthEncryptionAction::Disable => (
+                "Disable Storage Encryption",
+                "The device key will be stored unencrypted again and the unlock secret is \
+                 removed from this computer.",
+                "Disable",
+            ),
+        };
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let input = pin_input.clone();
+
+            dialog
+                .title(title)
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(description)
+                        .child(Input::new(&pin_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let input = input.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("confirm").primary().label(ok_label).on_click(
+                            move |_, _, cx| {
+                                let pin = input.read(cx).text().to_string();
+                                if !pin.is_empty() {
+                                    let _ = view.update(cx, |this, cx| {
+                                        this.run_auth_encryption(Some(action), Some(pin), cx);
+                                    });
+                                }
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    /// Runs enable/disable when an action is given, otherwise unlocks the device.
+    fn run_auth_encryption(
+        &mut self,
+        action: Option<AuthEncryptionAction>,
+        pin: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+        let serial = self.serial();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let serial_for_bg = serial.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    match (action, pin) {
+                        (Some(AuthEncryptionAction::Enable), Some(pin)) => {
+                            io::enable_auth_encryption(serial_for_bg, pin)
+                        }
+                        (Some(AuthEncryptionAction::Disable), Some(pin)) => {
+                            io::disable_auth_encryption(serial_for_bg, pin)
+                        }
+                        _ => io::unlock_device(serial_for_bg),
+                    }
+                })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                this.auth_status = io::get_auth_encryption_status(&serial);
+                match result {
+                    Ok(msg) => {
+                        if action.is_some() {
+                            cx.emit(SecurityEvent::CloseDialog);
+                        }
+                        cx.emit(SecurityEvent::Notification(msg));
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!("Error: {}", e)));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn verify_authenticity(&mut self, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        self.authenticity = None;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::verify_device_authenticity() })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(authenticity) => {
+                        cx.emit(SecurityEvent::Notification(if authenticity.authentic() {
+                            "Device key verified".to_string()
+                        } else {
+                            "Device key verification failed".to_string()
+                        }));
+                        this.authenticity = Some(authenticity);
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!(
+                            "Could not verify device: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn pick_device_certificate(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let path_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select device certificate".into()),
+        });
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let path = match path_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = true;
+                cx.notify();
+            });
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::upload_device_certificate(path) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                cx.emit(SecurityEvent::Notification(match result {
+                    Ok(msg) => msg,
+                    Err(e) => format!("Certificate upload failed: {}", e),
+                }));
+                cx.notify();
+            });
+        }));
+    }
+
+    fn render_auth_encryption_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let enable_listener = cx.listener(|this, _, window, cx| {
+            this.open_auth_encryption_dialog(AuthEncryptionAction::Enable, window, cx);
+        });
+        let disable_listener = cx.listener(|this, _, window, cx| {
+            this.open_auth_encryption_dialog(AuthEncryptionAction::Disable, window, cx);
+        });
+        let unlock_listener = cx.listener(|this, _, _, cx| {
+            this.run_auth_encryption(None, None, cx);
+        });
+
+        let theme = cx.theme();
+        let status = &self.auth_status;
+        let unsupported =
+            io::feature_support(self.device_status.as_ref(), Feature::FidoAuthEncryption).err();
+        let connected = self.device_status.is_some() && unsupported.is_none();
+
+        let (badge, badge_color) = match status.state {
+            StorageEncryption::Encrypted => ("Protected", theme.primary),
+            StorageEncryption::Plain => ("Not protected", theme.secondary),
+            StorageEncryption::Unknown => ("Unknown", theme.secondary),
+        };
+        let description = match (status.state, status.has_secret) {
+            (StorageEncryption::Plain, _) => "The device key is stored unencrypted in flash. \
+                 Anyone with physical access to the flash can read it."
+                .to_string(),
+            (_, true) => format!(
+                "The unlock secret for this key is stored at {}. Unlock the key after plugging \
+                 it in.",
+                status.secret_path
+            ),
+            (StorageEncryption::Encrypted, false) => "The device key is encrypted, but this \
+                 computer does not hold its unlock secret."
+                .to_string(),
+            (StorageEncryption::Unknown, false) => "The firmware does not report whether its \
+                 storage is encrypted, and this computer holds no unlock secret for this key."
+                .to_string(),
+        };
+
+        Card::new()
+            .title("Storage Encryption")
+            .icon(Icon::default().path("icons/shield.svg"))
+            .description("Keep the device key encrypted until this computer unlocks it")
+            .header_right(Badge::new().child(badge).color(badge_color))
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(description),
+                    )
+                    .children(unsupported.map(Self::unsupported_note))
+                    .child(if status.has_secret {
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/lock-open.svg"),
+                                    "Unlock Device",
+                                )
+                                .disabled(!connected || self.loading)
+                                .loading(self.loading)
+                                .on_click(unlock_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/circle-x.svg"),
+                                    "Disable",
+                                )
+                                .disabled(!connected || self.loading)
+                                .on_click(disable_listener),
+                            )
+                    } else {
+                        h_flex().gap_2().child(
+                            PFIconButton::new(Icon::default().path("icons/lock.svg"), "Enable")
+                                .disabled(
+                                    !connected
+                                        || self.loading
+                                        || status.state == StorageEncryption::Encrypted,
+                                )
+                                .loading(self.loading)
+                                .on_click(enable_listener),
+                        )
+                    }),
+            )
+    }
+
+    fn render_backup_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let backup_listener = cx.listener(|this, _, window, cx| {
+            this.open_backup_dialog(window, cx);
+        });
+        let restore_listener = cx.listener(|this, _, window, cx| {
+            this.pick_restore_file(window, cx);
+        });
+        let verify_listener = cx.listener(|this, _, window, cx| {
+            this.open_verify_dialog(window, cx);
+        });
+
+        let theme = cx.theme();
+        let unsupported =
+            io::feature_support(self.device_status.as_ref(), Feature::FidoBackup).err();
+        let connected = self.device_status.is_some() && unsupported.is_none();
+
+        Card::new()
+            .title("Seed Backup")
+            .icon(Icon::default().path("icons/hard-drive.svg"))
+            .description("Export the encrypted FIDO seed or restore it onto a fresh key")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(
+                                "Passkeys stored on the key itself are not part of the seed. Keep \
+                                 the backup file and its recovery words in separate, offline places.",
+                            ),
+                    )
+                    .children(unsupported.map(Self::unsupported_note))
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/save.svg"),
+                                    "Create Backup",
+                                )
+                                .disabled(!connected || self.loading)
+                                .loading(self.loading)
+                                .on_click(backup_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/folder-open.svg"),
+                                    "Restore Backup",
+                                )
+                                .disabled(!connected || self.loading)
+                                .on_click(restore_listener),
+                            )
+                            .children(self.restored_backup.as_ref().map(|_| {
+                                PFIconButton::new(
+                                    Icon::default().path("icons/circle-check.svg"),
+                                    "Verify Restore",
+                                )
+                                .disabled(self.loading)
+                                .on_click(verify_listener)
+                            })),
+                    )
+                    .children(
+                        self.verification
+                            .as_ref()
+                            .map(|v| Self::render_verification(v, theme)),
+                    ),
+            )
+    }
+
+    fn unsupported_note(reason: String) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .items_center()
+            .text_sm()
+            .text_color(rgb(0xfe9a00))
+            .child(Icon::default().path("icons/triangle-alert.svg"))
+            .child(reason)
+    }
+
+    fn render_verification(verification: &BackupVerification, theme: &Theme) -> impl IntoElement {
+        let color = if verification.seed_restored {
+            theme.foreground
+        } else {
+            gpui::red()
+        };
+
+        v_flex()
+            .gap_1()
+            .p_4()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(div().font_medium().child("Restore Verification"))
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(color)
+                    .child(verification.detail.clone()),
+            )
+    }
+
+    fn render_authenticity_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let verify_listener = cx.listener(|this, _, _, cx| {
+            this.verify_authenticity(cx);
+        });
+        let upload_listener = cx.listener(|this, _, window, cx| {
+            this.pick_device_certificate(window, cx);
+        });
+
+        let theme = cx.theme();
+        // The device key is only reachable through the rescue applet.
+        let rescue = self
+            .device_status
+            .as_ref()
+            .is_some_and(|s| s.provenance.serial == FieldSource::Rescue);
+        let unsupported =
+            io::feature_support(self.device_status.as_ref(), Feature::RescueDeviceKey).err();
+        let available = rescue && unsupported.is_none();
+
+        Card::new()
+            .title("Device Authenticity")
+            .icon(Icon::default().path("icons/shield-check.svg"))
+            .description("Prove the key holds the device key it held before")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(if rescue {
+                                "The device signs a random challenge with its device key. The \
+                                 key is remembered per serial number, so a different key on a \
+                                 known serial is reported."
+                            } else {
+                                "The device key is only reachable through the rescue interface, \
+                                 which is not available for this device."
+                            }),
+                    )
+                    .children(unsupported.map(Self::unsupported_note))
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/shield-check.svg"),
+                                    "Verify device authenticity",
+                                )
+                                .disabled(!available || self.loading)
+                                .loading(self.loading)
+                                .on_click(verify_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/folder-open.svg"),
+                                    "Upload Certificate",
+                                )
+                                .disabled(!available || self.loading)
+                                .on_click(upload_listener),
+                            ),
+                    )
+                    .children(
+                        self.authenticity
+                            .as_ref()
+                            .map(|a| Self::render_authenticity(a, theme)),
+                    ),
+            )
+    }
+
+    fn render_authenticity(authenticity: &DeviceAuthenticity, theme: &Theme) -> impl IntoElement {
+        let row = |label: String, color: Hsla| div().text_sm().text_color(color).child(label);
+        let result = |ok: bool, label: String| {
+            h_flex()
+                .gap_2()
+                .items_center()
+                .child(
+                    Icon::default()
+                        .path(if ok {
+                            "icons/circle-check.svg"
+                        } else {
+                            "icons/circle-x.svg"
+                        })
+                        .text_color(if ok { theme.primary } else { gpui::red() }),
+                )
+                .child(div().text_sm().child(label))
+        };
+
+        let pin = match &authenticity.pin {
+            DeviceKeyPin::FirstSeen => result(
+                true,
+                "First check of this serial, the key is now remembered".to_string(),
+            ),
+            DeviceKeyPin::Matches => result(true, "Same key as previous checks".to_string()),
+            DeviceKeyPin::Changed {
+                previous_fingerprint,
+            } => result(
+                false,
+                format!("Different key than before (was {})", previous_fingerprint),
+            ),
+            DeviceKeyPin::NoSerial => result(
+                false,
+                "The device did not report its serial, so the key cannot be remembered or \
+                 compared with earlier checks"
+                    .to_string(),
+            ),
+        };
+
+        v_flex()
+            .gap_2()
+            .p_4()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(div().font_medium().child("Device Key"))
+            .child(row(
+                format!(
+                    "Serial: {}",
+                    authenticity.serial.as_deref().unwrap_or("Unknown")
+                ),
+                theme.foreground,
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .font_family("Mono")
+                    .text_color(theme.muted_foreground)
+                    .child(authenticity.fingerprint.clone()),
+            )
+            .child(result(
+                authenticity.signature_valid,
+                if authenticity.signature_valid {
+                    "Challenge signature is valid".to_string()
+                } else {
+                    "Challenge signature does not match the public key".to_string()
+                },
+            ))
+            .child(pin)
+    }
+
+    fn run_secure_boot_preflight(&mut self, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        self.loading = true;
+        self.risk_acknowledged = false;
+        self.key_hash_confirmed = false;
+        cx.notify();
+
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::secure_boot_preflight() })
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok(preflight) => {
+                        if let Some(key) = preflight.state.boot_key {
+                            this.boot_key = key;
+                        }
+                        this.secure_boot_preflight = Some(preflight);
+                    }
+                    Err(e) => {
+                        this.secure_boot_preflight = None;
+                        cx.emit(SecurityEvent::Notification(format!(
+                            "Could not check the device: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    /// First confirmation step: restates what is about to be written.
+    fn open_secure_boot_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(preflight) = self.secure_boot_preflight.clone() else {
+            return;
+        };
+        let Some(signing_key) = self.signing_key.clone().filter(|_| self.key_hash_confirmed) else {
+            return;
+        };
+        let boot_key = self.boot_key;
+        let lock = self.secure_lock;
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let key_line = v_flex()
+                .gap_1()
+                .child(format!(
+                    "You confirmed boot key {} holds the hash of {}:",
+                    boot_key, signing_key.path
+                ))
+                .child(
+                    div()
+                        .text_xs()
+                        .font_family("Mono")
+                        .child(signing_key.otp_hash.clone()),
+                )
+                .child(div().text_color(rgb(0xfe9a00)).child(
+                    "PicoForge cannot read the boot key slots. If the slot holds a different \
+                     hash, the device will not boot any firmware you sign.",
+                ));
+
+            dialog
+                .title("Enable Secure Boot")
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .pb_4()
+                        .child(format!(
+                            "Device {} ({}, firmware {}) will only boot firmware signed with \
+                             boot key {}.",
+                            preflight.serial, preflight.chip, preflight.firmware_version, boot_key
+                        ))
+                        .child(key_line)
+                        .child(if lock {
+                            "Secure lock will also be set. The boot configuration and the debug \
+                             ports are locked for good."
+                        } else {
+                            "Secure lock stays off, so the debug ports remain usable."
+                        })
+                        .child(div().text_color(rgb(0xef4444)).child(
+                            "OTP fuses cannot be cleared. Flashing unsigned firmware afterwards \
+                             bricks the device.",
+                        )),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("continue")
+                            .primary()
+                            .label("Continue")
+                            .on_click(move |_, window, cx| {
+                                window.close_dialog(cx);
+                                let _ = view.update(cx, |this, cx| {
+                                    this.open_secure_boot_confirm_dialog(window, cx);
+                                });
+                            }),
+                    ]
+                })
+        });
+    }
+
+    /// Second confirmation step: the serial has to be typed back.
+    fn open_secure_boot_confirm_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(serial) = self
+            .secure_boot_preflight
+            .as_ref()
+            .map(|p| p.serial.clone())
+        else {
+            return;
+        };
+        let serial_input = cx.new(|cx| InputState::new(window, cx).placeholder(serial.clone()));
+        let view_handle = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _, _| {
+            let view = view_handle.clone();
+            let input = serial_input.clone();
+            let serial = serial.clone();
+
+            dialog
+                .title("Confirm Serial Number")
+                .child(
+                    v_flex()
+                        .gap_4()
+                        .pb_4()
+                        .child(format!(
+                            "Type the serial number {} to confirm this is the device to lock.",
+                            serial
+                        ))
+                        .child(Input::new(&serial_input)),
+                )
+                .footer(move |_, _, _, _| {
+                    let view = view.clone();
+                    let input = input.clone();
+                    let serial = serial.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .label("Cancel")
+                            .on_click(|_, window, cx| window.close_dialog(cx)),
+                        Button::new("confirm").primary().label("Enable").on_click(
+                            move |_, _, cx| {
+                                let typed = input.read(cx).value().trim().to_string();
+                                if typed.eq_ignore_ascii_case(&serial) {
+                                    let _ = view.update(cx, |this, cx| {
+                                        this.enable_secure_boot(typed, cx);
+                                    });
+                                } else {
+                                    let _ = view.update(cx, |_, cx| {
+                                        cx.emit(SecurityEvent::Notification(
+                                            "The serial number does not match".into(),
+                                        ));
+                                    });
+                                }
+                            },
+                        ),
+                    ]
+                })
+        });
+    }
+
+    fn enable_secure_boot(&mut self, confirm_serial: String, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let Some(key_hash) = self
+            .signing_key
+            .as_ref()
+            .filter(|_| self.key_hash_confirmed)
+            .map(|k| k.otp_hash.clone())
+        else {
+            return;
+        };
+        self.loading = true;
+        cx.notify();
+
+        let boot_key = self.boot_key;
+        let lock = self.secure_lock;
+        let entity = cx.entity().downgrade();
+
+        self._task = Some(cx.spawn(async move |_, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(
+                    async move { io::enable_secure_boot(boot_key, lock, confirm_serial, key_hash) },
+                )
+                .await;
+
+            let _ = entity.update(cx, |this, cx| {
+                this.loading = false;
+                this.risk_acknowledged = false;
+                this.key_hash_confirmed = false;
+                match result {
+                    Ok(state) => {
+                        cx.emit(SecurityEvent::CloseDialog);
+                        cx.emit(SecurityEvent::Notification(if state.locked {
+                            "Secure boot enabled and locked".to_string()
+                        } else {
+                            "Secure boot enabled".to_string()
+                        }));
+                        if let Some(status) = this.device_status.as_mut() {
+                            status.secure_boot = state.enabled;
+                            status.secure_lock = state.locked;
+                        }
+                        if let Some(preflight) = this.secure_boot_preflight.as_mut() {
+                            preflight.state = state;
+                        }
+                    }
+                    Err(e) => {
+                        cx.emit(SecurityEvent::Notification(format!(
+                            "Secure boot failed: {}",
+                            e
+                        )));
+                    }
+                }
+                cx.notify();
+            });
+        }));
+    }
+
+    fn generate_signing_key(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let path_rx = cx.prompt_for_new_path(&paths::default_export_dir(), Some("boot_key.pem"));
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(path))) = path_rx.await else {
+                return;
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::generate_boot_key(path) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| this.set_signing_key(result, cx));
+        }));
+    }
+
+    fn pick_signing_key(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.loading {
+            return;
+        }
+        let path_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select boot signing key".into()),
+        });
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let path = match path_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::load_boot_key(path) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| this.set_signing_key(result, cx));
+        }));
+    }
+
+    fn set_signing_key(
+        &mut self,
+        result: Result<BootKeyInfo, crate::device::error::PFError>,
+        cx: &mut Context<Self>,
+    ) {
+        match result {
+            Ok(key) => {
+                cx.emit(SecurityEvent::Notification(format!(
+                    "Loaded boot key {}",
+                    key.path
+                )));
+                self.signing_key = Some(key);
+                self.key_hash_confirmed = false;
+            }
+            Err(e) => {
+                cx.emit(SecurityEvent::Notification(format!(
+                    "Boot key error: {}",
+                    e
+                )));
+            }
+        }
+        cx.notify();
+    }
+
+    /// Asks for the UF2 to sign, then where to save the sealed copy.
+    fn seal_firmware(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(key_path) = self
+            .signing_key
+            .as_ref()
+            .map(|k| std::path::PathBuf::from(&k.path))
+        else {
+            return;
+        };
+        if self.loading {
+            return;
+        }
+        let input_rx = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select firmware UF2".into()),
+        });
+
+        self._task = Some(cx.spawn_in(window, async move |this, cx| {
+            let input = match input_rx.await {
+                Ok(Ok(Some(mut paths))) if !paths.is_empty() => paths.remove(0),
+                _ => return,
+            };
+            let suggested_name = format!(
+                "{}_signed.uf2",
+                input
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "firmware".into())
+            );
+            let dir = input
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_else(paths::default_export_dir);
+            let Ok(output_rx) = this.update(cx, |_, cx| {
+                cx.prompt_for_new_path(&dir, Some(&suggested_name))
+            }) else {
+                return;
+            };
+            let Ok(Ok(Some(output))) = output_rx.await else {
+                return;
+            };
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = true;
+                cx.notify();
+            });
+            let result = cx
+                .background_executor()
+                .spawn(async move { io::seal_firmware(key_path, input, output) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                cx.emit(SecurityEvent::Notification(match result {
+                    Ok(sealed) => format!(
+                        "Signed firmware ({} KB) saved to {}. Check it with picotool info -a \
+                         and on an unlocked device before relying on it.",
+                        sealed.image_size / 1024,
+                        sealed.output
+                    ),
+                    Err(e) => format!("Signing failed: {}", e),
+                }));
+                cx.notify();
+            });
+        }));
+    }
+
+    fn unload_signing_key(&mut self, cx: &mut Context<Self>) {
+        self.signing_key = None;
+        self.key_hash_confirmed = false;
+        cx.notify();
+    }
+
+    fn render_signing_key_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let generate_listener = cx.listener(|this, _, window, cx| {
+            this.generate_signing_key(window, cx);
+        });
+        let load_listener = cx.listener(|this, _, window, cx| {
+            this.pick_signing_key(window, cx);
+        });
+        let seal_listener = cx.listener(|this, _, window, cx| {
+            this.seal_firmware(window, cx);
+        });
+        let unload_listener = cx.listener(|this, _, _, cx| {
+            this.unload_signing_key(cx);
+        });
+
+        let theme = cx.theme();
+        let has_key = self.signing_key.is_some();
+
+        Card::new()
+            .title("Boot Signing Key")
+            .icon(Icon::default().path("icons/key-round.svg"))
+            .description("Sign firmware so it boots on a device with secure boot enabled")
+            .child(
+                v_flex()
+                    .gap_4()
+                    .child(match &self.signing_key {
+                        Some(key) => v_flex()
+                            .gap_1()
+                            .child(div().text_sm().child(key.path.clone()))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child("OTP boot key hash"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .font_family("Mono")
+                                    .child(key.otp_hash.clone()),
+                            ),
+                        None => v_flex().child(
+                            div().text_sm().text_color(theme.muted_foreground).child(
+                                "Generate a secp256k1 key or load an existing one. Keep it \
+                                     offline: anyone holding it can sign firmware your locked \
+                                     devices will run.",
+                            ),
+                        ),
+                    })
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/key.svg"),
+                                    "Generate Key",
+                                )
+                                .disabled(self.loading)
+                                .on_click(generate_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/folder-open.svg"),
+                                    "Load Key",
+                                )
+                                .disabled(self.loading)
+                                .on_click(load_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/save.svg"),
+                                    "Sign Firmware",
+                                )
+                                .disabled(!has_key || self.loading)
+                                .loading(self.loading)
+                                .on_click(seal_listener),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/close.svg"),
+                                    "Unload Key",
+                                )
+                                .disabled(!has_key || self.loading)
+                                .on_click(unload_listener),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_preflight(preflight: &SecureBootPreflight, theme: &Theme) -> impl IntoElement {
+        let row = |label: String, color: Hsla| div().text_sm().text_color(color).child(label);
+        let state = match (preflight.state.enabled, preflight.state.locked) {
+            (_, true) => "enabled and locked".to_string(),
+            (true, false) => match preflight.state.boot_key {
+                Some(key) => format!("enabled with boot key {}", key),
+                None => "enabled".to_string(),
+            },
+            (false, false) => "disabled".to_string(),
+        };
+
+        v_flex()
+            .gap_1()
+            .p_4()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_lg()
+            .child(div().font_medium().child("Pre-flight Checks"))
+            .child(row(
+                format!(
+                    "{} \u{2022} firmware {} \u{2022} serial {}",
+                    preflight.chip, preflight.firmware_version, preflight.serial
+                ),
+                theme.foreground,
+            ))
+            .child(row(
+                format!("Secure boot is {}", state),
+                theme.muted_foreground,
+            ))
+            .children(
+                preflight
+                    .blockers
+                    .iter()
+                    .map(|b| row(b.clone(), gpui::red())),
+            )
+            .children(
+                preflight
+                    .ready()
+                    .then(|| row("All checks passed".to_string(), theme.primary)),
+            )
+    }
+
+    fn render_secure_boot_card(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let check_listener = cx.listener(|this, _, _, cx| {
+            this.run_secure_boot_preflight(cx);
+        });
+        let lock_listener = cx.listener(|this, checked: &bool, _, cx| {
+            this.secure_lock = *checked;
+            this.risk_acknowledged = false;
+            cx.notify();
+        });
+        let acknowledge_listener = cx.listener(|this, checked: &bool, _, cx| {
+            this.risk_acknowledged = *checked;
+            cx.notify();
+        });
+        let hash_listener = cx.listener(|this, checked: &bool, _, cx| {
+            this.key_hash_confirmed = *checked;
+            cx.notify();
+        });
+        let enable_listener = cx.listener(|this, _, window, cx| {
+            this.open_secure_boot_dialog(window, cx);
+        });
+
         let (fg, muted_fg, border) = {
             let theme = cx.theme();
             (theme.foreground, theme.muted_foreground, theme.border)
@@ -23,7 +1567,43 @@ impl SecurityView {
         let destructive_border = rgba(0xef44444d);
         let destructive_bg_muted = rgba(0xef44441a);
 
-        let content = v_flex()
+        let rescue = self
+            .device_status
+            .as_ref()
+            .is_some_and(|s| s.provenance.secure_boot == FieldSource::Rescue);
+        let unsupported =
+            io::feature_support(self.device_status.as_ref(), Feature::RescueSecureBoot).err();
+        let ready = self
+            .secure_boot_preflight
+            .as_ref()
+            .is_some_and(|p| p.ready());
+        let key_fixed = self
+            .secure_boot_preflight
+            .as_ref()
+            .is_some_and(|p| p.state.enabled);
+        let can_enable =
+            ready && self.risk_acknowledged && self.key_hash_confirmed && !self.loading;
+
+        let key_buttons = (0..io::BOOT_KEY_SLOTS)
+            .map(|key| {
+                let button = Button::new(("boot-key", key as usize))
+                    .label(format!("Key {}", key))
+                    .disabled(!ready || key_fixed || self.loading)
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.boot_key = key;
+                        this.risk_acknowledged = false;
+                        this.key_hash_confirmed = false;
+                        cx.notify();
+                    }));
+                if key == self.boot_key {
+                    button.primary()
+                } else {
+                    button.ghost()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        v_flex()
             .gap_6()
             .w_full()
             .child(
@@ -48,15 +1628,13 @@ impl SecurityView {
                                 div()
                                     .font_bold()
                                     .text_color(destructive_red)
-                                    .child("Feature Unstable"),
+                                    .child("Irreversible"),
                             ),
                     )
-                    .child(
-                        div()
-                            .text_sm()
-                            .text_color(destructive_red)
-                            .child("This feature is currently under work and disabled for safety."),
-                    ),
+                    .child(div().text_sm().text_color(destructive_red).child(
+                        "Secure boot burns the boot key into OTP. The device will only run \
+                         firmware signed with that key from then on.",
+                    )),
             )
             .child(
                 v_flex()
@@ -67,13 +1645,27 @@ impl SecurityView {
                     .rounded_xl()
                     .overflow_hidden()
                     .child(
-                        div().p_6().child(
-                            div()
-                                .text_lg()
-                                .font_bold()
-                                .text_color(fg)
-                                .child("Lock Settings"),
-                        ),
+                        h_flex()
+                            .p_6()
+                            .justify_between()
+                            .items_center()
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_bold()
+                                    .text_color(fg)
+                                    .child("Lock Settings"),
+                            )
+                            .child(
+                                PFIconButton::new(
+                                    Icon::default().path("icons/search.svg"),
+                                    "Check Device",
+                                )
+                                .small()
+                                .disabled(!rescue || unsupported.is_some() || self.loading)
+                                .loading(self.loading)
+                                .on_click(check_listener),
+                            ),
                     )
                     // Card Content
                     .child(
@@ -81,6 +1673,37 @@ impl SecurityView {
                             .px_6()
                             .pb_6()
                             .gap_6()
+                            .child(match &self.secure_boot_preflight {
+                                Some(preflight) => {
+                                    Self::render_preflight(preflight, cx.theme()).into_any_element()
+                                }
+                                None => div()
+                                    .text_sm()
+                                    .text_color(muted_fg)
+                                    .child(if rescue {
+                                        "Check the device to read its chip, firmware and current \
+                                         secure boot state."
+                                    } else {
+                                        "Secure boot is configured through the rescue interface, \
+                                         which is not available for this device."
+                                    })
+                                    .into_any_element(),
+                            })
+                            .children(unsupported.map(Self::unsupported_note))
+                            .child(
+                                h_flex()
+                                    .justify_between()
+                                    .items_center()
+                                    .child(
+                                        v_flex()
+                                            .gap_1()
+                                            .child(div().text_sm().font_medium().child("Boot Key"))
+                                            .child(div().text_xs().text_color(muted_fg).child(
+                                                "OTP slot holding the hash of your signing key",
+                                            )),
+                                    )
+                                    .child(h_flex().gap_1().children(key_buttons)),
+                            )
                             .child(
                                 h_flex()
                                     .justify_between()
@@ -89,42 +1712,59 @@ impl SecurityView {
                                         v_flex()
                                             .gap_1()
                                             .child(
-                                                div()
-                                                    .text_sm()
-                                                    .font_medium()
-                                                    .child("Enable Secure Boot"),
+                                                div().text_sm().font_medium().child("Secure Lock"),
                                             )
-                                            .child(
-                                                div().text_xs().text_color(muted_fg).child(
-                                                    "Verifies firmware signature on startup",
-                                                ),
-                                            ),
+                                            .child(div().text_xs().text_color(muted_fg).child(
+                                                "Prevents reading key material via debug ports",
+                                            )),
                                     )
                                     .child(
-                                        Switch::new("secure-boot-switch")
-                                            .checked(false)
-                                            .disabled(true),
+                                        Switch::new("secure-lock-switch")
+                                            .checked(self.secure_lock)
+                                            .disabled(!ready || self.loading)
+                                            .on_click(lock_listener),
                                     ),
                             )
                             .child(
-                                // Secure Lock Row (Disabled)
                                 h_flex()
                                     .justify_between()
                                     .items_center()
+                                    .gap_4()
                                     .child(
                                         v_flex()
                                             .gap_1()
                                             .child(
-                                                div().text_sm().font_medium().child("Secure Lock"),
+                                                div().text_sm().font_medium().child(format!(
+                                                    "Boot key {} hash",
+                                                    self.boot_key
+                                                )),
                                             )
+                                            .child(match &self.signing_key {
+                                                Some(key) => div()
+                                                    .text_xs()
+                                                    .font_family("Mono")
+                                                    .text_color(fg)
+                                                    .child(key.otp_hash.clone()),
+                                                None => div().text_xs().text_color(muted_fg).child(
+                                                    "Load your boot signing key to confirm the \
+                                                     hash in this slot",
+                                                ),
+                                            })
                                             .child(div().text_xs().text_color(muted_fg).child(
-                                                "Prevents reading key material via debug ports",
+                                                "PicoForge cannot read the slot. Confirm you \
+                                                 burned this hash into it and verified it with \
+                                                 picotool otp get.",
                                             )),
                                     )
                                     .child(
-                                        Switch::new("secure-lock-switch")
-                                            .checked(false)
-                                            .disabled(true),
+                                        Switch::new("key-hash-switch")
+                                            .checked(self.key_hash_confirmed)
+                                            .disabled(
+                                                !ready
+                                                    || self.signing_key.is_none()
+                                                    || self.loading,
+                                            )
+                                            .on_click(hash_listener),
                                     ),
                             )
                             .child(div().h_px().bg(border))
@@ -138,7 +1778,10 @@ impl SecurityView {
                                     .border_1()
                                     .border_color(destructive_border)
                                     .child(
-                                        Switch::new("confirm-switch").checked(false).disabled(true),
+                                        Switch::new("confirm-switch")
+                                            .checked(self.risk_acknowledged)
+                                            .disabled(!ready || self.loading)
+                                            .on_click(acknowledge_listener),
                                     )
                                     .child(
                                         div()
@@ -166,21 +1809,39 @@ impl SecurityView {
                                             .hover(destructive_red_hover.into())
                                             .active(destructive_red_active.into()),
                                     )
-                                    .disabled(true)
+                                    .disabled(!can_enable)
+                                    .on_click(enable_listener)
                                     .child(
                                         h_flex()
                                             .gap_2()
                                             .items_center()
                                             .child(Icon::default().path("icons/lock.svg").size_4())
-                                            .child("Permanently Lock Device"),
+                                            .child(if self.secure_lock {
+                                                "Permanently Lock Device"
+                                            } else {
+                                                "Enable Secure Boot"
+                                            }),
                                     ),
                             ),
                     ),
-            );
+            )
+    }
+}
+
+impl Render for SecurityView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = v_flex()
+            .gap_6()
+            .w_full()
+            .child(self.render_auth_encryption_card(cx))
+            .child(self.render_backup_card(cx))
+            .child(self.render_authenticity_card(cx))
+            .child(self.render_signing_key_card(cx))
+            .child(self.render_secure_boot_card(cx));
 
         PageView::build(
-            "Secure Boot",
-            "Permanently lock this device to the current firmware vendor.",
+            "Security",
+            "Protect key storage, back up the device seed and lock the device to the current firmware vendor.",
             content,
             cx.theme(),
         )